@@ -3,13 +3,17 @@ use wasm_bindgen_futures::spawn_local;
 use log::{info, error};
 use web_sys::{console, Event};
 use std::rc::Rc;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
-use crate::models::{Release, Client, User, WsMessage, Environment, ReleaseStatus};
-use crate::services::api::ApiClient;
+use crate::models::{Release, Client, DeploymentItemType, FreezeWindow, InventoryRow, RecurringRelease, ReleaseTrain, TrainActionResult, TrainDetails, User, UserRole, WsMessage, Environment, ReleaseStatus, LogStream};
+use crate::services::api::{ApiClient, ApiError, CreateRecurringRequest, CreateTrainRequest};
 use crate::services::websocket::{WebSocketService, WsAction};
 use crate::components::kanban::KanbanBoard;
 use crate::components::header::Header;
+use crate::components::freeze_calendar::FreezeCalendar;
+use crate::components::train_panel::{TrainList, TrainPanel};
+use crate::components::recurring_panel::RecurringPanel;
+use crate::components::inventory_matrix::InventoryMatrix;
 use crate::components::chat::ChatPanel;
 use crate::components::release_form::ReleaseForm;
 use crate::components::log_drawer::{LogDrawer, LogEntry};
@@ -18,21 +22,43 @@ pub enum AppMsg {
     FetchReleases,
     ReleasesReceived(Vec<Release>),
     ClientsReceived(Vec<Client>),
+    DeploymentItemTypesReceived(Vec<DeploymentItemType>),
+    FreezesReceived(Vec<FreezeWindow>),
+    TrainsReceived(Vec<ReleaseTrain>),
     CurrentUserReceived(User),
     ReleaseUpdated(Release),
     DeleteRelease(String),
     ReleaseDeleted(String),
     MoveRelease(String, Environment),
     ClearRelease(String),
+    ClearBlockedByFreeze(String, String), // release_id, freeze message
+    OverrideFreeze,
     OpenReleaseForm,
     CloseReleaseForm,
     CreateRelease(Release),
     ReleaseCreated(Release),
+    CreateTrain(CreateTrainRequest),
+    TrainCreated(TrainDetails),
+    OpenTrain(String),
+    CloseTrain,
+    TrainAction(String, &'static str, serde_json::Value), // train_id, action, request body
+    TrainActionDone(String, Option<TrainDetails>), // summary, train after the action
+    RecurringReceived(Vec<RecurringRelease>),
+    ToggleRecurringPanel,
+    CreateRecurring(CreateRecurringRequest),
+    RecurringSaved(RecurringRelease),
+    SetRecurringEnabled(String, bool), // recurring_id, enabled
+    DeleteRecurring(String),
+    RecurringDeleted(String),
+    ToggleInventory,
+    FetchInventory,
+    InventoryReceived(Vec<InventoryRow>),
     ConnectWebSocket,
     WebSocketAction(WsAction),
     SendChatMessage(String),
     ToggleChatPanel,
     OpenLogDrawer(String),
+    StoredLogsReceived(String, Vec<LogEntry>), // release_id, stored history
     CloseLogDrawer,
     Error(String),
     Info(String),
@@ -42,12 +68,30 @@ pub enum AppMsg {
     AutoDismissInfo,
     ToggleAppLog,
     RerunDeploymentItem(String, String), // release_id, item_name
-    DeploymentItemRerun(Release),                                         
+    DeploymentItemRerun(Release),
+    CancelRelease(String),
+    RollbackRelease(String),
+    DryRunRelease(String),
+    AnswerApproval(String, bool, Option<String>), // release_id, approved, comment
+    CancelDryRun(String),
+    CancelDeploymentItem(String, String), // release_id, item_name
+    ConfirmDeploymentItem(String, String, bool), // release_id, item_name, approved
+    CompleteManualStep(String, String, bool, Option<String>), // release_id, item_name, done, note
 }
 
 pub struct App {
     releases: Vec<Release>,
     clients: Vec<Client>,
+    deployment_item_types: Vec<DeploymentItemType>,
+    freezes: Vec<FreezeWindow>,
+    trains: Vec<ReleaseTrain>,
+    active_train_id: Option<String>, // Train whose page is open
+    train_results: Vec<TrainActionResult>, // Outcome of the latest bulk action on the open train
+    recurring: Vec<RecurringRelease>,
+    show_recurring_panel: bool,
+    inventory: Vec<InventoryRow>,
+    show_inventory: bool,
+    freeze_blocked_release: Option<String>, // Release an Admin can clear by overriding a freeze
     current_user: Option<User>,
     ws_service: Option<WebSocketService>,
     chat_messages: Vec<WsMessage>,
@@ -72,6 +116,16 @@ impl Component for App {
         let app = Self {
             releases: Vec::new(),
             clients: Vec::new(),
+            deployment_item_types: Vec::new(),
+            freezes: Vec::new(),
+            trains: Vec::new(),
+            active_train_id: None,
+            train_results: Vec::new(),
+            recurring: Vec::new(),
+            show_recurring_panel: false,
+            inventory: Vec::new(),
+            show_inventory: false,
+            freeze_blocked_release: None,
             current_user: None,
             ws_service: None,
             chat_messages: Vec::new(),
@@ -116,6 +170,100 @@ impl Component for App {
                 }
                 true
             }
+            AppMsg::CancelRelease(release_id) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::cancel_release(&release_id).await {
+                        Ok(updated) => link.send_message(AppMsg::ReleaseUpdated(updated)),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to cancel release: {}", e))),
+                    }
+                });
+
+                false
+            }
+            AppMsg::RollbackRelease(release_id) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::rollback_release(&release_id).await {
+                        Ok(updated) => {
+                            link.send_message(AppMsg::ReleaseUpdated(updated));
+                            link.send_message(AppMsg::Info("Rollback started".to_string()));
+                        }
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to roll back release: {}", e))),
+                    }
+                });
+
+                false
+            }
+            AppMsg::DryRunRelease(release_id) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::dry_run_release(&release_id).await {
+                        Ok(updated) => {
+                            link.send_message(AppMsg::ReleaseUpdated(updated));
+                            link.send_message(AppMsg::Info("Dry run started".to_string()));
+                        }
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to start dry run: {}", e))),
+                    }
+                });
+
+                false
+            }
+            AppMsg::CancelDryRun(release_id) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::cancel_dry_run(&release_id).await {
+                        Ok(_) => link.send_message(AppMsg::Info("Dry run cancellation requested".to_string())),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to cancel dry run: {}", e))),
+                    }
+                });
+
+                false
+            }
+            AppMsg::AnswerApproval(release_id, approved, comment) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::answer_approval(&release_id, approved, comment).await {
+                        Ok(updated) => link.send_message(AppMsg::ReleaseUpdated(updated)),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to answer approval request: {}", e))),
+                    }
+                });
+
+                false
+            }
+            AppMsg::ConfirmDeploymentItem(release_id, item_name, approved) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::confirm_deployment_item(&release_id, &item_name, approved).await {
+                        Ok(updated) => link.send_message(AppMsg::ReleaseUpdated(updated)),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to answer {}: {}", item_name, e))),
+                    }
+                });
+
+                false
+            }
+            AppMsg::CompleteManualStep(release_id, item_name, done, note) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::complete_manual_step(&release_id, &item_name, done, note).await {
+                        Ok(updated) => link.send_message(AppMsg::ReleaseUpdated(updated)),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to complete {}: {}", item_name, e))),
+                    }
+                });
+
+                false
+            }
+            AppMsg::CancelDeploymentItem(release_id, item_name) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::cancel_deployment_item(&release_id, &item_name).await {
+                        Ok(_) => link.send_message(AppMsg::Info(format!("Cancellation requested for {}", item_name))),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to cancel deployment item: {}", e))),
+                    }
+                });
+
+                false
+            }
             AppMsg::DismissError => {
                 self.error_dismissing = true;
 
@@ -130,6 +278,7 @@ impl Component for App {
             }
             AppMsg::AutoDismissError => {
                 self.error = None;
+                self.freeze_blocked_release = None;
                 self.error_dismissing = false;
                 true
             }
@@ -198,6 +347,30 @@ impl Component for App {
                         Err(e) => link.send_message(AppMsg::Error(format!("Failed to fetch clients: {}", e))),
                     }
                     
+                    // And the deployment item types for the release form
+                    match ApiClient::get_deployment_item_types().await {
+                        Ok(item_types) => link.send_message(AppMsg::DeploymentItemTypesReceived(item_types)),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to fetch deployment item types: {}", e))),
+                    }
+                    
+                    // And the freeze windows
+                    match ApiClient::get_freezes().await {
+                        Ok(freezes) => link.send_message(AppMsg::FreezesReceived(freezes)),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to fetch freeze windows: {}", e))),
+                    }
+                    
+                    // And the release trains
+                    match ApiClient::get_trains().await {
+                        Ok(trains) => link.send_message(AppMsg::TrainsReceived(trains)),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to fetch release trains: {}", e))),
+                    }
+                    
+                    // And the recurring releases
+                    match ApiClient::get_recurring_releases().await {
+                        Ok(recurring) => link.send_message(AppMsg::RecurringReceived(recurring)),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to fetch recurring releases: {}", e))),
+                    }
+                    
                     // And current user
                     match ApiClient::get_current_user().await {
                         Ok(user) => link.send_message(AppMsg::CurrentUserReceived(user)),
@@ -218,6 +391,18 @@ impl Component for App {
                 self.clients = clients;
                 true
             }
+            AppMsg::DeploymentItemTypesReceived(item_types) => {
+                self.deployment_item_types = item_types;
+                true
+            }
+            AppMsg::FreezesReceived(freezes) => {
+                self.freezes = freezes;
+                true
+            }
+            AppMsg::TrainsReceived(trains) => {
+                self.trains = trains;
+                true
+            }
             AppMsg::CurrentUserReceived(user) => {
                 self.current_user = Some(user);
                 true
@@ -276,14 +461,38 @@ impl Component for App {
                 // Instead of updating the entire release, just call the status update endpoint directly
                 let link = ctx.link().clone();
                 spawn_local(async move {
-                    match ApiClient::update_release_status(&release_id, "clear").await {
+                    match ApiClient::update_release_status(&release_id, "clear", false).await {
                         Ok(updated) => link.send_message(AppMsg::ReleaseUpdated(updated)),
+                        Err(ApiError::Frozen(message)) => link.send_message(AppMsg::ClearBlockedByFreeze(release_id, message)),
                         Err(e) => link.send_message(AppMsg::Error(format!("Failed to clear release: {}", e))),
                     }
                 });
 
                 false
             }
+            AppMsg::ClearBlockedByFreeze(release_id, message) => {
+                // Admins get the option to clear the release anyway
+                let is_admin = self.current_user.as_ref().is_some_and(|user| user.role == UserRole::Admin);
+                self.freeze_blocked_release = if is_admin { Some(release_id) } else { None };
+                ctx.link().send_message(AppMsg::Error(format!("Failed to clear release: {}", message)));
+                false
+            }
+            AppMsg::OverrideFreeze => {
+                if let Some(release_id) = self.freeze_blocked_release.take() {
+                    let link = ctx.link().clone();
+                    spawn_local(async move {
+                        match ApiClient::update_release_status(&release_id, "clear", true).await {
+                            Ok(updated) => {
+                                link.send_message(AppMsg::ReleaseUpdated(updated));
+                                link.send_message(AppMsg::Info("Freeze overridden, release cleared".to_string()));
+                            }
+                            Err(e) => link.send_message(AppMsg::Error(format!("Failed to override freeze: {}", e))),
+                        }
+                    });
+                }
+                
+                true
+            }
             AppMsg::OpenReleaseForm => {
                 self.show_release_form = true;
                 true
@@ -322,6 +531,155 @@ impl Component for App {
                 self.show_release_form = false;
                 true
             }
+            AppMsg::CreateTrain(request) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::create_train(&request).await {
+                        Ok(details) => link.send_message(AppMsg::TrainCreated(details)),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to create release train: {}", e))),
+                    }
+                });
+                
+                false
+            }
+            AppMsg::TrainCreated(details) => {
+                // Every client's release goes on the board, and the train's page opens
+                self.show_release_form = false;
+                self.active_train_id = Some(details.train.id.clone());
+                self.train_results = Vec::new();
+                self.trains.retain(|train| train.id != details.train.id);
+                self.trains.push(details.train);
+                self.releases.extend(details.releases);
+                true
+            }
+            AppMsg::OpenTrain(train_id) => {
+                if self.active_train_id.as_ref() != Some(&train_id) {
+                    self.train_results = Vec::new();
+                }
+                self.active_train_id = Some(train_id.clone());
+                
+                // The board may not know every release of the train yet
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::get_train(&train_id).await {
+                        Ok(details) => link.send_message(AppMsg::TrainActionDone(String::new(), Some(details))),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to fetch release train: {}", e))),
+                    }
+                });
+                true
+            }
+            AppMsg::CloseTrain => {
+                self.active_train_id = None;
+                self.train_results = Vec::new();
+                true
+            }
+            AppMsg::TrainAction(train_id, action, body) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::train_action(&train_id, action, body).await {
+                        Ok((message, details)) => link.send_message(AppMsg::TrainActionDone(message, details)),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to {} release train: {}", action, e))),
+                    }
+                });
+                
+                false
+            }
+            AppMsg::TrainActionDone(message, details) => {
+                if let Some(details) = details {
+                    for release in details.releases {
+                        match self.releases.iter_mut().find(|r| r.id == release.id) {
+                            Some(existing) => *existing = release,
+                            None => self.releases.push(release),
+                        }
+                    }
+                    match self.trains.iter_mut().find(|train| train.id == details.train.id) {
+                        Some(existing) => *existing = details.train,
+                        None => self.trains.push(details.train),
+                    }
+                    self.train_results = details.results;
+                }
+                if !message.is_empty() {
+                    ctx.link().send_message(AppMsg::Info(message));
+                }
+                true
+            }
+            AppMsg::RecurringReceived(recurring) => {
+                self.recurring = recurring;
+                true
+            }
+            AppMsg::ToggleRecurringPanel => {
+                self.show_recurring_panel = !self.show_recurring_panel;
+                true
+            }
+            AppMsg::CreateRecurring(request) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::create_recurring_release(&request).await {
+                        Ok(recurring) => {
+                            link.send_message(AppMsg::RecurringSaved(recurring));
+                            link.send_message(AppMsg::Info("Recurring release created".to_string()));
+                        }
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to create recurring release: {}", e))),
+                    }
+                });
+                
+                false
+            }
+            AppMsg::RecurringSaved(recurring) => {
+                match self.recurring.iter_mut().find(|r| r.id == recurring.id) {
+                    Some(existing) => *existing = recurring,
+                    None => self.recurring.push(recurring),
+                }
+                true
+            }
+            AppMsg::SetRecurringEnabled(recurring_id, enabled) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::set_recurring_enabled(&recurring_id, enabled).await {
+                        Ok(recurring) => link.send_message(AppMsg::RecurringSaved(recurring)),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to update recurring release: {}", e))),
+                    }
+                });
+                
+                false
+            }
+            AppMsg::DeleteRecurring(recurring_id) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::delete_recurring_release(&recurring_id).await {
+                        Ok(_) => link.send_message(AppMsg::RecurringDeleted(recurring_id)),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to delete recurring release: {}", e))),
+                    }
+                });
+                
+                false
+            }
+            AppMsg::RecurringDeleted(recurring_id) => {
+                self.recurring.retain(|r| r.id != recurring_id);
+                true
+            }
+            AppMsg::ToggleInventory => {
+                self.show_inventory = !self.show_inventory;
+                if self.show_inventory {
+                    ctx.link().send_message(AppMsg::FetchInventory);
+                }
+                true
+            }
+            AppMsg::FetchInventory => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match ApiClient::get_inventory_matrix().await {
+                        Ok(rows) => link.send_message(AppMsg::InventoryReceived(rows)),
+                        Err(e) => link.send_message(AppMsg::Error(format!("Failed to fetch the inventory: {}", e))),
+                    }
+                });
+                
+                false
+            }
+            AppMsg::InventoryReceived(rows) => {
+                self.inventory = rows;
+                true
+            }
             AppMsg::ConnectWebSocket => {
                 // Only initialize WebSocket if we don't already have one
                 if self.ws_service.is_none() {
@@ -376,8 +734,13 @@ impl Component for App {
                                             "DeployingToProduction" => Some(ReleaseStatus::DeployingToProduction),
                                             "ReadyToTestInProduction" => Some(ReleaseStatus::ReadyToTestInProduction),
                                             "ClearedInProduction" => Some(ReleaseStatus::ClearedInProduction),
+                                            "AwaitingApproval" => Some(ReleaseStatus::AwaitingApproval),
                                             "Error" => Some(ReleaseStatus::Error),
                                             "Blocked" => Some(ReleaseStatus::Blocked),
+                                            "Cancelled" => Some(ReleaseStatus::Cancelled),
+                                            "Skipped" => Some(ReleaseStatus::Skipped),
+                                            "RollingBack" => Some(ReleaseStatus::RollingBack),
+                                            "RolledBack" => Some(ReleaseStatus::RolledBack),
                                             _ => None,
                                         };
                                         
@@ -455,8 +818,6 @@ impl Component for App {
                                         
                                         // Find the deployment item with this name and update its status
                                         if let Some(item) = release.deployment_items.iter_mut().find(|i| i.name == item_name) {
-                                            item.logs.push(log.clone());
-                                            
                                             // Set the item status to completed based on the current environment
                                             match release.current_environment {
                                                 Environment::Development => item.status = ReleaseStatus::DeployingToStaging,
@@ -477,6 +838,9 @@ impl Component for App {
                                         // Determine if this is an error message
                                         let is_error = log_line.contains("ERROR") || log_line.contains("FAILED") || status == "Error";
                                         
+                                        // Dry run output is tagged by the scheduler and never marks the item as failed
+                                        let dry_run = log_line.contains("[dry run]");
+                                        
                                         // Extract the item name from the log line if possible
                                         let item_name = if let Some(start_idx) = log_line.find('[') {
                                             if let Some(end_idx) = log_line.find(']') {
@@ -507,10 +871,8 @@ impl Component for App {
                                         // Find the deployment item with this name
                                         if let Some(item) = release.deployment_items.iter_mut()
                                             .find(|i| i.name == item_name) {
-                                            item.logs.push(log_line.clone());
-                                            
                                             // Set error if this is an error message
-                                            if is_error && item.error.is_none() {
+                                            if is_error && !dry_run && item.error.is_none() {
                                                 item.error = Some(log_line.clone());
                                             }
                                         }
@@ -536,6 +898,87 @@ impl Component for App {
                                     ctx.link().send_message(AppMsg::Error(message.clone()));
                                 }
                             }
+                            WsMessage::ItemUpdate { release_id, item_name, status, attempt, max_attempts, error, progress, started_at, finished_at, duration_ms, exit_code } => {
+                                // Keep the item's status, attempt counter and run details in sync with the scheduler
+                                if let Some(item) = self.releases.iter_mut()
+                                    .find(|r| r.id == *release_id)
+                                    .and_then(|r| r.deployment_items.iter_mut().find(|i| i.name == *item_name))
+                                {
+                                    item.status = status.clone();
+                                    item.attempt = *attempt;
+                                    item.max_attempts = *max_attempts;
+                                    item.error = error.clone();
+                                    item.progress = *progress;
+                                    item.started_at = *started_at;
+                                    item.finished_at = *finished_at;
+                                    item.duration_ms = *duration_ms;
+                                    item.exit_code = *exit_code;
+                                }
+                            }
+                            WsMessage::ItemEvent { release_id, item_name, event } => {
+                                // Steps, outputs and confirmation requests reported by the script
+                                if let Some(item) = self.releases.iter_mut()
+                                    .find(|r| r.id == *release_id)
+                                    .and_then(|r| r.deployment_items.iter_mut().find(|i| i.name == *item_name))
+                                {
+                                    item.record_event(event.clone());
+                                }
+                            }
+                            WsMessage::DryRunUpdate { release_id, dry_run } => {
+                                // Replace the dry run, or add it when it just started
+                                if let Some(release) = self.releases.iter_mut().find(|r| r.id == *release_id) {
+                                    match release.dry_runs.iter_mut().find(|d| d.id == dry_run.id) {
+                                        Some(existing) => *existing = dry_run.clone(),
+                                        None => release.dry_runs.push(dry_run.clone()),
+                                    }
+                                }
+                            }
+                            WsMessage::ApprovalUpdate { release_id, status, approval_request, approvals } => {
+                                // Approvals given by other users and the status they led to
+                                if let Some(release) = self.releases.iter_mut().find(|r| r.id == *release_id) {
+                                    release.status = status.clone();
+                                    release.approval_request = approval_request.clone();
+                                    release.approvals = approvals.clone();
+                                }
+                            }
+                            WsMessage::TrainUpdate { train } => {
+                                // Failures of a train, or the train being stopped
+                                match self.trains.iter_mut().find(|t| t.id == train.id) {
+                                    Some(existing) => *existing = (**train).clone(),
+                                    None => self.trains.push((**train).clone()),
+                                }
+                            }
+                            WsMessage::RecurringUpdate { recurring } => {
+                                // A release created for an occurrence isn't on the board yet
+                                let created = recurring.occurrences.last()
+                                    .and_then(|occurrence| occurrence.release_id.as_ref())
+                                    .is_some_and(|release_id| !self.releases.iter().any(|r| r.id == *release_id));
+                                if created {
+                                    ctx.link().send_message(AppMsg::FetchReleases);
+                                }
+                                match self.recurring.iter_mut().find(|r| r.id == recurring.id) {
+                                    Some(existing) => *existing = (**recurring).clone(),
+                                    None => self.recurring.push((**recurring).clone()),
+                                }
+                            }
+                            WsMessage::InventoryUpdate { .. } => {
+                                // Drift is worked out by the server, so the open matrix is fetched again
+                                if self.show_inventory {
+                                    ctx.link().send_message(AppMsg::FetchInventory);
+                                }
+                            }
+                            WsMessage::HealthCheckUpdate { release_id, health_checks } => {
+                                // Health checks running after a deployment
+                                if let Some(release) = self.releases.iter_mut().find(|r| r.id == *release_id) {
+                                    release.health_checks = health_checks.clone();
+                                }
+                            }
+                            WsMessage::QueueUpdate { release_id, position } => {
+                                // Show where a waiting release is in the deployment queue
+                                if let Some(release) = self.releases.iter_mut().find(|r| r.id == *release_id) {
+                                    release.queue_position = *position;
+                                }
+                            }
                         }
                         return true;
                     }
@@ -595,6 +1038,48 @@ impl Component for App {
                 true
             }
             AppMsg::OpenLogDrawer(release_id) => {
+                // Load the stored history so earlier runs show up, not just live lines
+                if let Some(release) = self.releases.iter().find(|r| r.id == release_id) {
+                    let item_names: Vec<String> = release.deployment_items.iter()
+                        .map(|item| item.name.clone())
+                        .collect();
+                    let release_id = release_id.clone();
+                    let link = ctx.link().clone();
+                    
+                    spawn_local(async move {
+                        let mut entries = Vec::new();
+                        
+                        for item_name in item_names {
+                            match ApiClient::get_all_item_logs(&release_id, &item_name).await {
+                                Ok(lines) => entries.extend(lines.into_iter().map(|line| {
+                                    let is_error = line.stream == LogStream::Stderr;
+                                    let content = if is_error {
+                                        format!("[{}] [stderr] {}", line.item_name, line.line)
+                                    } else {
+                                        format!("[{}] {}", line.item_name, line.line)
+                                    };
+                                    
+                                    // Keep dry run output apart from real deployments
+                                    let run = if line.dry_run { "dry run" } else { "run" };
+                                    
+                                    LogEntry {
+                                        release_id: release_id.clone(),
+                                        item_name: line.item_name,
+                                        content: format!("({} {}) {}", run, line.run, content),
+                                        timestamp: line.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+                                        is_error,
+                                    }
+                                })),
+                                Err(e) => {
+                                    link.send_message(AppMsg::Error(format!("Failed to load logs for {}: {}", item_name, e)));
+                                }
+                            }
+                        }
+                        
+                        link.send_message(AppMsg::StoredLogsReceived(release_id, entries));
+                    });
+                }
+                
                 self.show_log_drawer = true;
                 self.active_release_id = release_id;
                 if self.show_app_log {
@@ -602,6 +1087,12 @@ impl Component for App {
                 }
                 true
             }
+            AppMsg::StoredLogsReceived(release_id, entries) => {
+                // Stored history replaces the live lines already collected for this release
+                self.logs.retain(|log| log.release_id != release_id);
+                self.logs.extend(entries);
+                true
+            }
             AppMsg::CloseLogDrawer => {
                 self.show_log_drawer = false;
                 true
@@ -626,9 +1117,19 @@ impl Component for App {
                     on_new_release={ctx.link().callback(|_| AppMsg::OpenReleaseForm)}
                     on_toggle_chat={ctx.link().callback(|_| AppMsg::ToggleChatPanel)}
                     on_toggle_log={ctx.link().callback(|_| AppMsg::ToggleAppLog)}
+                    on_toggle_recurring={ctx.link().callback(|_| AppMsg::ToggleRecurringPanel)}
+                    on_toggle_inventory={ctx.link().callback(|_| AppMsg::ToggleInventory)}
                     is_connected={is_connected}
                 />
 
+                <FreezeCalendar freezes={self.freezes.clone()} />
+                
+                <TrainList
+                    trains={self.trains.clone()}
+                    releases={self.releases.clone()}
+                    on_open={ctx.link().callback(AppMsg::OpenTrain)}
+                />
+
                 <main class="main-content">
                     <KanbanBoard 
                         releases={self.releases.clone()}
@@ -637,6 +1138,16 @@ impl Component for App {
                         on_clear_release={ctx.link().callback(AppMsg::ClearRelease)}
                         on_delete_release={ctx.link().callback(AppMsg::DeleteRelease)}
                         on_view_logs={ctx.link().callback(AppMsg::OpenLogDrawer)}
+                        on_rerun_item={ctx.link().callback(|(id, item_name)| AppMsg::RerunDeploymentItem(id, item_name))}
+                        on_cancel_release={ctx.link().callback(AppMsg::CancelRelease)}
+                        on_rollback_release={ctx.link().callback(AppMsg::RollbackRelease)}
+                        on_dry_run_release={ctx.link().callback(AppMsg::DryRunRelease)}
+                        on_cancel_dry_run={ctx.link().callback(AppMsg::CancelDryRun)}
+                        on_answer_approval={ctx.link().callback(|(id, approved, comment)| AppMsg::AnswerApproval(id, approved, comment))}
+                        on_cancel_item={ctx.link().callback(|(id, item_name)| AppMsg::CancelDeploymentItem(id, item_name))}
+                        on_confirm_item={ctx.link().callback(|(id, item_name, approved)| AppMsg::ConfirmDeploymentItem(id, item_name, approved))}
+                        on_complete_manual_step={ctx.link().callback(|(id, item_name, done, note)| AppMsg::CompleteManualStep(id, item_name, done, note))}
+                        on_open_train={ctx.link().callback(AppMsg::OpenTrain)}
                     />
                     
                     {
@@ -689,6 +1200,20 @@ impl Component for App {
                         html! {
                             <div class={error_class}>
                                 <p>{ error }</p>
+                                {
+                                    if self.freeze_blocked_release.is_some() {
+                                        html! {
+                                            <button
+                                                class="override-freeze-btn"
+                                                onclick={ctx.link().callback(|_| AppMsg::OverrideFreeze)}
+                                            >
+                                                { "Override freeze" }
+                                            </button>
+                                        }
+                                    } else {
+                                        html! {}
+                                    }
+                                }
                                 <button
                                     onclick={ctx.link().callback(|_| AppMsg::DismissError)}
                                 >
@@ -738,6 +1263,81 @@ impl Component for App {
                     }
                 }
                 
+                // Release train page
+                {
+                    match self.active_train_id.as_ref().and_then(|id| self.trains.iter().find(|train| train.id == *id)) {
+                        Some(train) => {
+                            let releases: Vec<Release> = self.releases.iter()
+                                .filter(|release| release.train_id.as_ref() == Some(&train.id))
+                                .cloned()
+                                .collect();
+                            html! {
+                                <div class="modal-overlay">
+                                    <div class="modal-container">
+                                        <TrainPanel
+                                            train={train.clone()}
+                                            releases={releases}
+                                            clients={self.clients.clone()}
+                                            results={self.train_results.clone()}
+                                            current_user={self.current_user.clone()}
+                                            on_clear={ctx.link().callback(|id| AppMsg::TrainAction(id, "clear", serde_json::json!({})))}
+                                            on_reschedule={ctx.link().callback(|(id, scheduled_at): (String, DateTime<Utc>)| {
+                                                AppMsg::TrainAction(id, "reschedule", serde_json::json!({ "scheduled_at": scheduled_at }))
+                                            })}
+                                            on_cancel={ctx.link().callback(|id| AppMsg::TrainAction(id, "cancel", serde_json::json!({})))}
+                                            on_resume={ctx.link().callback(|id| AppMsg::TrainAction(id, "resume", serde_json::json!({})))}
+                                            on_close={ctx.link().callback(|_| AppMsg::CloseTrain)}
+                                        />
+                                    </div>
+                                </div>
+                            }
+                        }
+                        None => html! {},
+                    }
+                }
+                
+                // Recurring releases
+                {
+                    if self.show_recurring_panel {
+                        html! {
+                            <div class="modal-overlay">
+                                <div class="modal-container">
+                                    <RecurringPanel
+                                        recurring={self.recurring.clone()}
+                                        clients={self.clients.clone()}
+                                        deployment_item_types={self.deployment_item_types.clone()}
+                                        current_user={self.current_user.clone()}
+                                        on_create={ctx.link().callback(AppMsg::CreateRecurring)}
+                                        on_set_enabled={ctx.link().callback(|(id, enabled)| AppMsg::SetRecurringEnabled(id, enabled))}
+                                        on_delete={ctx.link().callback(AppMsg::DeleteRecurring)}
+                                        on_close={ctx.link().callback(|_| AppMsg::ToggleRecurringPanel)}
+                                    />
+                                </div>
+                            </div>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                
+                // Environment inventory
+                {
+                    if self.show_inventory {
+                        html! {
+                            <div class="modal-overlay">
+                                <div class="modal-container">
+                                    <InventoryMatrix
+                                        rows={self.inventory.clone()}
+                                        on_close={ctx.link().callback(|_| AppMsg::ToggleInventory)}
+                                    />
+                                </div>
+                            </div>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                
                 // Release form modal
                 {
                     if self.show_release_form {
@@ -746,7 +1346,9 @@ impl Component for App {
                                 <div class="modal-container">
                                     <ReleaseForm 
                                         clients={self.clients.clone()}
+                                        deployment_item_types={self.deployment_item_types.clone()}
                                         on_submit={ctx.link().callback(AppMsg::CreateRelease)}
+                                        on_submit_train={ctx.link().callback(AppMsg::CreateTrain)}
                                         on_cancel={ctx.link().callback(|_| AppMsg::CloseReleaseForm)}
                                         on_create={ctx.link().callback(|s| AppMsg::Info(s))}
                                     />
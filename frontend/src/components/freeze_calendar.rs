@@ -0,0 +1,50 @@
+use yew::prelude::*;
+use chrono::{Local, Utc};
+use crate::models::FreezeWindow;
+
+#[derive(Properties, PartialEq)]
+pub struct FreezeCalendarProps {
+    pub freezes: Vec<FreezeWindow>,
+}
+
+// Current and upcoming freeze windows, shown above the board
+#[function_component(FreezeCalendar)]
+pub fn freeze_calendar(props: &FreezeCalendarProps) -> Html {
+    let now = Utc::now();
+    let freezes: Vec<&FreezeWindow> = props.freezes.iter()
+        .filter(|freeze| freeze.ends_at > now)
+        .collect();
+    
+    if freezes.is_empty() {
+        return html! {};
+    }
+    
+    html! {
+        <div class="freeze-calendar">
+            <h3>{ "Deployment freezes" }</h3>
+            <ul>
+                {
+                    freezes.iter().map(|freeze| {
+                        let active = freeze.starts_at <= now;
+                        let scope = match &freeze.client_id {
+                            Some(client_id) => format!("{:?} ({})", freeze.environment, client_id),
+                            None => format!("{:?}", freeze.environment),
+                        };
+                        
+                        html! {
+                            <li class={classes!("freeze-window", active.then_some("freeze-active"))}>
+                                <span class="freeze-scope">{ scope }</span>
+                                <span class="freeze-period">
+                                    { format!("{} - {}",
+                                        freeze.starts_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                                        freeze.ends_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")) }
+                                </span>
+                                <span class="freeze-reason">{ format!("{} ({})", freeze.reason, freeze.owner) }</span>
+                            </li>
+                        }
+                    }).collect::<Html>()
+                }
+            </ul>
+        </div>
+    }
+}
@@ -7,6 +7,8 @@ pub struct HeaderProps {
     pub on_new_release: Callback<()>,
     pub on_toggle_chat: Callback<()>,
     pub on_toggle_log: Callback<()>,
+    pub on_toggle_recurring: Callback<()>,
+    pub on_toggle_inventory: Callback<()>,
     pub is_connected: bool,
 }
 
@@ -33,6 +35,20 @@ pub fn header(props: &HeaderProps) -> Html {
         })
     };
     
+    let on_toggle_recurring = {
+        let callback = props.on_toggle_recurring.clone();
+        Callback::from(move |_| {
+            callback.emit(());
+        })
+    };
+    
+    let on_toggle_inventory = {
+        let callback = props.on_toggle_inventory.clone();
+        Callback::from(move |_| {
+            callback.emit(());
+        })
+    };
+    
     // Get role display name and CSS class
     let get_role_display = |role: &UserRole| -> &'static str {
         match role {
@@ -100,6 +116,20 @@ pub fn header(props: &HeaderProps) -> Html {
                 >
                     { "Blend Log" }
                 </button>
+                
+                <button 
+                    class="toggle-recurring-btn"
+                    onclick={on_toggle_recurring}
+                >
+                    { "Recurring" }
+                </button>
+                
+                <button 
+                    class="toggle-inventory-btn"
+                    onclick={on_toggle_inventory}
+                >
+                    { "Inventory" }
+                </button>
             </div>
             
             <div class="user-info">
@@ -0,0 +1,122 @@
+use yew::prelude::*;
+use chrono::Local;
+use crate::models::{InventoryEntry, InventoryRow};
+
+fn inventory_cell(entry: &Option<InventoryEntry>) -> Html {
+    let entry = match entry {
+        Some(entry) => entry,
+        None => return html! { <td class="inventory-empty">{ "-" }</td> },
+    };
+
+    let version = match (&entry.version, entry.outputs.is_empty()) {
+        (Some(version), _) => version.clone(),
+        (None, false) => entry.outputs.iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(", "),
+        (None, true) => "unknown version".to_string(),
+    };
+    let source = match (&entry.release_title, &entry.reported_by) {
+        (Some(title), _) => format!("by release {}", title),
+        (None, Some(user)) => format!("reported by {}", user),
+        (None, None) => String::new(),
+    };
+
+    html! {
+        <td class={classes!("inventory-cell", entry.rolled_back_at.is_some().then_some("inventory-rolled-back"))}>
+            <span class="inventory-version">{ version }</span>
+            <span class="inventory-since">
+                { format!("since {}", entry.since.with_timezone(&Local).format("%Y-%m-%d %H:%M")) }
+            </span>
+            <span class="inventory-source">{ source }</span>
+            {
+                match entry.rolled_back_at {
+                    Some(rolled_back_at) => html! {
+                        <span class="inventory-rollback">
+                            { format!("rolled back {}", rolled_back_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")) }
+                        </span>
+                    },
+                    None => html! {},
+                }
+            }
+        </td>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct InventoryMatrixProps {
+    pub rows: Vec<InventoryRow>,
+    pub on_close: Callback<()>,
+}
+
+// What every client runs in Development, Staging and Production, drifted items highlighted
+#[function_component(InventoryMatrix)]
+pub fn inventory_matrix(props: &InventoryMatrixProps) -> Html {
+    let drifted = props.rows.iter().filter(|row| row.drifted).count();
+
+    let on_close = {
+        let callback = props.on_close.clone();
+        Callback::from(move |_: MouseEvent| callback.emit(()))
+    };
+
+    html! {
+        <div class="inventory-panel">
+            <div class="inventory-panel-header">
+                <h2>{ "Environment inventory" }</h2>
+                <button class="close-btn" onclick={on_close}>{ "Close" }</button>
+            </div>
+
+            {
+                if props.rows.is_empty() {
+                    html! { <p class="inventory-empty">{ "Nothing deployed yet" }</p> }
+                } else {
+                    html! {
+                        <>
+                            <p class="inventory-summary">
+                                { format!("{} items, {} drifted", props.rows.len(), drifted) }
+                            </p>
+                            <table class="inventory-matrix">
+                                <thead>
+                                    <tr>
+                                        <th>{ "Client" }</th>
+                                        <th>{ "Item" }</th>
+                                        <th>{ "Development" }</th>
+                                        <th>{ "Staging" }</th>
+                                        <th>{ "Production" }</th>
+                                    </tr>
+                                </thead>
+                                <tbody>
+                                    {
+                                        props.rows.iter().map(|row| {
+                                            html! {
+                                                <tr
+                                                    key={format!("{}:{}", row.client_id, row.item)}
+                                                    class={classes!(row.drifted.then_some("inventory-drifted"))}
+                                                >
+                                                    <td>{ &row.client_name }</td>
+                                                    <td>
+                                                        { &row.item }
+                                                        {
+                                                            if row.drifted {
+                                                                html! { <span class="inventory-drift-label">{ "Drifted" }</span> }
+                                                            } else {
+                                                                html! {}
+                                                            }
+                                                        }
+                                                    </td>
+                                                    { inventory_cell(&row.development) }
+                                                    { inventory_cell(&row.staging) }
+                                                    { inventory_cell(&row.production) }
+                                                </tr>
+                                            }
+                                        }).collect::<Html>()
+                                    }
+                                </tbody>
+                            </table>
+                        </>
+                    }
+                }
+            }
+        </div>
+    }
+}
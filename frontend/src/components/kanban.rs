@@ -11,6 +11,16 @@ pub struct KanbanBoardProps {
     pub on_clear_release: Callback<String>,
     pub on_delete_release: Callback<String>,
     pub on_view_logs: Callback<String>,
+    pub on_rerun_item: Callback<(String, String)>,
+    pub on_cancel_release: Callback<String>,
+    pub on_rollback_release: Callback<String>,
+    pub on_dry_run_release: Callback<String>,
+    pub on_cancel_dry_run: Callback<String>,
+    pub on_answer_approval: Callback<(String, bool, Option<String>)>,
+    pub on_cancel_item: Callback<(String, String)>,
+    pub on_confirm_item: Callback<(String, String, bool)>,
+    pub on_complete_manual_step: Callback<(String, String, bool, Option<String>)>,
+    pub on_open_train: Callback<String>,
 }
 
 #[function_component(KanbanBoard)]
@@ -43,6 +53,62 @@ pub fn kanban_board(props: &KanbanBoardProps) -> Html {
         })
     };
     
+    let on_rerun_item = {
+        let callback = props.on_rerun_item.clone();
+        Callback::from(move |(id, item_name): (String, String)| {
+            callback.emit((id, item_name));
+        })
+    };
+    
+    let on_cancel = {
+        let callback = props.on_cancel_release.clone();
+        Callback::from(move |id: String| {
+            callback.emit(id);
+        })
+    };
+    
+    let on_rollback = {
+        let callback = props.on_rollback_release.clone();
+        Callback::from(move |id: String| {
+            callback.emit(id);
+        })
+    };
+    
+    let on_dry_run = {
+        let callback = props.on_dry_run_release.clone();
+        Callback::from(move |id: String| {
+            callback.emit(id);
+        })
+    };
+    
+    let on_cancel_dry_run = {
+        let callback = props.on_cancel_dry_run.clone();
+        Callback::from(move |id: String| {
+            callback.emit(id);
+        })
+    };
+    
+    let on_answer_approval = {
+        let callback = props.on_answer_approval.clone();
+        Callback::from(move |(id, approved, comment): (String, bool, Option<String>)| {
+            callback.emit((id, approved, comment));
+        })
+    };
+    
+    let on_cancel_item = {
+        let callback = props.on_cancel_item.clone();
+        Callback::from(move |(id, item_name): (String, String)| {
+            callback.emit((id, item_name));
+        })
+    };
+    
+    let on_confirm_item = {
+        let callback = props.on_confirm_item.clone();
+        Callback::from(move |answer: (String, String, bool)| {
+            callback.emit(answer);
+        })
+    };
+    
     let on_drag_over = Callback::from(|event: DragEvent| {
         event.prevent_default();
     });
@@ -141,6 +207,16 @@ pub fn kanban_board(props: &KanbanBoardProps) -> Html {
                                     on_move={on_move.clone()}
                                     on_clear={on_clear.clone()}
                                     on_view_logs={on_view_logs.clone()}
+                                    on_rerun_item={on_rerun_item.clone()}
+                                    on_cancel={on_cancel.clone()}
+                                    on_rollback={on_rollback.clone()}
+                                    on_dry_run={on_dry_run.clone()}
+                                    on_cancel_dry_run={on_cancel_dry_run.clone()}
+                                    on_answer_approval={on_answer_approval.clone()}
+                                    on_cancel_item={on_cancel_item.clone()}
+                                    on_confirm_item={on_confirm_item.clone()}
+                                    on_complete_manual_step={props.on_complete_manual_step.clone()}
+                                    on_open_train={props.on_open_train.clone()}
                                 />
                             }
                         }).collect::<Html>()
@@ -169,6 +245,16 @@ pub fn kanban_board(props: &KanbanBoardProps) -> Html {
                                     on_move={on_move.clone()}
                                     on_clear={on_clear.clone()}
                                     on_view_logs={on_view_logs.clone()}
+                                    on_rerun_item={on_rerun_item.clone()}
+                                    on_cancel={on_cancel.clone()}
+                                    on_rollback={on_rollback.clone()}
+                                    on_dry_run={on_dry_run.clone()}
+                                    on_cancel_dry_run={on_cancel_dry_run.clone()}
+                                    on_answer_approval={on_answer_approval.clone()}
+                                    on_cancel_item={on_cancel_item.clone()}
+                                    on_confirm_item={on_confirm_item.clone()}
+                                    on_complete_manual_step={props.on_complete_manual_step.clone()}
+                                    on_open_train={props.on_open_train.clone()}
                                 />
                             }
                         }).collect::<Html>()
@@ -197,6 +283,16 @@ pub fn kanban_board(props: &KanbanBoardProps) -> Html {
                                     on_move={on_move.clone()}
                                     on_clear={on_clear.clone()}
                                     on_view_logs={on_view_logs.clone()}
+                                    on_rerun_item={on_rerun_item.clone()}
+                                    on_cancel={on_cancel.clone()}
+                                    on_rollback={on_rollback.clone()}
+                                    on_dry_run={on_dry_run.clone()}
+                                    on_cancel_dry_run={on_cancel_dry_run.clone()}
+                                    on_answer_approval={on_answer_approval.clone()}
+                                    on_cancel_item={on_cancel_item.clone()}
+                                    on_confirm_item={on_confirm_item.clone()}
+                                    on_complete_manual_step={props.on_complete_manual_step.clone()}
+                                    on_open_train={props.on_open_train.clone()}
                                 />
                             }
                         }).collect::<Html>()
@@ -4,3 +4,7 @@ pub mod chat;
 pub mod release_form;
 pub mod release_card;
 pub mod log_drawer;
+pub mod freeze_calendar;
+pub mod train_panel;
+pub mod recurring_panel;
+pub mod inventory_matrix;
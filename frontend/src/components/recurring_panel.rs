@@ -0,0 +1,270 @@
+use yew::prelude::*;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use chrono::Local;
+use std::collections::HashSet;
+use crate::models::{Client, DeploymentItemType, RecurringRelease, User};
+use crate::services::api::CreateRecurringRequest;
+
+// Occurrences shown per recurring release, newest first
+const SHOWN_OCCURRENCES: usize = 5;
+
+// The browser's IANA time zone, the default for new schedules
+fn browser_time_zone() -> String {
+    let options = js_sys::Intl::DateTimeFormat::new(&js_sys::Array::new(), &js_sys::Object::new()).resolved_options();
+    js_sys::Reflect::get(&options, &"timeZone".into()).ok()
+        .and_then(|time_zone| time_zone.as_string())
+        .unwrap_or_else(|| "UTC".to_string())
+}
+
+fn client_name(clients: &[Client], client_id: &str) -> String {
+    clients.iter()
+        .find(|client| client.id == client_id)
+        .map(|client| client.name.clone())
+        .unwrap_or_else(|| client_id.to_string())
+}
+
+#[derive(Properties, PartialEq)]
+pub struct RecurringPanelProps {
+    pub recurring: Vec<RecurringRelease>,
+    pub clients: Vec<Client>,
+    pub deployment_item_types: Vec<DeploymentItemType>,
+    pub current_user: Option<User>,
+    pub on_create: Callback<CreateRecurringRequest>,
+    pub on_set_enabled: Callback<(String, bool)>,
+    pub on_delete: Callback<String>,
+    pub on_close: Callback<()>,
+}
+
+// Recurring releases with their schedules and latest occurrences, and a form for new ones
+#[function_component(RecurringPanel)]
+pub fn recurring_panel(props: &RecurringPanelProps) -> Html {
+    let title_ref = use_node_ref();
+    let client_ref = use_node_ref();
+    let schedule_ref = use_node_ref();
+    let time_zone_ref = use_node_ref();
+    let current_env_ref = use_node_ref();
+    let target_env_ref = use_node_ref();
+    let skip_staging_ref = use_node_ref();
+    let unchecked_items = use_state(HashSet::<String>::new);
+
+    let can_edit = props.current_user.as_ref()
+        .is_some_and(|user| user.can_deploy_to_staging() || user.can_deploy_to_production());
+
+    let on_item_change = {
+        let unchecked_items = unchecked_items.clone();
+        Callback::from(move |name: String| {
+            let mut items = (*unchecked_items).clone();
+            if !items.remove(&name) {
+                items.insert(name);
+            }
+            unchecked_items.set(items);
+        })
+    };
+
+    let on_submit = {
+        let refs = (
+            title_ref.clone(),
+            client_ref.clone(),
+            schedule_ref.clone(),
+            time_zone_ref.clone(),
+            current_env_ref.clone(),
+            target_env_ref.clone(),
+            skip_staging_ref.clone(),
+        );
+        let unchecked_items = unchecked_items.clone();
+        let item_types = props.deployment_item_types.clone();
+        let callback = props.on_create.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let (title_ref, client_ref, schedule_ref, time_zone_ref, current_env_ref, target_env_ref, skip_staging_ref) = &refs;
+            let input = |node: &NodeRef| node.cast::<HtmlInputElement>().map(|input| input.value()).unwrap_or_default();
+            let select = |node: &NodeRef| node.cast::<HtmlSelectElement>().map(|select| select.value()).unwrap_or_default();
+
+            callback.emit(CreateRecurringRequest {
+                title: input(title_ref),
+                client_id: select(client_ref),
+                schedule: input(schedule_ref),
+                time_zone: input(time_zone_ref),
+                current_environment: select(current_env_ref),
+                target_environment: select(target_env_ref),
+                deployment_items: item_types.iter()
+                    .filter(|item_type| !unchecked_items.contains(&item_type.name))
+                    .map(|item_type| item_type.name.clone())
+                    .collect(),
+                skip_staging: skip_staging_ref.cast::<HtmlInputElement>().is_some_and(|input| input.checked()),
+            });
+        })
+    };
+
+    let on_close = {
+        let callback = props.on_close.clone();
+        Callback::from(move |_: MouseEvent| callback.emit(()))
+    };
+
+    html! {
+        <div class="recurring-panel">
+            <div class="recurring-panel-header">
+                <h2>{ "Recurring releases" }</h2>
+                <button class="close-btn" onclick={on_close}>{ "Close" }</button>
+            </div>
+
+            {
+                if props.recurring.is_empty() {
+                    html! { <p class="recurring-empty">{ "No recurring releases yet" }</p> }
+                } else {
+                    props.recurring.iter().map(|recurring| {
+                        let id = recurring.id.clone();
+                        let enabled = recurring.enabled;
+                        let on_toggle = props.on_set_enabled.reform(move |_: MouseEvent| (id.clone(), !enabled));
+                        let id = recurring.id.clone();
+                        let on_delete = props.on_delete.reform(move |_: MouseEvent| id.clone());
+
+                        html! {
+                            <div key={recurring.id.clone()} class={classes!("recurring-entry", (!recurring.enabled).then_some("recurring-disabled"))}>
+                                <div class="recurring-entry-header">
+                                    <strong>{ &recurring.title }</strong>
+                                    <span class="recurring-client">{ client_name(&props.clients, &recurring.client_id) }</span>
+                                    {
+                                        if can_edit {
+                                            html! {
+                                                <>
+                                                    <button class="form-btn" onclick={on_toggle}>
+                                                        { if recurring.enabled { "Pause" } else { "Resume" } }
+                                                    </button>
+                                                    <button class="delete-btn" onclick={on_delete}>{ "Delete" }</button>
+                                                </>
+                                            }
+                                        } else {
+                                            html! {}
+                                        }
+                                    }
+                                </div>
+                                <p class="recurring-meta">
+                                    { format!("{} ({}), {:?} to {:?}{}, items: {}",
+                                        recurring.schedule,
+                                        recurring.time_zone,
+                                        recurring.current_environment,
+                                        recurring.target_environment,
+                                        if recurring.skip_staging { " (skip staging)" } else { "" },
+                                        recurring.deployment_items.join(", ")) }
+                                </p>
+                                <p class="recurring-meta">
+                                    {
+                                        match recurring.next_run_at {
+                                            Some(next_run_at) => format!("Next: {}", next_run_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")),
+                                            None => "Paused".to_string(),
+                                        }
+                                    }
+                                </p>
+                                <ul class="recurring-occurrences">
+                                    {
+                                        recurring.occurrences.iter().rev().take(SHOWN_OCCURRENCES).map(|occurrence| {
+                                            let scheduled_for = occurrence.scheduled_for.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+                                            match &occurrence.skipped {
+                                                Some(reason) => html! {
+                                                    <li class="recurring-occurrence recurring-skipped">
+                                                        { format!("{}: skipped, {}", scheduled_for, reason) }
+                                                    </li>
+                                                },
+                                                None => html! {
+                                                    <li class="recurring-occurrence">
+                                                        { format!("{}: release created", scheduled_for) }
+                                                    </li>
+                                                },
+                                            }
+                                        }).collect::<Html>()
+                                    }
+                                </ul>
+                            </div>
+                        }
+                    }).collect::<Html>()
+                }
+            }
+
+            {
+                if can_edit {
+                    html! {
+                        <form class="recurring-form" onsubmit={on_submit}>
+                            <h3>{ "New recurring release" }</h3>
+                            <div class="form-group">
+                                <label for="recurring-title">{ "Title" }</label>
+                                <input ref={title_ref} id="recurring-title" type="text" required=true />
+                            </div>
+                            <div class="form-group">
+                                <label for="recurring-client">{ "Client" }</label>
+                                <select ref={client_ref} id="recurring-client" required=true>
+                                    <option value="">{ "-- Select Client --" }</option>
+                                    {
+                                        props.clients.iter().map(|client| {
+                                            html! { <option value={client.id.clone()}>{ &client.name }</option> }
+                                        }).collect::<Html>()
+                                    }
+                                </select>
+                            </div>
+                            <div class="form-group">
+                                <label for="recurring-schedule">{ "Schedule (cron or RRULE)" }</label>
+                                <input
+                                    ref={schedule_ref}
+                                    id="recurring-schedule"
+                                    type="text"
+                                    placeholder="0 22 * * SUN or FREQ=WEEKLY;BYDAY=SU;BYHOUR=22"
+                                    required=true
+                                />
+                            </div>
+                            <div class="form-group">
+                                <label for="recurring-time-zone">{ "Time Zone" }</label>
+                                <input ref={time_zone_ref} id="recurring-time-zone" type="text" value={browser_time_zone()} required=true />
+                            </div>
+                            <div class="form-group">
+                                <label for="recurring-current-env">{ "Current Environment" }</label>
+                                <select ref={current_env_ref} id="recurring-current-env">
+                                    <option selected=true value="Development">{ "Development" }</option>
+                                    <option value="Staging">{ "Staging" }</option>
+                                </select>
+                            </div>
+                            <div class="form-group">
+                                <label for="recurring-target-env">{ "Target Environment" }</label>
+                                <select ref={target_env_ref} id="recurring-target-env">
+                                    <option selected=true value="Staging">{ "Staging" }</option>
+                                    <option value="Production">{ "Production" }</option>
+                                </select>
+                            </div>
+                            <div class="form-group">
+                                <label class="checkbox-label">
+                                    { "Skip Staging (Development to Production only)" }
+                                    <input ref={skip_staging_ref} type="checkbox" />
+                                </label>
+                            </div>
+                            <div class="form-group">
+                                <label>{ "Deployment Items" }</label>
+                                <div class="checkbox-group">
+                                    {
+                                        props.deployment_item_types.iter().map(|item_type| {
+                                            let name = item_type.name.clone();
+                                            let on_change = on_item_change.reform(move |_: Event| name.clone());
+                                            html! {
+                                                <label key={item_type.name.clone()}>
+                                                    <input
+                                                        type="checkbox"
+                                                        checked={!unchecked_items.contains(&item_type.name)}
+                                                        onchange={on_change}
+                                                    />
+                                                    { &item_type.display_name }
+                                                </label>
+                                            }
+                                        }).collect::<Html>()
+                                    }
+                                </div>
+                            </div>
+                            <div class="form-actions">
+                                <button type="submit" class="submit-btn">{ "Create Recurring Release" }</button>
+                            </div>
+                        </form>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+        </div>
+    }
+}
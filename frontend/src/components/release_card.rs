@@ -1,6 +1,6 @@
 use yew::prelude::*;
-use crate::models::{Release, Environment, ReleaseStatus, User, UserRole};
-use web_sys::{DragEvent, DataTransfer};
+use crate::models::{Release, Environment, HealthCheckStatus, ReleaseStatus, TargetStatus, User, UserRole};
+use web_sys::{DragEvent, DataTransfer, HtmlInputElement};
 use wasm_bindgen::JsCast;
 use chrono::Local;
 
@@ -12,6 +12,16 @@ pub struct ReleaseCardProps {
     pub on_move: Callback<(String, Environment)>,
     pub on_clear: Callback<String>,
     pub on_view_logs: Callback<String>,
+    pub on_rerun_item: Callback<(String, String)>,
+    pub on_cancel: Callback<String>,
+    pub on_rollback: Callback<String>,
+    pub on_dry_run: Callback<String>,
+    pub on_cancel_dry_run: Callback<String>,
+    pub on_answer_approval: Callback<(String, bool, Option<String>)>,
+    pub on_cancel_item: Callback<(String, String)>,
+    pub on_confirm_item: Callback<(String, String, bool)>,
+    pub on_complete_manual_step: Callback<(String, String, bool, Option<String>)>,
+    pub on_open_train: Callback<String>,
 }
 
 #[function_component(ReleaseCard)]
@@ -37,6 +47,29 @@ pub fn release_card(props: &ReleaseCardProps) -> Html {
         _ => false,
     };
     
+    // Deployers and admins can cancel a waiting or running deployment
+    let can_cancel = release.can_be_cancelled() && (can_deploy_to_staging || can_deploy_to_production);
+    
+    // Rolling back needs the same permission as deploying to the environment
+    let can_roll_back = match release.rollback_environment() {
+        Some(Environment::Production) => can_deploy_to_production,
+        Some(_) => can_deploy_to_staging,
+        None => false,
+    };
+    
+    // Dry runs touch nothing, but still run the scripts, so they need deploy permission
+    let dry_run = release.latest_dry_run();
+    let dry_run_running = dry_run.is_some_and(|dry_run| dry_run.is_running());
+    let can_dry_run = release.can_dry_run() && !dry_run_running
+        && (can_deploy_to_staging || can_deploy_to_production);
+    let can_cancel_dry_run = dry_run_running && (can_deploy_to_staging || can_deploy_to_production);
+    
+    // Approvers need one of the rule's roles, and may not be the author unless the rule allows it
+    let can_answer_approval = props.current_user.as_ref()
+        .map(|user| release.can_be_approved_by(user))
+        .unwrap_or(false);
+    let approval_comment_ref = use_node_ref();
+    
     // Check if delete is allowed (admin only)
     let can_delete = props.current_user.as_ref()
         .map(|user| matches!(user.role, UserRole::Admin))
@@ -96,6 +129,57 @@ pub fn release_card(props: &ReleaseCardProps) -> Html {
         })
     };
     
+    let on_cancel = {
+        let id = release.id.clone();
+        let callback = props.on_cancel.clone();
+        
+        Callback::from(move |_| {
+            callback.emit(id.clone());
+        })
+    };
+    
+    let on_rollback = {
+        let id = release.id.clone();
+        let callback = props.on_rollback.clone();
+        
+        Callback::from(move |_| {
+            callback.emit(id.clone());
+        })
+    };
+    
+    let on_dry_run = {
+        let id = release.id.clone();
+        let callback = props.on_dry_run.clone();
+        
+        Callback::from(move |_| {
+            callback.emit(id.clone());
+        })
+    };
+    
+    let on_cancel_dry_run = {
+        let id = release.id.clone();
+        let callback = props.on_cancel_dry_run.clone();
+        
+        Callback::from(move |_| {
+            callback.emit(id.clone());
+        })
+    };
+    
+    let answer_approval = |approved: bool| {
+        let id = release.id.clone();
+        let callback = props.on_answer_approval.clone();
+        let comment_ref = approval_comment_ref.clone();
+        
+        Callback::from(move |_| {
+            let comment = comment_ref.cast::<HtmlInputElement>()
+                .map(|input| input.value())
+                .filter(|comment| !comment.trim().is_empty());
+            callback.emit((id.clone(), approved, comment));
+        })
+    };
+    let on_approve = answer_approval(true);
+    let on_reject = answer_approval(false);
+    
     // Get status class and display name
     let status_class = release.status.css_class();
     let status_display = release.status.display_name();
@@ -116,6 +200,22 @@ pub fn release_card(props: &ReleaseCardProps) -> Html {
                 <h3 class="release-title">{ &release.title }</h3>
                 <div class="release-status">
                     <span class={status_class}>{ status_display }</span>
+                    {
+                        // Open the page of the train the release belongs to
+                        if let Some(train_id) = release.train_id.clone() {
+                            let on_open_train = props.on_open_train.reform(move |_: MouseEvent| train_id.clone());
+                            html! { <button class="train-badge" onclick={on_open_train}>{ "Train" }</button> }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    {
+                        if let Some(position) = release.queue_position {
+                            html! { <span class="queue-position">{ format!("Queued #{}", position) }</span> }
+                        } else {
+                            html! {}
+                        }
+                    }
                 </div>
             </div>
             
@@ -138,6 +238,65 @@ pub fn release_card(props: &ReleaseCardProps) -> Html {
                 </div>
             </div>
             
+            {
+                // How the health checks of the last deployment are doing
+                if !release.health_checks.is_empty() {
+                    let passed = release.health_checks.iter()
+                        .filter(|check| check.status == HealthCheckStatus::Passed)
+                        .count();
+                    let failed = release.health_checks.iter().any(|check| check.status == HealthCheckStatus::Failed);
+                    html! {
+                        <p class={classes!("health-check-line", if failed { "health-failed" } else { "" })}>
+                            { format!("Health checks: {} of {} passed", passed, release.health_checks.len()) }
+                        </p>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+            
+            {
+                // Pending approvals, with the answers given so far
+                if let Some(request) = &release.approval_request {
+                    let approvals = release.current_approvals();
+                    let approved = approvals.iter().filter(|approval| approval.approved).count();
+                    html! {
+                        <div class="approval-panel">
+                            <p class="approval-summary">
+                                { format!("{} to {:?}: {} of {} approvals from {}",
+                                    request.rerun_item.as_ref()
+                                        .map(|item_name| format!("Rerun of {}", item_name))
+                                        .unwrap_or_else(|| "Deployment".to_string()),
+                                    request.environment,
+                                    approved,
+                                    request.required,
+                                    request.roles.iter().map(|role| format!("{:?}", role)).collect::<Vec<_>>().join(" or ")) }
+                            </p>
+                            {
+                                if can_answer_approval {
+                                    html! {
+                                        <div class="approval-actions">
+                                            <input
+                                                type="text"
+                                                class="approval-comment"
+                                                placeholder="Comment (optional)"
+                                                ref={approval_comment_ref.clone()}
+                                            />
+                                            <button class="approve-btn" onclick={on_approve}>{ "Approve" }</button>
+                                            <button class="reject-btn" onclick={on_reject}>{ "Reject" }</button>
+                                        </div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+                        </div>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+            
             <div class="card-actions">
                 <button onclick={on_details_click}>
                     { if *show_details { "Hide Details" } else { "Show Details" } }
@@ -164,6 +323,51 @@ pub fn release_card(props: &ReleaseCardProps) -> Html {
                     }
                 }
                 
+                {
+                    // Cancel button - only while waiting for or running a deployment
+                    if can_cancel {
+                        html! {
+                            <button class="cancel-deploy-btn" onclick={on_cancel}>
+                                { "Cancel Deployment" }
+                            </button>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                
+                {
+                    // Roll back the items the latest deployment got out
+                    if can_roll_back {
+                        html! {
+                            <button class="rollback-btn" onclick={on_rollback}>
+                                { "Roll back" }
+                            </button>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                
+                {
+                    // Rehearse the next deployment, or stop the rehearsal in progress
+                    if can_cancel_dry_run {
+                        html! {
+                            <button class="cancel-dry-run-btn" onclick={on_cancel_dry_run}>
+                                { "Cancel Dry Run" }
+                            </button>
+                        }
+                    } else if can_dry_run {
+                        html! {
+                            <button class="dry-run-btn" onclick={on_dry_run}>
+                                { "Dry Run" }
+                            </button>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                
                 {
                     // View logs button - always visible
                     if has_logs {
@@ -228,17 +432,57 @@ pub fn release_card(props: &ReleaseCardProps) -> Html {
                                         let on_rerun_item = {
                                             let release_id = release.id.clone();
                                             let item_name = item.name.clone();
+                                            let callback = props.on_rerun_item.clone();
                                             Callback::from(move |_| {
-                                                // TODO: Add API call to rerun this specific item
-                                                let release_id = release_id.clone();
-                                                let item_name = item_name.clone();
                                                 log::info!("Rerunning item {} for release {}", item_name, release_id);
-                                                
-                                                // This would be replaced with an actual API call later
-                                                // on_rerun_item.emit((release_id, item_name));
+                                                callback.emit((release_id.clone(), item_name.clone()));
+                                            })
+                                        };
+                                        
+                                        // Create cancel callback
+                                        let on_cancel_item = {
+                                            let release_id = release.id.clone();
+                                            let item_name = item.name.clone();
+                                            let callback = props.on_cancel_item.clone();
+                                            Callback::from(move |_| {
+                                                log::info!("Cancelling item {} for release {}", item_name, release_id);
+                                                callback.emit((release_id.clone(), item_name.clone()));
+                                            })
+                                        };
+                                        
+                                        let artifacts = item.artifacts();
+                                        let warnings = item.warnings();
+                                        
+                                        // Answer the script's confirmation request
+                                        let on_confirm_item = |approved: bool| {
+                                            let release_id = release.id.clone();
+                                            let item_name = item.name.clone();
+                                            let callback = props.on_confirm_item.clone();
+                                            Callback::from(move |_| {
+                                                callback.emit((release_id.clone(), item_name.clone(), approved));
                                             })
                                         };
                                         
+                                        // Mark the manual step done or failed, with the note typed next to the buttons
+                                        let on_complete_manual_step = |done: bool| {
+                                            let release_id = release.id.clone();
+                                            let item_name = item.name.clone();
+                                            let callback = props.on_complete_manual_step.clone();
+                                            Callback::from(move |e: MouseEvent| {
+                                                let note = e.target_dyn_into::<web_sys::Element>()
+                                                    .and_then(|button| button.parent_element())
+                                                    .and_then(|actions| actions.query_selector("input").ok().flatten())
+                                                    .and_then(|input| input.dyn_into::<HtmlInputElement>().ok())
+                                                    .map(|input| input.value())
+                                                    .filter(|note| !note.trim().is_empty());
+                                                callback.emit((release_id.clone(), item_name.clone(), done, note));
+                                            })
+                                        };
+                                        
+                                        let item_running = matches!(item.status,
+                                            ReleaseStatus::DeployingToStaging |
+                                            ReleaseStatus::DeployingToProduction);
+                                        
                                         html! {
                                             <li class={classes!("deployment-item", item_status_class)}>
                                                 <div class="item-header">
@@ -246,46 +490,145 @@ pub fn release_card(props: &ReleaseCardProps) -> Html {
                                                     <span class={classes!("item-status", if item.status == ReleaseStatus::Error { "item-status-error" } else { "" })}>
                                                         { item.status.display_name() }
                                                     </span>
+                                                    {
+                                                        // Only items with a retry policy show their attempt
+                                                        if item.max_attempts > 1 && item.attempt > 0 {
+                                                            html! {
+                                                                <span class="item-attempt">
+                                                                    { format!("attempt {}/{}", item.attempt, item.max_attempts) }
+                                                                </span>
+                                                            }
+                                                        } else {
+                                                            html! {}
+                                                        }
+                                                    }
+                                                    {
+                                                        // The step the script announced last
+                                                        match &item.current_step {
+                                                            Some(step) if item_running => html! {
+                                                                <span class="item-step">{ step }</span>
+                                                            },
+                                                            _ => html! {},
+                                                        }
+                                                    }
                                                 </div>
                                                 
                                                 // Add individual progress indicator
                                                 {
-                                                    if item.status == ReleaseStatus::InDevelopment || 
-                                                        item.status == ReleaseStatus::DeployingToStaging ||
-                                                        item.status == ReleaseStatus::DeployingToProduction {
+                                                    if item.started_at.is_some() || item_running {
+                                                        let progress = item.display_progress();
+                                                        let progress_text = if item_running {
+                                                            format!("{:.0}%", progress)
+                                                        } else if progress >= 100.0 {
+                                                            "Completed".to_string()
+                                                        } else {
+                                                            format!("Stopped at {:.0}%", progress)
+                                                        };
+                                                        
                                                         html! {
                                                             <div class="item-progress">
                                                                 <div class="item-progress-bar">
                                                                     <div class="item-progress-fill" 
-                                                                        style={
-                                                                            if item.status == ReleaseStatus::InDevelopment { 
-                                                                                "width: 0%" 
-                                                                            } else if matches!(item.status, 
-                                                                                ReleaseStatus::ReadyToTestInStaging | 
-                                                                                ReleaseStatus::ReadyToTestInProduction | 
-                                                                                ReleaseStatus::ClearedInStaging |
-                                                                                ReleaseStatus::ClearedInProduction) {
-                                                                                "width: 100%"
+                                                                        style={format!("width: {:.1}%", progress)} />
+                                                                </div>
+                                                                <span class="item-progress-text">{ progress_text }</span>
+                                                            </div>
+                                                        }
+                                                    } else {
+                                                        html! {}
+                                                    }
+                                                }
+                                                
+                                                // Progress of a phased rollout, target by target
+                                                {
+                                                    if !item.targets.is_empty() {
+                                                        html! {
+                                                            <ul class="item-targets">
+                                                                { for item.targets.iter().map(|target| html! {
+                                                                    <li class={classes!("item-target", target.status.css_class())}>
+                                                                        <span class="item-target-batch">{ format!("Batch {}", target.batch) }</span>
+                                                                        <span class="item-target-name">{ &target.name }</span>
+                                                                        <span class="item-target-status">
+                                                                            {
+                                                                                if target.status == TargetStatus::Running {
+                                                                                    format!("{} {:.0}%", target.status.display_name(), target.progress)
+                                                                                } else {
+                                                                                    target.status.display_name().to_string()
+                                                                                }
+                                                                            }
+                                                                        </span>
+                                                                        {
+                                                                            if let Some(exit_code) = target.exit_code {
+                                                                                html! { <span class="item-target-exit">{ format!("exit {}", exit_code) }</span> }
                                                                             } else {
-                                                                                "width: 50%"  // In progress
+                                                                                html! {}
                                                                             }
-                                                                        } />
-                                                                </div>
-                                                                <span class="item-progress-text">
-                                                                    {
-                                                                        if item.status == ReleaseStatus::InDevelopment {
-                                                                            "Not started"
-                                                                        } else if matches!(item.status, 
-                                                                            ReleaseStatus::ReadyToTestInStaging | 
-                                                                            ReleaseStatus::ReadyToTestInProduction | 
-                                                                            ReleaseStatus::ClearedInStaging |
-                                                                            ReleaseStatus::ClearedInProduction) {
-                                                                            "Completed"
-                                                                        } else {
-                                                                            "In progress"
                                                                         }
+                                                                        {
+                                                                            if let Some(error) = &target.error {
+                                                                                html! { <div class="item-target-error">{ error }</div> }
+                                                                            } else {
+                                                                                html! {}
+                                                                            }
+                                                                        }
+                                                                    </li>
+                                                                }) }
+                                                            </ul>
+                                                        }
+                                                    } else {
+                                                        html! {}
+                                                    }
+                                                }
+                                                
+                                                // Timing and exit code of the latest run
+                                                {
+                                                    if let Some(started_at) = item.started_at {
+                                                        html! {
+                                                            <div class="item-run-details">
+                                                                <span>{ format!("Started {}", started_at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S")) }</span>
+                                                                {
+                                                                    if let Some(duration) = item.duration_text() {
+                                                                        html! { <span>{ format!("took {}", duration) }</span> }
+                                                                    } else {
+                                                                        html! {}
+                                                                    }
+                                                                }
+                                                                {
+                                                                    if let Some(exit_code) = item.exit_code {
+                                                                        html! { <span>{ format!("exit code {}", exit_code) }</span> }
+                                                                    } else {
+                                                                        html! {}
+                                                                    }
+                                                                }
+                                                            </div>
+                                                        }
+                                                    } else {
+                                                        html! {}
+                                                    }
+                                                }
+                                                
+                                                // The script is waiting for someone to answer
+                                                {
+                                                    if let Some(prompt) = &item.pending_confirmation {
+                                                        html! {
+                                                            <div class="item-confirmation">
+                                                                <p>{ prompt }</p>
+                                                                {
+                                                                    if can_deploy_to_staging || can_deploy_to_production {
+                                                                        html! {
+                                                                            <>
+                                                                                <button class="confirm-item-btn" onclick={on_confirm_item(true)}>
+                                                                                    { "Approve" }
+                                                                                </button>
+                                                                                <button class="reject-item-btn" onclick={on_confirm_item(false)}>
+                                                                                    { "Reject" }
+                                                                                </button>
+                                                                            </>
+                                                                        }
+                                                                    } else {
+                                                                        html! {}
                                                                     }
-                                                                </span>
+                                                                }
                                                             </div>
                                                         }
                                                     } else {
@@ -293,11 +636,45 @@ pub fn release_card(props: &ReleaseCardProps) -> Html {
                                                     }
                                                 }
                                                 
+                                                // A manual step waiting for someone to do it, or who did it
                                                 {
-                                                    if !item.logs.is_empty() {
+                                                    if let Some(instructions) = &item.pending_manual_step {
+                                                        html! {
+                                                            <div class="item-manual-step">
+                                                                <p>{ instructions }</p>
+                                                                {
+                                                                    if can_deploy_to_staging || can_deploy_to_production {
+                                                                        html! {
+                                                                            <div class="manual-step-actions">
+                                                                                <input type="text" class="manual-step-note" placeholder="Note (optional)" />
+                                                                                <button class="confirm-item-btn" onclick={on_complete_manual_step(true)}>
+                                                                                    { "Done" }
+                                                                                </button>
+                                                                                <button class="reject-item-btn" onclick={on_complete_manual_step(false)}>
+                                                                                    { "Failed" }
+                                                                                </button>
+                                                                            </div>
+                                                                        }
+                                                                    } else {
+                                                                        html! {}
+                                                                    }
+                                                                }
+                                                            </div>
+                                                        }
+                                                    } else if let Some(completion) = &item.manual_completion {
                                                         html! {
-                                                            <div class="logs-summary">
-                                                                <p>{ format!("{} log entries", item.logs.len()) }</p>
+                                                            <div class={classes!("item-manual-completion", (!completion.done).then_some("manual-step-failed"))}>
+                                                                { format!("Marked {} by {} at {}",
+                                                                    if completion.done { "done" } else { "failed" },
+                                                                    completion.completed_by,
+                                                                    completion.completed_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")) }
+                                                                {
+                                                                    if let Some(note) = &completion.note {
+                                                                        html! { <span class="manual-step-note-text">{ format!("\"{}\"", note) }</span> }
+                                                                    } else {
+                                                                        html! {}
+                                                                    }
+                                                                }
                                                             </div>
                                                         }
                                                     } else {
@@ -305,6 +682,50 @@ pub fn release_card(props: &ReleaseCardProps) -> Html {
                                                     }
                                                 }
                                                 
+                                                // Outputs, artifacts and warnings of the latest run
+                                                {
+                                                    if !item.outputs.is_empty() {
+                                                        html! {
+                                                            <dl class="item-outputs">
+                                                                { for item.outputs.iter().map(|(key, value)| html! {
+                                                                    <>
+                                                                        <dt>{ key }</dt>
+                                                                        <dd>{ value }</dd>
+                                                                    </>
+                                                                }) }
+                                                            </dl>
+                                                        }
+                                                    } else {
+                                                        html! {}
+                                                    }
+                                                }
+                                                {
+                                                    if !artifacts.is_empty() {
+                                                        html! {
+                                                            <ul class="item-artifacts">
+                                                                { for artifacts.iter().map(|(name, location)| html! {
+                                                                    <li><strong>{ name }</strong>{ format!(": {}", location) }</li>
+                                                                }) }
+                                                            </ul>
+                                                        }
+                                                    } else {
+                                                        html! {}
+                                                    }
+                                                }
+                                                {
+                                                    if !warnings.is_empty() {
+                                                        html! {
+                                                            <ul class="item-warnings">
+                                                                { for warnings.iter().map(|warning| html! {
+                                                                    <li>{ format!("⚠ {}", warning) }</li>
+                                                                }) }
+                                                            </ul>
+                                                        }
+                                                    } else {
+                                                        html! {}
+                                                    }
+                                                }
+                                                
                                                 {
                                                     if let Some(error) = &item.error {
                                                         html! {
@@ -330,12 +751,150 @@ pub fn release_card(props: &ReleaseCardProps) -> Html {
                                                         html! {}
                                                     }
                                                 }
+                                                
+                                                // Add cancel button for running items
+                                                {
+                                                    if item_running && (can_deploy_to_staging || can_deploy_to_production) {
+                                                        html! {
+                                                            <button class="cancel-item-btn" onclick={on_cancel_item}>
+                                                                { "CANCEL" }
+                                                            </button>
+                                                        }
+                                                    } else {
+                                                        html! {}
+                                                    }
+                                                }
                                             </li>
                                         }
                                     }).collect::<Html>()
                                 }
                             </ul>
                             
+                            {
+                                if let Some(dry_run) = dry_run {
+                                    html! {
+                                        <div class="dry-run-summary">
+                                            <h4>
+                                                { format!("Latest Dry Run ({:?})", dry_run.environment) }
+                                                <span class={classes!("dry-run-badge", dry_run.status.css_class())}>
+                                                    { format!("DRY RUN: {}", dry_run.status.display_name()) }
+                                                </span>
+                                            </h4>
+                                            <p class="dry-run-meta">
+                                                { format!("Started {} by {}",
+                                                    dry_run.started_at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+                                                    dry_run.requested_by.as_deref().unwrap_or("an anonymous user")) }
+                                            </p>
+                                            <ul class="dry-run-items">
+                                                {
+                                                    dry_run.items.iter().map(|item| {
+                                                        html! {
+                                                            <li class={classes!("dry-run-item", item.status.css_class())}>
+                                                                <span class="dry-run-item-wave">{ format!("Wave {}", item.wave) }</span>
+                                                                <span class="dry-run-item-name">{ &item.name }</span>
+                                                                <span class="dry-run-item-status">{ item.status.display_name() }</span>
+                                                                {
+                                                                    if let Some(code) = item.exit_code {
+                                                                        html! { <span class="dry-run-item-exit">{ format!("exit {}", code) }</span> }
+                                                                    } else {
+                                                                        html! {}
+                                                                    }
+                                                                }
+                                                                {
+                                                                    if let Some(command) = &item.command {
+                                                                        html! { <div class="dry-run-item-command">{ command }</div> }
+                                                                    } else {
+                                                                        html! {}
+                                                                    }
+                                                                }
+                                                                {
+                                                                    item.problems.iter().map(|problem| html! {
+                                                                        <div class="dry-run-item-problem">{ format!("⚠️ {}", problem) }</div>
+                                                                    }).collect::<Html>()
+                                                                }
+                                                            </li>
+                                                        }
+                                                    }).collect::<Html>()
+                                                }
+                                            </ul>
+                                        </div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+                            
+                            {
+                                if let Some(first) = release.health_checks.first() {
+                                    html! {
+                                        <div class="health-check-summary">
+                                            <h4>{ format!("Health Checks ({:?})", first.environment) }</h4>
+                                            <ul class="health-checks">
+                                                {
+                                                    release.health_checks.iter().map(|check| {
+                                                        html! {
+                                                            <li class={classes!("health-check", check.status.css_class())}>
+                                                                <span class="health-check-name">{ &check.name }</span>
+                                                                <span class="health-check-status">{ check.status.display_name() }</span>
+                                                                <span class="health-check-attempts">
+                                                                    { format!("attempt {} of {}", check.attempts, check.max_attempts) }
+                                                                </span>
+                                                                <div class="health-check-probe">{ &check.probe }</div>
+                                                                {
+                                                                    if let Some(output) = &check.output {
+                                                                        html! { <pre class="health-check-output">{ output }</pre> }
+                                                                    } else {
+                                                                        html! {}
+                                                                    }
+                                                                }
+                                                            </li>
+                                                        }
+                                                    }).collect::<Html>()
+                                                }
+                                            </ul>
+                                        </div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+                            
+                            {
+                                if !release.approvals.is_empty() {
+                                    html! {
+                                        <div class="approval-history">
+                                            <h4>{ "Approvals" }</h4>
+                                            <ul>
+                                                {
+                                                    release.approvals.iter().rev().map(|approval| {
+                                                        let verdict = if approval.approved { "approved" } else { "rejected" };
+                                                        html! {
+                                                            <li class={classes!("approval-entry", if approval.approved { "approval-approved" } else { "approval-rejected" })}>
+                                                                <span class="approval-verdict">
+                                                                    { format!("{} ({:?}) {} {:?}", approval.username, approval.role, verdict, approval.environment) }
+                                                                </span>
+                                                                <span class="approval-time">
+                                                                    { approval.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string() }
+                                                                </span>
+                                                                {
+                                                                    if let Some(comment) = &approval.comment {
+                                                                        html! { <div class="approval-comment-text">{ format!("\"{}\"", comment) }</div> }
+                                                                    } else {
+                                                                        html! {}
+                                                                    }
+                                                                }
+                                                            </li>
+                                                        }
+                                                    }).collect::<Html>()
+                                                }
+                                            </ul>
+                                        </div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+                            
                             <div class="pipeline-info">
                                 <h4>{ "Pipeline Information" }</h4>
                                 <p><strong>{ "Current Status: " }</strong>{ release.status.display_name() }</p>
@@ -3,7 +3,9 @@ use web_sys::{HtmlInputElement, HtmlSelectElement};
 use log::{info, debug, error};
 use wasm_bindgen::JsCast;
 use chrono::{Utc, TimeZone, Local, NaiveDateTime, NaiveTime, DateTime, Datelike, Duration, Weekday};
-use crate::models::{Release, Client, Environment, ReleaseStatus, DeploymentItem};
+use std::collections::HashSet;
+use crate::models::{Release, Client, DeploymentItemType, Environment, ReleaseStatus, DeploymentItem};
+use crate::services::api::CreateTrainRequest;
 
 fn get_next_wednesday() -> DateTime<Local> {
     let now = Utc::now();
@@ -29,7 +31,9 @@ fn get_next_wednesday() -> DateTime<Local> {
 #[derive(Properties, PartialEq)]
 pub struct ReleaseFormProps {
     pub clients: Vec<Client>,
+    pub deployment_item_types: Vec<DeploymentItemType>,
     pub on_submit: Callback<Release>,
+    pub on_submit_train: Callback<CreateTrainRequest>,
     pub on_cancel: Callback<()>,
     pub on_create: Callback<String>,
 }
@@ -42,11 +46,14 @@ pub fn release_form(props: &ReleaseFormProps) -> Html {
     let target_env_ref = use_node_ref();
     let scheduled_date_ref = use_node_ref();
     let scheduled_time_ref = use_node_ref();
+    let max_failures_ref = use_node_ref();
     
-    // Deployment item checkboxes state
-    let data_checked = use_state(|| true);
-    let solr_checked = use_state(|| true);
-    let app_checked = use_state(|| true);
+    // A release train creates the release for several clients at once
+    let train_mode = use_state(|| false);
+    let train_clients = use_state(HashSet::<String>::new);
+    
+    // Deployment item checkboxes state, every item type starts out checked
+    let unchecked_items = use_state(HashSet::<String>::new);
 
     // Release date and time
     let next_wed_5pm = get_next_wednesday();
@@ -105,24 +112,34 @@ pub fn release_form(props: &ReleaseFormProps) -> Html {
         })
     };
     
-    let on_data_change = {
-        let data_checked = data_checked.clone();
-        Callback::from(move |_| {
-            data_checked.set(!*data_checked);
+    let on_train_mode_change = {
+        let train_mode = train_mode.clone();
+        Callback::from(move |e: Event| {
+            if let Some(checkbox) = e.target_dyn_into::<HtmlInputElement>() {
+                train_mode.set(checkbox.checked());
+            }
         })
     };
     
-    let on_solr_change = {
-        let solr_checked = solr_checked.clone();
-        Callback::from(move |_| {
-            solr_checked.set(!*solr_checked);
+    let on_train_client_change = {
+        let train_clients = train_clients.clone();
+        Callback::from(move |client_id: String| {
+            let mut clients = (*train_clients).clone();
+            if !clients.remove(&client_id) {
+                clients.insert(client_id);
+            }
+            train_clients.set(clients);
         })
     };
     
-    let on_app_change = {
-        let app_checked = app_checked.clone();
-        Callback::from(move |_| {
-            app_checked.set(!*app_checked);
+    let on_item_change = {
+        let unchecked_items = unchecked_items.clone();
+        Callback::from(move |name: String| {
+            let mut items = (*unchecked_items).clone();
+            if !items.remove(&name) {
+                items.insert(name);
+            }
+            unchecked_items.set(items);
         })
     };
     
@@ -135,8 +152,13 @@ pub fn release_form(props: &ReleaseFormProps) -> Html {
 
     let on_create = {
         let callback = props.on_create.clone();
+        let train_mode = train_mode.clone();
         Callback::from(move |_|{
-            let success = "Release successfully created!".to_owned();
+            let success = if *train_mode {
+                "Release train successfully created!".to_owned()
+            } else {
+                "Release successfully created!".to_owned()
+            };
             callback.emit(success);
         })
     };
@@ -175,12 +197,16 @@ pub fn release_form(props: &ReleaseFormProps) -> Html {
         let scheduled_date_ref = scheduled_date_ref.clone();
         let scheduled_time_ref = scheduled_time_ref.clone();
         
-        let data_checked = data_checked.clone();
-        let solr_checked = solr_checked.clone();
-        let app_checked = app_checked.clone();
+        let unchecked_items = unchecked_items.clone();
+        let item_types = props.deployment_item_types.clone();
         let skip_staging = skip_staging.clone();
+        let train_mode = train_mode.clone();
+        let train_clients = train_clients.clone();
+        let max_failures_ref = max_failures_ref.clone();
+        let clients = props.clients.clone();
         
         let callback = props.on_submit.clone();
+        let train_callback = props.on_submit_train.clone();
         
         Callback::from(move |e: SubmitEvent| {
             info!("release submitted");
@@ -226,17 +252,11 @@ pub fn release_form(props: &ReleaseFormProps) -> Html {
                 _ => Environment::Production,
             };
             
-            // Collect deployment items
-            let mut deployment_items = Vec::new();
-            if *data_checked {
-                deployment_items.push("data".to_string());
-            }
-            if *solr_checked {
-                deployment_items.push("solr".to_string());
-            }
-            if *app_checked {
-                deployment_items.push("app".to_string());
-            }
+            // Collect deployment items in registry order
+            let deployment_items: Vec<String> = item_types.iter()
+                .filter(|item_type| !unchecked_items.contains(&item_type.name))
+                .map(|item_type| item_type.name.clone())
+                .collect();
             
             // Parse scheduled date and time
             let datetime_str = format!("{}T{}:00Z", date_str, time_str);
@@ -247,13 +267,52 @@ pub fn release_form(props: &ReleaseFormProps) -> Html {
 
             //info!("SCHEDULED AT: {}", scheduled_at);
             
+            if *train_mode {
+                // Clients in the order they're listed
+                let client_ids = clients.iter()
+                    .filter(|client| train_clients.contains(&client.id))
+                    .map(|client| client.id.clone())
+                    .collect();
+                let max_failures = max_failures_ref.cast::<HtmlInputElement>()
+                    .and_then(|input| input.value().parse::<usize>().ok())
+                    .filter(|max| *max > 0);
+                
+                let request = CreateTrainRequest {
+                    title,
+                    client_ids,
+                    current_environment: format!("{:?}", current_env),
+                    target_environment: format!("{:?}", target_env),
+                    deployment_items,
+                    scheduled_at,
+                    skip_staging: *skip_staging,
+                    max_failures,
+                };
+                info!("{:?}", &request);
+                train_callback.emit(request);
+                return;
+            }
+            
             // Create deployment items
             let deployment_items = deployment_items.into_iter()
                 .map(|name| DeploymentItem {
                     name,
                     status: ReleaseStatus::InDevelopment,
-                    logs: Vec::new(),
                     error: None,
+                    runs: 0,
+                    attempt: 0,
+                    max_attempts: 0,
+                    progress: 0.0,
+                    started_at: None,
+                    finished_at: None,
+                    duration_ms: None,
+                    exit_code: None,
+                    events: Vec::new(),
+                    current_step: None,
+                    outputs: Default::default(),
+                    pending_confirmation: None,
+                    pending_manual_step: None,
+                    manual_completion: None,
+                    targets: Vec::new(),
                 })
                 .collect();
             
@@ -271,6 +330,13 @@ pub fn release_form(props: &ReleaseFormProps) -> Html {
                 created_by: "current_user".to_string(), // Will be assigned by backend
                 progress: 0.0,
                 skip_staging: *skip_staging, // Add the skip_staging flag
+                queue_position: None,
+                freeze_override_by: None,
+                dry_runs: Vec::new(),
+                approval_request: None,
+                approvals: Vec::new(),
+                health_checks: Vec::new(),
+                train_id: None,
             };
             info!("{:?}", &release);
             callback.emit(release);
@@ -300,25 +366,82 @@ pub fn release_form(props: &ReleaseFormProps) -> Html {
                 </div>
                 
                 <div class="form-group">
-                    <label for="client">{ "Client" }</label>
-                    <select 
-                        ref={client_ref}
-                        id="client"
-                        required=true
-                    >
-                        <option value="">{ "-- Select Client --" }</option>
-                        {
-                            props.clients.iter().map(|client| {
-                                html! {
-                                    <option value={client.id.clone()}>
-                                        { &client.name }
-                                    </option>
-                                }
-                            }).collect::<Html>()
-                        }
-                    </select>
+                    <div class="checkbox-group">
+                        <label class="checkbox-label">
+                            { "Release train (same release for several clients)" }
+                            <input 
+                                type="checkbox"
+                                checked={*train_mode}
+                                onchange={on_train_mode_change}
+                            />
+                        </label>
+                    </div>
                 </div>
                 
+                {
+                    if *train_mode {
+                        html! {
+                            <>
+                                <div class="form-group">
+                                    <label>{ "Clients" }</label>
+                                    <div class="checkbox-group train-clients">
+                                        {
+                                            props.clients.iter().map(|client| {
+                                                let client_id = client.id.clone();
+                                                let on_change = on_train_client_change.reform(move |_: Event| client_id.clone());
+                                                
+                                                html! {
+                                                    <label key={client.id.clone()}>
+                                                        <input 
+                                                            type="checkbox"
+                                                            checked={train_clients.contains(&client.id)}
+                                                            onchange={on_change}
+                                                        />
+                                                        { &client.name }
+                                                    </label>
+                                                }
+                                            }).collect::<Html>()
+                                        }
+                                    </div>
+                                </div>
+                                
+                                <div class="form-group">
+                                    <label for="max-failures">{ "Stop the train after this many failed clients" }</label>
+                                    <input 
+                                        ref={max_failures_ref}
+                                        id="max-failures"
+                                        type="number"
+                                        min="1"
+                                        placeholder="Never"
+                                    />
+                                </div>
+                            </>
+                        }
+                    } else {
+                        html! {
+                            <div class="form-group">
+                                <label for="client">{ "Client" }</label>
+                                <select 
+                                    ref={client_ref}
+                                    id="client"
+                                    required=true
+                                >
+                                    <option value="">{ "-- Select Client --" }</option>
+                                    {
+                                        props.clients.iter().map(|client| {
+                                            html! {
+                                                <option value={client.id.clone()}>
+                                                    { &client.name }
+                                                </option>
+                                            }
+                                        }).collect::<Html>()
+                                    }
+                                </select>
+                            </div>
+                        }
+                    }
+                }
+                
                 <div class="form-group">
                     <label for="current-env">{ "Current Environment" }</label>
                     <select 
@@ -380,32 +503,34 @@ pub fn release_form(props: &ReleaseFormProps) -> Html {
                     <label>{ "Deployment Items" }</label>
                     
                     <div class="checkbox-group">
-                        <label>
-                            <input 
-                                type="checkbox"
-                                checked={*data_checked}
-                                onchange={on_data_change}
-                            />
-                            { "Data" }
-                        </label>
-                        
-                        <label>
-                            <input 
-                                type="checkbox"
-                                checked={*solr_checked}
-                                onchange={on_solr_change}
-                            />
-                            { "Solr" }
-                        </label>
-                        
-                        <label>
-                            <input 
-                                type="checkbox"
-                                checked={*app_checked}
-                                onchange={on_app_change}
-                            />
-                            { "App" }
-                        </label>
+                        {
+                            props.deployment_item_types.iter().map(|item_type| {
+                                let name = item_type.name.clone();
+                                let on_change = on_item_change.reform(move |_: Event| name.clone());
+                                
+                                html! {
+                                    <label key={item_type.name.clone()}>
+                                        <input 
+                                            type="checkbox"
+                                            checked={!unchecked_items.contains(&item_type.name)}
+                                            onchange={on_change}
+                                        />
+                                        { &item_type.display_name }
+                                        {
+                                            if item_type.depends_on.is_empty() {
+                                                html! {}
+                                            } else {
+                                                html! {
+                                                    <span class="item-depends-on">
+                                                        { format!(" (after {})", item_type.depends_on.join(", ")) }
+                                                    </span>
+                                                }
+                                            }
+                                        }
+                                    </label>
+                                }
+                            }).collect::<Html>()
+                        }
                     </div>
                 </div>
                 
@@ -443,7 +568,7 @@ pub fn release_form(props: &ReleaseFormProps) -> Html {
                     </button>
                     //<button type="submit" class="submit-btn">
                     <button class="submit-btn" onclick={on_create}>
-                        { "Create Release" }
+                        { if *train_mode { "Create Release Train" } else { "Create Release" } }
                     </button>
                 </div>
             </form>
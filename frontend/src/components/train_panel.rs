@@ -0,0 +1,314 @@
+use yew::prelude::*;
+use web_sys::HtmlInputElement;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use crate::models::{Client, Release, ReleaseStatus, ReleaseTrain, TrainActionResult, User};
+
+// Statuses a train's release doesn't move on from
+fn release_done(release: &Release) -> bool {
+    matches!(release.status,
+        ReleaseStatus::ClearedInProduction |
+        ReleaseStatus::Cancelled |
+        ReleaseStatus::RolledBack)
+}
+
+fn client_name(clients: &[Client], client_id: &str) -> String {
+    clients.iter()
+        .find(|client| client.id == client_id)
+        .map(|client| client.name.clone())
+        .unwrap_or_else(|| client_id.to_string())
+}
+
+#[derive(Properties, PartialEq)]
+pub struct TrainListProps {
+    pub trains: Vec<ReleaseTrain>,
+    pub releases: Vec<Release>,
+    pub on_open: Callback<String>,
+}
+
+// Trains with releases still on their way, shown above the board
+#[function_component(TrainList)]
+pub fn train_list(props: &TrainListProps) -> Html {
+    let trains: Vec<&ReleaseTrain> = props.trains.iter()
+        .filter(|train| props.releases.iter()
+            .any(|release| release.train_id.as_ref() == Some(&train.id) && !release_done(release)))
+        .collect();
+
+    if trains.is_empty() {
+        return html! {};
+    }
+
+    html! {
+        <div class="train-list">
+            <h3>{ "Release trains" }</h3>
+            <ul>
+                {
+                    trains.iter().map(|train| {
+                        let id = train.id.clone();
+                        let on_open = props.on_open.reform(move |_: MouseEvent| id.clone());
+                        let releases = props.releases.iter()
+                            .filter(|release| release.train_id.as_ref() == Some(&train.id))
+                            .count();
+
+                        html! {
+                            <li class={classes!("train-entry", train.stopped.is_some().then_some("train-stopped"))}>
+                                <button class="train-open-btn" onclick={on_open}>{ &train.title }</button>
+                                <span class="train-summary">
+                                    { format!("{} clients, {} failed, {}", releases, train.failures.len(),
+                                        train.scheduled_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")) }
+                                </span>
+                                {
+                                    if train.stopped.is_some() {
+                                        html! { <span class="train-stopped-label">{ "Stopped" }</span> }
+                                    } else {
+                                        html! {}
+                                    }
+                                }
+                            </li>
+                        }
+                    }).collect::<Html>()
+                }
+            </ul>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct TrainPanelProps {
+    pub train: ReleaseTrain,
+    pub releases: Vec<Release>, // The train's releases
+    pub clients: Vec<Client>,
+    pub results: Vec<TrainActionResult>, // Outcome of the latest bulk action
+    pub current_user: Option<User>,
+    pub on_clear: Callback<String>,
+    pub on_reschedule: Callback<(String, DateTime<Utc>)>,
+    pub on_cancel: Callback<String>,
+    pub on_resume: Callback<String>,
+    pub on_close: Callback<()>,
+}
+
+// A train's aggregate progress and failures, with the actions for all of its releases
+#[function_component(TrainPanel)]
+pub fn train_panel(props: &TrainPanelProps) -> Html {
+    let train = &props.train;
+    let date_ref = use_node_ref();
+    let time_ref = use_node_ref();
+
+    let can_act = props.current_user.as_ref()
+        .is_some_and(|user| user.can_deploy_to_staging() || user.can_deploy_to_production());
+
+    let progress = if props.releases.is_empty() {
+        0.0
+    } else {
+        props.releases.iter().map(|release| release.progress.clamp(0.0, 100.0)).sum::<f32>() / props.releases.len() as f32
+    };
+
+    // How many releases are in each status, in the order they're first seen
+    let mut status_counts: Vec<(ReleaseStatus, usize)> = Vec::new();
+    for release in props.releases.iter() {
+        match status_counts.iter_mut().find(|(status, _)| *status == release.status) {
+            Some((_, count)) => *count += 1,
+            None => status_counts.push((release.status.clone(), 1)),
+        }
+    }
+
+    let action = |callback: &Callback<String>| {
+        let id = train.id.clone();
+        callback.reform(move |_: MouseEvent| id.clone())
+    };
+    let on_clear = action(&props.on_clear);
+    let on_cancel = action(&props.on_cancel);
+    let on_resume = action(&props.on_resume);
+
+    let on_reschedule = {
+        let id = train.id.clone();
+        let date_ref = date_ref.clone();
+        let time_ref = time_ref.clone();
+        let callback = props.on_reschedule.clone();
+        Callback::from(move |_: MouseEvent| {
+            let date = date_ref.cast::<HtmlInputElement>().map(|input| input.value()).unwrap_or_default();
+            let time = time_ref.cast::<HtmlInputElement>().map(|input| input.value()).unwrap_or_default();
+            // The inputs are in the user's local time
+            let scheduled_at = NaiveDateTime::parse_from_str(&format!("{}T{}", date, time), "%Y-%m-%dT%H:%M").ok()
+                .and_then(|naive| Local.from_local_datetime(&naive).single())
+                .map(|local| local.with_timezone(&Utc));
+            if let Some(scheduled_at) = scheduled_at {
+                callback.emit((id.clone(), scheduled_at));
+            }
+        })
+    };
+
+    let on_close = {
+        let callback = props.on_close.clone();
+        Callback::from(move |_: MouseEvent| callback.emit(()))
+    };
+
+    let scheduled = train.scheduled_at.with_timezone(&Local);
+
+    html! {
+        <div class="train-panel">
+            <div class="train-panel-header">
+                <h2>{ format!("Release train: {}", train.title) }</h2>
+                <button class="close-btn" onclick={on_close}>{ "Close" }</button>
+            </div>
+
+            <p class="train-meta">
+                { format!("{:?} to {:?}{}, scheduled {}, created by {}",
+                    train.current_environment,
+                    train.target_environment,
+                    if train.skip_staging { " (skip staging)" } else { "" },
+                    scheduled.format("%Y-%m-%d %H:%M"),
+                    train.created_by) }
+            </p>
+            <p class="train-meta">
+                { format!("Items: {}", train.deployment_items.join(", ")) }
+            </p>
+
+            {
+                if let Some(stop) = &train.stopped {
+                    html! {
+                        <p class="train-stop">
+                            { format!("Stopped {}: {}", stop.stopped_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"), stop.reason) }
+                        </p>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+
+            <div class="progress-bar">
+                <div
+                    class="progress-fill"
+                    style={format!("width: {}%", progress)}
+                />
+                <span class="progress-text">
+                    { format!("{:.1}% over {} clients", progress, props.releases.len()) }
+                </span>
+            </div>
+
+            <div class="train-status-counts">
+                {
+                    status_counts.iter().map(|(status, count)| {
+                        html! {
+                            <span class={classes!("train-status-count", status.css_class())}>
+                                { format!("{}: {}", status.display_name(), count) }
+                            </span>
+                        }
+                    }).collect::<Html>()
+                }
+            </div>
+
+            <p class="train-failures-summary">
+                {
+                    match train.max_failures {
+                        Some(max) => format!("Failures: {} (stops at {})", train.failures.len(), max),
+                        None => format!("Failures: {}", train.failures.len()),
+                    }
+                }
+            </p>
+            {
+                if train.failures.is_empty() {
+                    html! {}
+                } else {
+                    html! {
+                        <ul class="train-failures">
+                            {
+                                train.failures.iter().map(|failure| {
+                                    html! {
+                                        <li class="train-failure">
+                                            { format!("{} in {:?} at {}{}",
+                                                client_name(&props.clients, &failure.client_id),
+                                                failure.environment,
+                                                failure.failed_at.with_timezone(&Local).format("%H:%M:%S"),
+                                                failure.error.as_ref().map(|error| format!(": {}", error)).unwrap_or_default()) }
+                                        </li>
+                                    }
+                                }).collect::<Html>()
+                            }
+                        </ul>
+                    }
+                }
+            }
+
+            <table class="train-releases">
+                <thead>
+                    <tr>
+                        <th>{ "Client" }</th>
+                        <th>{ "Status" }</th>
+                        <th>{ "Progress" }</th>
+                        <th>{ "Scheduled" }</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {
+                        props.releases.iter().map(|release| {
+                            html! {
+                                <tr key={release.id.clone()}>
+                                    <td>{ client_name(&props.clients, &release.client_id) }</td>
+                                    <td><span class={release.status.css_class()}>{ release.status.display_name() }</span></td>
+                                    <td>{ format!("{:.1}%", release.progress) }</td>
+                                    <td>{ release.scheduled_at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string() }</td>
+                                </tr>
+                            }
+                        }).collect::<Html>()
+                    }
+                </tbody>
+            </table>
+
+            {
+                if can_act {
+                    html! {
+                        <div class="train-actions">
+                            {
+                                if train.stopped.is_some() {
+                                    html! {
+                                        <button class="train-resume-btn" onclick={on_resume}>{ "Resume Train" }</button>
+                                    }
+                                } else {
+                                    html! {
+                                        <button class="clear-btn" onclick={on_clear}>{ "Clear All" }</button>
+                                    }
+                                }
+                            }
+                            <span class="train-reschedule">
+                                <input
+                                    ref={date_ref}
+                                    type="date"
+                                    value={scheduled.format("%Y-%m-%d").to_string()}
+                                />
+                                <input
+                                    ref={time_ref}
+                                    type="time"
+                                    value={scheduled.format("%H:%M").to_string()}
+                                />
+                                <button class="form-btn" onclick={on_reschedule}>{ "Reschedule All" }</button>
+                            </span>
+                            <button class="cancel-deploy-btn" onclick={on_cancel}>{ "Cancel Train" }</button>
+                        </div>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+
+            {
+                if props.results.is_empty() {
+                    html! {}
+                } else {
+                    html! {
+                        <ul class="train-results">
+                            {
+                                props.results.iter().map(|result| {
+                                    html! {
+                                        <li class={classes!("train-result", (!result.success).then_some("train-result-failed"))}>
+                                            { format!("{}: {}", client_name(&props.clients, &result.client_id), result.message) }
+                                        </li>
+                                    }
+                                }).collect::<Html>()
+                            }
+                        </ul>
+                    }
+                }
+            }
+        </div>
+    }
+}
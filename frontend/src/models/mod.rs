@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Environment {
@@ -27,9 +28,18 @@ pub enum ReleaseStatus {
     ReadyToTestInProduction,
     ClearedInProduction,
     
+    // Waiting for the approvals the target environment requires
+    AwaitingApproval,
+    
     // Error states
     Error,
     Blocked,
+    Cancelled,
+    Skipped,
+    
+    // Rollback
+    RollingBack,
+    RolledBack,
 }
 
 // Helper method to get display string for status
@@ -47,8 +57,13 @@ impl ReleaseStatus {
             ReleaseStatus::DeployingToProduction => "Deploying to Production",
             ReleaseStatus::ReadyToTestInProduction => "Ready to Test in Production",
             ReleaseStatus::ClearedInProduction => "Completed",
+            ReleaseStatus::AwaitingApproval => "Awaiting Approval",
             ReleaseStatus::Error => "Error",
             ReleaseStatus::Blocked => "Blocked",
+            ReleaseStatus::Cancelled => "Cancelled",
+            ReleaseStatus::Skipped => "Skipped",
+            ReleaseStatus::RollingBack => "Rolling Back",
+            ReleaseStatus::RolledBack => "Rolled Back",
         }
     }
     
@@ -66,8 +81,13 @@ impl ReleaseStatus {
             ReleaseStatus::DeployingToProduction => "status-deploying status-deploying-to-production",
             ReleaseStatus::ReadyToTestInProduction => "status-ready",
             ReleaseStatus::ClearedInProduction => "status-completed",
+            ReleaseStatus::AwaitingApproval => "status-awaiting-approval",
             ReleaseStatus::Error => "status-error",
             ReleaseStatus::Blocked => "status-blocked",
+            ReleaseStatus::Cancelled => "status-cancelled",
+            ReleaseStatus::Skipped => "status-skipped",
+            ReleaseStatus::RollingBack => "status-rolling-back",
+            ReleaseStatus::RolledBack => "status-rolled-back",
         }
     }
     
@@ -83,7 +103,12 @@ impl ReleaseStatus {
             ReleaseStatus::DeployingToProduction | ReleaseStatus::ReadyToTestInProduction | 
             ReleaseStatus::ClearedInProduction => Environment::Production,
             
-            ReleaseStatus::Error | ReleaseStatus::Blocked => Environment::Development, // Default to Development for error states
+            ReleaseStatus::AwaitingApproval |
+            ReleaseStatus::Error | ReleaseStatus::Blocked |
+            ReleaseStatus::Cancelled |
+            ReleaseStatus::Skipped |
+            ReleaseStatus::RollingBack |
+            ReleaseStatus::RolledBack => Environment::Development, // Default to Development for error states
         }
     }
     
@@ -104,12 +129,356 @@ impl ReleaseStatus {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+    System,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LogLine {
+    pub timestamp: DateTime<Utc>,
+    pub stream: LogStream,
+    pub item_name: String,
+    pub line: String,
+    #[serde(default)]
+    pub run: u32,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+// Structured events reported by a deployment script
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum ItemEventKind {
+    Step { name: String },
+    Warning { message: String },
+    Output { key: String, value: String },
+    Artifact { name: String, location: String },
+    ConfirmationRequested { prompt: String },
+    ConfirmationAnswered { approved: bool, answered_by: String },
+    ManualStepRequested { instructions: String },
+    ManualStepCompleted { done: bool, completed_by: String, note: Option<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ItemEvent {
+    pub timestamp: DateTime<Utc>,
+    pub run: u32,
+    pub kind: ItemEventKind,
+}
+
+// Who marked a manual step done or failed, and when
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ManualCompletion {
+    pub done: bool,
+    pub completed_by: String,
+    pub completed_at: DateTime<Utc>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum TargetStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    Skipped,
+}
+
+impl TargetStatus {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TargetStatus::Pending => "Pending",
+            TargetStatus::Running => "Running",
+            TargetStatus::Succeeded => "Succeeded",
+            TargetStatus::Failed => "Failed",
+            TargetStatus::Cancelled => "Cancelled",
+            TargetStatus::Skipped => "Skipped",
+        }
+    }
+    
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            TargetStatus::Pending => "target-pending",
+            TargetStatus::Running => "target-running",
+            TargetStatus::Succeeded => "target-succeeded",
+            TargetStatus::Failed => "target-failed",
+            TargetStatus::Cancelled => "target-cancelled",
+            TargetStatus::Skipped => "target-skipped",
+        }
+    }
+}
+
+// A deployment item's run on one of the targets of a phased rollout
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TargetRun {
+    pub name: String,
+    pub batch: usize,
+    pub status: TargetStatus,
+    #[serde(default)]
+    pub progress: f32,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct DeploymentItem {
     pub name: String,
     pub status: ReleaseStatus,
-    pub logs: Vec<String>,
     pub error: Option<String>,
+    #[serde(default)]
+    pub runs: u32,
+    #[serde(default)]
+    pub attempt: u32,
+    #[serde(default)]
+    pub max_attempts: u32,
+    #[serde(default)]
+    pub progress: f32,
+    #[serde(default)]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub events: Vec<ItemEvent>,
+    #[serde(default)]
+    pub current_step: Option<String>,
+    #[serde(default)]
+    pub outputs: BTreeMap<String, String>,
+    #[serde(default)]
+    pub pending_confirmation: Option<String>,
+    #[serde(default)]
+    pub pending_manual_step: Option<String>,
+    #[serde(default)]
+    pub manual_completion: Option<ManualCompletion>,
+    #[serde(default)]
+    pub targets: Vec<TargetRun>,
+}
+
+impl DeploymentItem {
+    // Apply an event from the WebSocket the same way the backend does
+    pub fn record_event(&mut self, event: ItemEvent) {
+        match &event.kind {
+            ItemEventKind::Step { name } => self.current_step = Some(name.clone()),
+            ItemEventKind::Output { key, value } => {
+                self.outputs.insert(key.clone(), value.clone());
+            }
+            ItemEventKind::ConfirmationRequested { prompt } => self.pending_confirmation = Some(prompt.clone()),
+            ItemEventKind::ConfirmationAnswered { .. } => self.pending_confirmation = None,
+            ItemEventKind::ManualStepRequested { instructions } => self.pending_manual_step = Some(instructions.clone()),
+            ItemEventKind::ManualStepCompleted { done, completed_by, note } => {
+                self.pending_manual_step = None;
+                self.manual_completion = Some(ManualCompletion {
+                    done: *done,
+                    completed_by: completed_by.clone(),
+                    completed_at: event.timestamp,
+                    note: note.clone(),
+                });
+            }
+            ItemEventKind::Warning { .. } |
+            ItemEventKind::Artifact { .. } => {}
+        }
+        self.events.push(event);
+    }
+    
+    // Warnings of the latest run
+    pub fn warnings(&self) -> Vec<&str> {
+        self.events.iter()
+            .filter(|event| event.run == self.runs)
+            .filter_map(|event| match &event.kind {
+                ItemEventKind::Warning { message } => Some(message.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+    
+    // Artifacts of the latest run as (name, location)
+    pub fn artifacts(&self) -> Vec<(&str, &str)> {
+        self.events.iter()
+            .filter(|event| event.run == self.runs)
+            .filter_map(|event| match &event.kind {
+                ItemEventKind::Artifact { name, location } => Some((name.as_str(), location.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+    
+    // Progress shown for the item, deployed items are always complete
+    pub fn display_progress(&self) -> f32 {
+        match self.status {
+            ReleaseStatus::ReadyToTestInStaging |
+            ReleaseStatus::ReadyToTestInProduction |
+            ReleaseStatus::ClearedInStaging |
+            ReleaseStatus::ClearedInProduction => 100.0,
+            _ => self.progress.clamp(0.0, 100.0),
+        }
+    }
+    
+    // Human readable duration of the latest finished run, e.g. "2m 05s"
+    pub fn duration_text(&self) -> Option<String> {
+        self.duration_ms.map(|ms| {
+            let secs = ms / 1000;
+            if secs >= 60 {
+                format!("{}m {:02}s", secs / 60, secs % 60)
+            } else {
+                format!("{:.1}s", ms as f64 / 1000.0)
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum DryRunStatus {
+    Pending,
+    Running,
+    Passed,
+    Failed,
+    Skipped,
+    Cancelled,
+}
+
+impl DryRunStatus {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            DryRunStatus::Pending => "Pending",
+            DryRunStatus::Running => "Running",
+            DryRunStatus::Passed => "Passed",
+            DryRunStatus::Failed => "Failed",
+            DryRunStatus::Skipped => "Skipped",
+            DryRunStatus::Cancelled => "Cancelled",
+        }
+    }
+    
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            DryRunStatus::Pending => "dry-run-pending",
+            DryRunStatus::Running => "dry-run-running",
+            DryRunStatus::Passed => "dry-run-passed",
+            DryRunStatus::Failed => "dry-run-failed",
+            DryRunStatus::Skipped => "dry-run-skipped",
+            DryRunStatus::Cancelled => "dry-run-cancelled",
+        }
+    }
+}
+
+// How one deployment item fared in a dry run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DryRunItem {
+    pub name: String,
+    pub run: u32,
+    pub wave: usize,
+    pub command: Option<String>,
+    pub status: DryRunStatus,
+    #[serde(default)]
+    pub problems: Vec<String>,
+    pub exit_code: Option<i32>,
+    pub duration_ms: Option<u64>,
+}
+
+// A rehearsal of a release's deployment that leaves its status alone
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DryRun {
+    pub id: String,
+    pub environment: Environment,
+    pub requested_by: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub status: DryRunStatus,
+    pub items: Vec<DryRunItem>,
+}
+
+impl DryRun {
+    pub fn is_running(&self) -> bool {
+        self.finished_at.is_none()
+    }
+}
+
+// A deployment waiting for the approvals its environment's rule asks for
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApprovalRequest {
+    pub environment: Environment,
+    pub target_status: ReleaseStatus,
+    pub previous_status: ReleaseStatus,
+    pub required: u32,
+    pub roles: Vec<UserRole>,
+    pub allow_author: bool,
+    pub requested_by: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    #[serde(default)]
+    pub rerun_item: Option<String>,
+}
+
+// An approval or rejection of a deployment
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Approval {
+    pub username: String,
+    pub role: UserRole,
+    pub environment: Environment,
+    pub approved: bool,
+    #[serde(default)]
+    pub comment: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+// Status of a health check run after a deployment
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum HealthCheckStatus {
+    Pending,
+    Running,
+    Passed,
+    Failed,
+}
+
+impl HealthCheckStatus {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            HealthCheckStatus::Pending => "Pending",
+            HealthCheckStatus::Running => "Running",
+            HealthCheckStatus::Passed => "Passed",
+            HealthCheckStatus::Failed => "Failed",
+        }
+    }
+    
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            HealthCheckStatus::Pending => "health-pending",
+            HealthCheckStatus::Running => "health-running",
+            HealthCheckStatus::Passed => "health-passed",
+            HealthCheckStatus::Failed => "health-failed",
+        }
+    }
+}
+
+// The outcome of a health check against the environment a release was deployed to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HealthCheckResult {
+    pub name: String,
+    pub probe: String,
+    pub environment: Environment,
+    pub status: HealthCheckStatus,
+    pub attempts: u32,
+    pub max_attempts: u32,
+    pub output: Option<String>,
+    pub checked_at: Option<DateTime<Utc>>,
+}
+
+// One page of stored log lines for a deployment item
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LogPage {
+    pub item_name: String,
+    pub runs: u32,
+    pub total: usize,
+    pub dropped: usize,
+    pub offset: usize,
+    pub lines: Vec<LogLine>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -127,6 +496,20 @@ pub struct Release {
     pub progress: f32,
     #[serde(default)]
     pub skip_staging: bool,
+    #[serde(default)]
+    pub queue_position: Option<usize>,
+    #[serde(default)]
+    pub freeze_override_by: Option<String>,
+    #[serde(default)]
+    pub dry_runs: Vec<DryRun>,
+    #[serde(default)]
+    pub approval_request: Option<ApprovalRequest>,
+    #[serde(default)]
+    pub approvals: Vec<Approval>,
+    #[serde(default)]
+    pub health_checks: Vec<HealthCheckResult>,
+    #[serde(default)]
+    pub train_id: Option<String>,
 }
 
 impl Release {
@@ -140,6 +523,68 @@ impl Release {
         }
     }
     
+    // Helper to determine if a waiting or running deployment can be cancelled
+    pub fn can_be_cancelled(&self) -> bool {
+        matches!(self.status,
+            ReleaseStatus::WaitingForStaging |
+            ReleaseStatus::WaitingForProduction |
+            ReleaseStatus::WaitingForProductionFromStaging |
+            ReleaseStatus::DeployingToStaging |
+            ReleaseStatus::DeployingToProduction)
+    }
+    
+    // Approvals and rejections of the pending approval request
+    pub fn current_approvals(&self) -> Vec<&Approval> {
+        match &self.approval_request {
+            Some(request) => self.approvals.iter()
+                .filter(|approval| approval.environment == request.environment
+                    && approval.timestamp >= request.requested_at)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+    
+    // Check if the user may approve or reject the pending approval request
+    pub fn can_be_approved_by(&self, user: &User) -> bool {
+        match &self.approval_request {
+            Some(request) => request.roles.contains(&user.role)
+                && (request.allow_author || user.username != self.created_by)
+                && !self.current_approvals().iter().any(|approval| approval.username == user.username),
+            None => false,
+        }
+    }
+    
+    // A dry run can be started unless the release is being deployed or rolled back
+    pub fn can_dry_run(&self) -> bool {
+        !self.deployment_items.is_empty() && !matches!(self.status,
+            ReleaseStatus::DeployingToStaging |
+            ReleaseStatus::DeployingToProduction |
+            ReleaseStatus::RollingBack)
+    }
+    
+    // The most recent dry run, if any
+    pub fn latest_dry_run(&self) -> Option<&DryRun> {
+        self.dry_runs.last()
+    }
+    
+    // Environment whose deployed items can be rolled back, if any
+    pub fn rollback_environment(&self) -> Option<Environment> {
+        let finished = matches!(self.status,
+            ReleaseStatus::ReadyToTestInStaging |
+            ReleaseStatus::ReadyToTestInProduction |
+            ReleaseStatus::Error |
+            ReleaseStatus::Cancelled);
+        if !finished {
+            return None;
+        }
+        
+        self.deployment_items.iter().find_map(|item| match item.status {
+            ReleaseStatus::ReadyToTestInStaging => Some(Environment::Staging),
+            ReleaseStatus::ReadyToTestInProduction => Some(Environment::Production),
+            _ => None,
+        })
+    }
+    
     // Helper to get the next status when cleared
     pub fn next_status(&self) -> Option<ReleaseStatus> {
         self.status.next_status_when_cleared(self.skip_staging)
@@ -158,6 +603,11 @@ impl Release {
                     Environment::Staging
                 }
             },
+            // Waits where it was cleared from
+            ReleaseStatus::AwaitingApproval => match &self.approval_request {
+                Some(request) => request.previous_status.environment(),
+                None => self.current_environment.clone(),
+            },
             // All other states follow the normal environment mapping
             _ => self.status.environment()
         }
@@ -205,6 +655,146 @@ pub struct Client {
     pub name: String,
 }
 
+// A period in which deployments to an environment are forbidden
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FreezeWindow {
+    pub id: String,
+    pub environment: Environment,
+    #[serde(default)]
+    pub client_id: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub reason: String,
+    pub owner: String,
+}
+
+// A client release of a train that failed to deploy
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrainFailure {
+    pub release_id: String,
+    pub client_id: String,
+    pub environment: Environment,
+    pub error: Option<String>,
+    pub failed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrainStop {
+    pub reason: String,
+    pub stopped_by: Option<String>,
+    pub stopped_at: DateTime<Utc>,
+}
+
+// Releases of the same change for many clients
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReleaseTrain {
+    pub id: String,
+    pub title: String,
+    pub client_ids: Vec<String>,
+    pub current_environment: Environment,
+    pub target_environment: Environment,
+    pub deployment_items: Vec<String>,
+    pub scheduled_at: DateTime<Utc>,
+    #[serde(default)]
+    pub skip_staging: bool,
+    #[serde(default)]
+    pub max_failures: Option<usize>,
+    #[serde(default)]
+    pub failures: Vec<TrainFailure>,
+    #[serde(default)]
+    pub stopped: Option<TrainStop>,
+    pub created_by: String,
+}
+
+// What a bulk action on a train did to one of its releases
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrainActionResult {
+    pub release_id: String,
+    pub client_id: String,
+    pub success: bool,
+    pub message: String,
+}
+
+// A train with its client releases, and what the latest bulk action did to them
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrainDetails {
+    pub train: ReleaseTrain,
+    pub releases: Vec<Release>,
+    #[serde(default)]
+    pub results: Vec<TrainActionResult>,
+}
+
+// What the scheduler did when a recurring release came due
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecurringOccurrence {
+    pub scheduled_for: DateTime<Utc>,
+    pub generated_at: DateTime<Utc>,
+    pub release_id: Option<String>,
+    pub skipped: Option<String>,
+}
+
+// A release created again on every occurrence of its schedule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecurringRelease {
+    pub id: String,
+    pub title: String,
+    pub client_id: String,
+    pub schedule: String,
+    pub time_zone: String,
+    pub current_environment: Environment,
+    pub target_environment: Environment,
+    pub deployment_items: Vec<String>,
+    #[serde(default)]
+    pub skip_staging: bool,
+    pub enabled: bool,
+    pub next_run_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub occurrences: Vec<RecurringOccurrence>,
+    pub created_by: String,
+}
+
+// What is deployed of an item in one of a client's environments
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InventoryEntry {
+    pub client_id: String,
+    pub environment: Environment,
+    pub item: String,
+    pub version: Option<String>,
+    #[serde(default)]
+    pub outputs: BTreeMap<String, String>,
+    pub release_id: Option<String>,
+    pub release_title: Option<String>,
+    #[serde(default)]
+    pub reported_by: Option<String>,
+    pub deployed_at: DateTime<Utc>,
+    pub since: DateTime<Utc>,
+    #[serde(default)]
+    pub rolled_back_at: Option<DateTime<Utc>>,
+}
+
+// An item of a client across its environments
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InventoryRow {
+    pub client_id: String,
+    pub client_name: String,
+    pub item: String,
+    pub development: Option<InventoryEntry>,
+    pub staging: Option<InventoryEntry>,
+    pub production: Option<InventoryEntry>,
+    pub drifted: bool,
+}
+
+// A deployment item type from the backend registry
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeploymentItemType {
+    pub name: String,
+    pub display_name: String,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub manual_instructions: Option<String>, // Set for manual steps
+}
+
 // WebSocket message types
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type")]
@@ -225,4 +815,54 @@ pub enum WsMessage {
         message: String,
         timestamp: String,
     },
+    ItemUpdate {
+        release_id: String,
+        item_name: String,
+        status: ReleaseStatus,
+        attempt: u32,
+        max_attempts: u32,
+        error: Option<String>,
+        #[serde(default)]
+        progress: f32,
+        #[serde(default)]
+        started_at: Option<DateTime<Utc>>,
+        #[serde(default)]
+        finished_at: Option<DateTime<Utc>>,
+        #[serde(default)]
+        duration_ms: Option<u64>,
+        #[serde(default)]
+        exit_code: Option<i32>,
+    },
+    QueueUpdate {
+        release_id: String,
+        position: Option<usize>,
+    },
+    ItemEvent {
+        release_id: String,
+        item_name: String,
+        event: ItemEvent,
+    },
+    DryRunUpdate {
+        release_id: String,
+        dry_run: DryRun,
+    },
+    ApprovalUpdate {
+        release_id: String,
+        status: ReleaseStatus,
+        approval_request: Option<ApprovalRequest>,
+        approvals: Vec<Approval>,
+    },
+    HealthCheckUpdate {
+        release_id: String,
+        health_checks: Vec<HealthCheckResult>,
+    },
+    TrainUpdate {
+        train: Box<ReleaseTrain>,
+    },
+    RecurringUpdate {
+        recurring: Box<RecurringRelease>,
+    },
+    InventoryUpdate {
+        entry: Box<InventoryEntry>,
+    },
 }
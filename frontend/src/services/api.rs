@@ -1,7 +1,7 @@
 use gloo_net::http::Request;
 use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
-use crate::models::{Release, Client, User, Environment, ReleaseStatus};
+use crate::models::{Release, Client, DeploymentItemType, FreezeWindow, InventoryRow, RecurringRelease, ReleaseTrain, TrainDetails, User, Environment, ReleaseStatus, LogLine, LogPage};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsValue;
@@ -30,12 +30,37 @@ pub struct CreateReleaseRequest {
     pub skip_staging: bool, // Added skip_staging field
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CreateTrainRequest {
+    pub title: String,
+    pub client_ids: Vec<String>,
+    pub current_environment: String,
+    pub target_environment: String,
+    pub deployment_items: Vec<String>,
+    pub scheduled_at: DateTime<Utc>,
+    pub skip_staging: bool,
+    pub max_failures: Option<usize>, // Stop the train after this many client failures
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CreateRecurringRequest {
+    pub title: String,
+    pub client_id: String,
+    pub schedule: String, // Five field cron expression or RRULE
+    pub time_zone: String,
+    pub current_environment: String,
+    pub target_environment: String,
+    pub deployment_items: Vec<String>,
+    pub skip_staging: bool,
+}
+
 // Generic API error
 #[derive(Debug, Clone)]
 pub enum ApiError {
     RequestError(String),
     ParseError(String),
     ApiError(String),
+    Frozen(String), // The release would deploy during a freeze window
 }
 
 impl From<gloo_net::Error> for ApiError {
@@ -56,14 +81,62 @@ impl std::fmt::Display for ApiError {
             ApiError::RequestError(err) => write!(f, "Request error: {}", err),
             ApiError::ParseError(err) => write!(f, "Parse error: {}", err),
             ApiError::ApiError(err) => write!(f, "API error: {}", err),
+            ApiError::Frozen(err) => write!(f, "{}", err),
         }
     }
 }
 
+// Number of stored log lines requested per page
+const LOG_PAGE_SIZE: usize = 500;
+
 // API client
 pub struct ApiClient;
 
 impl ApiClient {
+    // Fetch one page of stored log lines for a deployment item
+    pub async fn get_item_logs(
+        release_id: &str,
+        item_name: &str,
+        offset: usize,
+    ) -> Result<LogPage, ApiError> {
+        let url = format!("{}/releases/{}/items/{}/logs?offset={}&limit={}",
+            API_URL, release_id, item_name, offset, LOG_PAGE_SIZE);
+        
+        let response = Request::get(&url).send().await?;
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let response: ApiResponse<LogPage> = response.json().await?;
+        
+        match response.data {
+            Some(page) => Ok(page),
+            None => Err(ApiError::ApiError(response.message.unwrap_or_else(|| 
+                "Unknown error fetching logs".to_string()))),
+        }
+    }
+    
+    // Fetch every stored log line for a deployment item, page by page
+    pub async fn get_all_item_logs(
+        release_id: &str,
+        item_name: &str,
+    ) -> Result<Vec<LogLine>, ApiError> {
+        let mut lines = Vec::new();
+        
+        loop {
+            let page = Self::get_item_logs(release_id, item_name, lines.len()).await?;
+            let received = page.lines.len();
+            lines.extend(page.lines);
+            
+            if received == 0 || lines.len() >= page.total {
+                break;
+            }
+        }
+        
+        Ok(lines)
+    }
+    
     pub async fn rerun_deployment_item(
         release_id: &str,
         item_name: &str,
@@ -74,6 +147,13 @@ impl ApiClient {
             .send()
             .await?;
             
+        // Reruns are checked like clearing the release, keep the reason it was refused
+        if matches!(response.status(), 400 | 401 | 403 | 409) {
+            let response: ApiResponse<Release> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "Not allowed to rerun the deployment item".to_string())));
+        }
+        
         if !response.ok() {
             return Err(ApiError::ApiError(format!("API error: {}", response.status())));
         }
@@ -86,6 +166,246 @@ impl ApiClient {
                 "Unknown error rerunning deployment item".to_string()))),
         }
     }
+    // Cancel a waiting or running release
+    pub async fn rollback_release(release_id: &str) -> Result<Release, ApiError> {
+        let url = format!("{}/releases/{}/rollback", API_URL, release_id);
+        
+        let response = Request::post(&url)
+            .send()
+            .await?;
+            
+        // Nothing to roll back, not allowed to, or the release is busy
+        if matches!(response.status(), 400 | 401 | 403 | 409) {
+            let response: ApiResponse<Release> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "The release can't be rolled back right now".to_string())));
+        }
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let response: ApiResponse<Release> = response.json().await?;
+        
+        match response.data {
+            Some(release) => Ok(release),
+            None => Err(ApiError::ApiError(response.message.unwrap_or_else(|| 
+                "Unknown error rolling back release".to_string()))),
+        }
+    }
+    
+    pub async fn cancel_release(release_id: &str) -> Result<Release, ApiError> {
+        let url = format!("{}/releases/{}/cancel", API_URL, release_id);
+        
+        let response = Request::post(&url)
+            .send()
+            .await?;
+            
+        // Not running, or not allowed to stop the deployment
+        if matches!(response.status(), 400 | 401 | 403 | 409) {
+            let response: ApiResponse<Release> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "The release can't be cancelled right now".to_string())));
+        }
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let response: ApiResponse<Release> = response.json().await?;
+        
+        match response.data {
+            Some(release) => Ok(release),
+            None => Err(ApiError::ApiError(response.message.unwrap_or_else(|| 
+                "Unknown error cancelling release".to_string()))),
+        }
+    }
+
+    // Rehearse the release's next deployment without changing its status
+    pub async fn dry_run_release(release_id: &str) -> Result<Release, ApiError> {
+        let url = format!("{}/releases/{}/dry-run", API_URL, release_id);
+
+        let response = Request::post(&url)
+            .send()
+            .await?;
+
+        // Already running, not allowed to, or the release can't be rehearsed right now
+        if matches!(response.status(), 400 | 401 | 403 | 409) {
+            let response: ApiResponse<Release> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "The release can't be dry run right now".to_string())));
+        }
+
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+
+        let response: ApiResponse<Release> = response.json().await?;
+
+        match response.data {
+            Some(release) => Ok(release),
+            None => Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "Unknown error starting dry run".to_string()))),
+        }
+    }
+
+    // Approve or reject a release that is awaiting approval
+    pub async fn answer_approval(
+        release_id: &str,
+        approved: bool,
+        comment: Option<String>,
+    ) -> Result<Release, ApiError> {
+        let url = format!("{}/releases/{}/approvals", API_URL, release_id);
+
+        let answer = serde_json::json!({ "approved": approved, "comment": comment });
+
+        let response = Request::post(&url)
+            .json(&answer)?
+            .send()
+            .await?;
+
+        // Not logged in, not allowed to approve, or already answered
+        if matches!(response.status(), 401 | 403 | 409) {
+            let response: ApiResponse<Release> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "The release can't be approved right now".to_string())));
+        }
+
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+
+        let response: ApiResponse<Release> = response.json().await?;
+
+        match response.data {
+            Some(release) => Ok(release),
+            None => Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "Unknown error answering approval request".to_string()))),
+        }
+    }
+
+    pub async fn cancel_dry_run(release_id: &str) -> Result<(), ApiError> {
+        let url = format!("{}/releases/{}/dry-run/cancel", API_URL, release_id);
+
+        let response = Request::post(&url)
+            .send()
+            .await?;
+
+        // No dry run in progress, or not allowed to stop it
+        if matches!(response.status(), 400 | 401 | 403) {
+            let response: ApiResponse<()> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "The dry run can't be cancelled right now".to_string())));
+        }
+
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+
+        Ok(())
+    }
+
+    // Cancel a single running deployment item
+    pub async fn confirm_deployment_item(
+        release_id: &str,
+        item_name: &str,
+        approved: bool,
+    ) -> Result<Release, ApiError> {
+        let url = format!("{}/releases/{}/items/{}/confirm", API_URL, release_id, item_name);
+        
+        let answer = serde_json::json!({ "approved": approved });
+        
+        let response = Request::post(&url)
+            .json(&answer)?
+            .send()
+            .await?;
+            
+        // The script may have stopped waiting or the user may not answer it, pass on the server's reason
+        if matches!(response.status(), 401 | 403 | 409) {
+            let response: ApiResponse<Release> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "The item is not waiting for a confirmation".to_string())));
+        }
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let response: ApiResponse<Release> = response.json().await?;
+        
+        match response.data {
+            Some(release) => Ok(release),
+            None => Err(ApiError::ApiError(response.message.unwrap_or_else(|| 
+                "Unknown error answering the confirmation".to_string()))),
+        }
+    }
+    
+    // Mark the manual step an item is waiting on as done or failed
+    pub async fn complete_manual_step(
+        release_id: &str,
+        item_name: &str,
+        done: bool,
+        note: Option<String>,
+    ) -> Result<Release, ApiError> {
+        let url = format!("{}/releases/{}/items/{}/manual", API_URL, release_id, item_name);
+        
+        let answer = serde_json::json!({ "done": done, "note": note });
+        
+        let response = Request::post(&url)
+            .json(&answer)?
+            .send()
+            .await?;
+            
+        // Not logged in, not allowed to do the step, or it's no longer waiting
+        if matches!(response.status(), 401 | 403 | 409) {
+            let response: ApiResponse<Release> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "The manual step can't be completed right now".to_string())));
+        }
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let response: ApiResponse<Release> = response.json().await?;
+        
+        match response.data {
+            Some(release) => Ok(release),
+            None => Err(ApiError::ApiError(response.message.unwrap_or_else(|| 
+                "Unknown error completing the manual step".to_string()))),
+        }
+    }
+    
+    pub async fn cancel_deployment_item(
+        release_id: &str,
+        item_name: &str,
+    ) -> Result<Release, ApiError> {
+        let url = format!("{}/releases/{}/cancel/{}", API_URL, release_id, item_name);
+        
+        let response = Request::post(&url)
+            .send()
+            .await?;
+            
+        // Not running, or not allowed to stop the deployment
+        if matches!(response.status(), 400 | 401 | 403 | 409) {
+            let response: ApiResponse<Release> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "The deployment item can't be cancelled right now".to_string())));
+        }
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let response: ApiResponse<Release> = response.json().await?;
+        
+        match response.data {
+            Some(release) => Ok(release),
+            None => Err(ApiError::ApiError(response.message.unwrap_or_else(|| 
+                "Unknown error cancelling deployment item".to_string()))),
+        }
+    }
+    
     // Fetch all releases
     pub async fn get_releases() -> Result<Vec<Release>, ApiError> {
         let url = format!("{}/releases", API_URL);
@@ -158,12 +478,14 @@ impl ApiClient {
     }
     pub async fn update_release_status(
         id: &str, 
-        status: &str
+        status: &str,
+        override_freeze: bool, // Admins only
     ) -> Result<Release, ApiError> {
         let url = format!("{}/releases/{}/status", API_URL, id);
 
         let status_update = serde_json::json!({
-            "status": status
+            "status": status,
+            "override_freeze": override_freeze
         });
 
         let response = Request::put(&url)
@@ -171,6 +493,20 @@ impl ApiClient {
             .send()
             .await?;
 
+        // A freeze window blocks the release, keep the reason so it can be shown
+        if response.status() == 409 {
+            let response: ApiResponse<Release> = response.json().await?;
+            return Err(ApiError::Frozen(response.message.unwrap_or_else(||
+                "The release would deploy during a freeze".to_string())));
+        }
+
+        // Not logged in, or not allowed to deploy to the environment
+        if matches!(response.status(), 401 | 403) {
+            let response: ApiResponse<Release> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "Not allowed to change the release's status".to_string())));
+        }
+
         if !response.ok() {
             return Err(ApiError::ApiError(format!("API error: {}", response.status())));
         }
@@ -252,7 +588,220 @@ impl ApiClient {
         Ok(clients)
     }
     
+    // Fetch the registered deployment item types
+    pub async fn get_deployment_item_types() -> Result<Vec<DeploymentItemType>, ApiError> {
+        let url = format!("{}/deployment-items", API_URL);
+        let response = Request::get(&url).send().await?;
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let item_types: Vec<DeploymentItemType> = response.json().await?;
+        Ok(item_types)
+    }
+    
     // Fetch current user info
+    // Get freeze windows, ordered by start
+    pub async fn get_freezes() -> Result<Vec<FreezeWindow>, ApiError> {
+        let url = format!("{}/freezes", API_URL);
+        let response = Request::get(&url).send().await?;
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let freezes: Vec<FreezeWindow> = response.json().await?;
+        Ok(freezes)
+    }
+    
+    // Get release trains, ordered by schedule
+    pub async fn get_trains() -> Result<Vec<ReleaseTrain>, ApiError> {
+        let url = format!("{}/trains", API_URL);
+        let response = Request::get(&url).send().await?;
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let trains: Vec<ReleaseTrain> = response.json().await?;
+        Ok(trains)
+    }
+    
+    // Get a train with its client releases
+    pub async fn get_train(train_id: &str) -> Result<TrainDetails, ApiError> {
+        let url = format!("{}/trains/{}", API_URL, train_id);
+        let response = Request::get(&url).send().await?;
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let details: TrainDetails = response.json().await?;
+        Ok(details)
+    }
+    
+    // Create a train with a release for every client
+    pub async fn create_train(request: &CreateTrainRequest) -> Result<TrainDetails, ApiError> {
+        let url = format!("{}/trains", API_URL);
+        
+        let response = Request::post(&url)
+            .json(request)?
+            .send()
+            .await?;
+        
+        // Keep the reason a client couldn't join the train so it can be shown
+        if response.status() == 400 || response.status() == 403 || response.status() == 409 {
+            let response: ApiResponse<TrainDetails> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "The release train can't be created".to_string())));
+        }
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let response: ApiResponse<TrainDetails> = response.json().await?;
+        
+        match response.data {
+            Some(details) => Ok(details),
+            None => Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "Unknown error creating release train".to_string()))),
+        }
+    }
+    
+    // Run a bulk action ("clear", "reschedule", "cancel" or "resume") on a train.
+    // Returns the summary message and the train's releases after the action.
+    pub async fn train_action(
+        train_id: &str,
+        action: &str,
+        body: serde_json::Value,
+    ) -> Result<(String, Option<TrainDetails>), ApiError> {
+        let url = format!("{}/trains/{}/{}", API_URL, train_id, action);
+        
+        let response = Request::post(&url)
+            .json(&body)?
+            .send()
+            .await?;
+        
+        // A stopped train, a train that isn't stopped or a user who may not change it
+        if matches!(response.status(), 400 | 401 | 403 | 409) {
+            let response: ApiResponse<TrainDetails> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                format!("The release train can't {} right now", action))));
+        }
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let response: ApiResponse<TrainDetails> = response.json().await?;
+        Ok((response.message.unwrap_or_default(), response.data))
+    }
+    
+    // Get the recurring releases, ordered by title
+    pub async fn get_recurring_releases() -> Result<Vec<RecurringRelease>, ApiError> {
+        let url = format!("{}/recurring", API_URL);
+        let response = Request::get(&url).send().await?;
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let recurring: Vec<RecurringRelease> = response.json().await?;
+        Ok(recurring)
+    }
+    
+    pub async fn create_recurring_release(request: &CreateRecurringRequest) -> Result<RecurringRelease, ApiError> {
+        let url = format!("{}/recurring", API_URL);
+        
+        let response = Request::post(&url)
+            .json(request)?
+            .send()
+            .await?;
+        
+        // Keep the reason the schedule, release or user was rejected so it can be shown
+        if matches!(response.status(), 400 | 401 | 403) {
+            let response: ApiResponse<RecurringRelease> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "The recurring release can't be created".to_string())));
+        }
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let response: ApiResponse<RecurringRelease> = response.json().await?;
+        
+        match response.data {
+            Some(recurring) => Ok(recurring),
+            None => Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "Unknown error creating recurring release".to_string()))),
+        }
+    }
+    
+    // Pause or resume a recurring release
+    pub async fn set_recurring_enabled(id: &str, enabled: bool) -> Result<RecurringRelease, ApiError> {
+        let url = format!("{}/recurring/{}/enabled", API_URL, id);
+        
+        let response = Request::put(&url)
+            .json(&serde_json::json!({ "enabled": enabled }))?
+            .send()
+            .await?;
+        
+        // Not logged in or not allowed to deploy where it releases to
+        if matches!(response.status(), 401 | 403) {
+            let response: ApiResponse<RecurringRelease> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "The recurring release can't be changed".to_string())));
+        }
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let response: ApiResponse<RecurringRelease> = response.json().await?;
+        
+        match response.data {
+            Some(recurring) => Ok(recurring),
+            None => Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "Unknown error updating recurring release".to_string()))),
+        }
+    }
+    
+    pub async fn delete_recurring_release(id: &str) -> Result<(), ApiError> {
+        let url = format!("{}/recurring/{}", API_URL, id);
+        
+        let response = Request::delete(&url)
+            .send()
+            .await?;
+            
+        if matches!(response.status(), 401 | 403) {
+            let response: ApiResponse<RecurringRelease> = response.json().await?;
+            return Err(ApiError::ApiError(response.message.unwrap_or_else(||
+                "The recurring release can't be deleted".to_string())));
+        }
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        Ok(())
+    }
+    
+    // Get every client's items across their environments
+    pub async fn get_inventory_matrix() -> Result<Vec<InventoryRow>, ApiError> {
+        let url = format!("{}/inventory/matrix", API_URL);
+        let response = Request::get(&url).send().await?;
+        
+        if !response.ok() {
+            return Err(ApiError::ApiError(format!("API error: {}", response.status())));
+        }
+        
+        let rows: Vec<InventoryRow> = response.json().await?;
+        Ok(rows)
+    }
+    
     pub async fn get_current_user() -> Result<User, ApiError> {
         let url = format!("{}/users/me", API_URL);
         let response = Request::get(&url).send().await?;
@@ -28,31 +28,75 @@ progress_update() {
     echo "[PROGRESS:app:$1] Application deployment progress update"
 }
 
+# Name the step we're working on
+# Usage: step <name>
+step() {
+    echo "[STEP:$1]"
+}
+
+# Report a problem that doesn't fail the deployment
+# Usage: warn <message>
+warn() {
+    echo "[WARN:$1]"
+}
+
+# Report a result of the deployment
+# Usage: output <key> <value>
+output() {
+    echo "[OUTPUT:$1=$2]"
+}
+
+# Declare something the deployment produced
+# Usage: artifact <name> <location>
+artifact() {
+    echo "[ARTIFACT:$1=$2]"
+}
+
+# Ask a human to confirm, the answer ("yes" or "no") arrives on stdin
+# Usage: confirm <prompt>
+confirm() {
+    echo "[CONFIRM:$1]"
+    local answer
+    read -r answer || answer="no"
+    [[ "$answer" == "yes" ]]
+}
+
 # Log start of the process
 log "Starting application deployment to $ENV environment"
 progress_update 0
 
+# Blend passes the release context as BLEND_* variables
+log "Release: ${BLEND_RELEASE_TITLE:-unknown} (${BLEND_RELEASE_ID:-no id}) for ${BLEND_CLIENT_NAME:-unknown client}, requested by ${BLEND_REQUESTED_BY:-unknown}"
+
 # Simulate pulling latest code (step 1/8)
+step "Checkout"
 log "Pulling latest code from repository..."
 sleep 2
 progress_update 10
 
 # Simulate dependency installation (step 2/8)
+step "Dependencies"
 log "Installing dependencies..."
 if [[ "$ENV" == "production" ]]; then
     log "Using production dependency settings (no dev dependencies)"
 else
     log "Including development dependencies"
+    warn "Development dependencies are installed in $ENV"
 fi
 sleep 3
 progress_update 25
 
 # Simulate building the application (step 3/8)
+step "Build"
 log "Building application for $ENV environment..."
 sleep 3
+BUILD_NUMBER=$((RANDOM % 1000 + 1))
+output "build_number" "$BUILD_NUMBER"
+artifact "bundle" "builds/$ENV/app-$BUILD_NUMBER.tar.gz"
 progress_update 40
 
 # Simulate running tests (step 4/8)
+step "Tests"
 log "Running automated tests..."
 sleep 2
 
@@ -66,22 +110,26 @@ fi
 progress_update 55
 
 # Simulate configuring the environment (step 5/8)
+step "Configuration"
 log "Applying $ENV configuration..."
 sleep 1
 progress_update 65
 
 # Simulate database migrations (step 6/8)
+step "Migrations"
 log "Running database migrations..."
 sleep 2
 progress_update 75
 
 # Simulate restarting services (step 7/8)
+step "Restart"
 log "Stopping application services..."
 sleep 1
 log "Starting application with new version..."
 sleep 2
 
 # Simulate verification (step 8/8)
+step "Verification"
 log "Verifying application health..."
 sleep 2
 
@@ -110,7 +158,9 @@ progress_update 98
 
 # Simulate completion
 log "Application deployment to $ENV completed successfully"
-log "App is now running version 1.$(date +%Y%m%d)"
+VERSION="1.$(date +%Y%m%d)"
+log "App is now running version $VERSION"
+output "version" "$VERSION"
 progress_update 100
 
 exit 0
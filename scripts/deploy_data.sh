@@ -28,21 +28,69 @@ progress_update() {
     echo "[PROGRESS:data:$1] Data deployment progress update"
 }
 
+# Name the step we're working on
+# Usage: step <name>
+step() {
+    echo "[STEP:$1]"
+}
+
+# Report a problem that doesn't fail the deployment
+# Usage: warn <message>
+warn() {
+    echo "[WARN:$1]"
+}
+
+# Report a result of the deployment
+# Usage: output <key> <value>
+output() {
+    echo "[OUTPUT:$1=$2]"
+}
+
+# Declare something the deployment produced
+# Usage: artifact <name> <location>
+artifact() {
+    echo "[ARTIFACT:$1=$2]"
+}
+
+# Ask a human to confirm, the answer ("yes" or "no") arrives on stdin
+# Usage: confirm <prompt>
+confirm() {
+    echo "[CONFIRM:$1]"
+    local answer
+    read -r answer || answer="no"
+    [[ "$answer" == "yes" ]]
+}
+
 # Log start of the process
 log "Starting data deployment to $ENV environment"
 progress_update 0
 
+# Blend passes the release context as BLEND_* variables
+log "Release: ${BLEND_RELEASE_TITLE:-unknown} (${BLEND_RELEASE_ID:-no id}) for ${BLEND_CLIENT_NAME:-unknown client}, requested by ${BLEND_REQUESTED_BY:-unknown}"
+
 # Simulate database backup (step 1/10)
+step "Backup"
 log "Creating database backup..."
 sleep 2
+artifact "backup" "backups/$ENV/data-$(date +%Y%m%d%H%M%S).sql.gz"
 progress_update 10
 
 # Simulate data validation (step 2/10)
+step "Validation"
 log "Validating data structures..."
 sleep 1
 progress_update 20
 
 # Simulate schema migration (step 3/10)
+step "Schema migration"
+
+# Production schema changes need a human to sign off when DATA_CONFIRM_MIGRATIONS=1,
+# without it the script runs unattended
+if [[ "$ENV" == "production" && "${DATA_CONFIRM_MIGRATIONS:-0}" == "1" ]] && ! confirm "Apply schema migrations to production?"; then
+    log "ERROR: Schema migrations were not confirmed"
+    exit 1
+fi
+
 log "Applying schema migrations..."
 log "Creating new tables..."
 sleep 2
@@ -53,13 +101,17 @@ sleep 1
 progress_update 40
 
 # Simulate data migration (step 4/10)
+step "Data migration"
 log "Migrating data to new schema..."
 sleep 2
+output "rows_migrated" "$((RANDOM * 10))"
 progress_update 50
 
 # Simulate data transformation (step 5/10)
+step "Transformation"
 log "Transforming legacy data formats..."
 sleep 2
+warn "Legacy rows without a created date were given today's date"
 
 # Randomly fail sometimes in staging to demonstrate error handling
 if [[ "$ENV" == "staging" && $((RANDOM % 50)) -eq 0 ]]; then
@@ -70,21 +122,25 @@ fi
 progress_update 60
 
 # Simulate data verification (step 6/10)
+step "Verification"
 log "Verifying data integrity..."
 sleep 2
 progress_update 70
 
 # Simulate index creation (step 7/10)
+step "Indexes"
 log "Creating database indexes..."
 sleep 1
 progress_update 80
 
 # Simulate cache warming (step 8/10)
+step "Cache warming"
 log "Warming data caches..."
 sleep 2
 progress_update 90
 
 # Simulate final verification (step 9/10)
+step "Final verification"
 log "Running final verification tests..."
 sleep 1
 
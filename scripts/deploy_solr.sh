@@ -28,31 +28,72 @@ progress_update() {
     echo "[PROGRESS:solr:$1] Solr index rebuild progress update"
 }
 
+# Name the step we're working on
+# Usage: step <name>
+step() {
+    echo "[STEP:$1]"
+}
+
+# Report a problem that doesn't fail the deployment
+# Usage: warn <message>
+warn() {
+    echo "[WARN:$1]"
+}
+
+# Report a result of the deployment
+# Usage: output <key> <value>
+output() {
+    echo "[OUTPUT:$1=$2]"
+}
+
+# Declare something the deployment produced
+# Usage: artifact <name> <location>
+artifact() {
+    echo "[ARTIFACT:$1=$2]"
+}
+
+# Ask a human to confirm, the answer ("yes" or "no") arrives on stdin
+# Usage: confirm <prompt>
+confirm() {
+    echo "[CONFIRM:$1]"
+    local answer
+    read -r answer || answer="no"
+    [[ "$answer" == "yes" ]]
+}
+
 # Log start of the process
 log "Starting Solr index rebuild in $ENV environment"
 progress_update 0
 
+# Blend passes the release context as BLEND_* variables
+log "Release: ${BLEND_RELEASE_TITLE:-unknown} (${BLEND_RELEASE_ID:-no id}) for ${BLEND_CLIENT_NAME:-unknown client}, requested by ${BLEND_REQUESTED_BY:-unknown}"
+
 # Simulate connecting to Solr (step 1/6)
+step "Connect"
 log "Connecting to Solr instance in $ENV..."
 sleep 1
 progress_update 15
 
 # Simulate cleaning old indexes (step 2/6)
+step "Clean indexes"
 log "Cleaning existing indexes..."
 sleep 2
 progress_update 30
 
 # Simulate schema configuration (step 3/6)
+step "Schema"
 log "Configuring Solr schema..."
 sleep 1
 progress_update 40
 
 # Simulate data extraction (step 4/6)
+step "Extract"
 log "Extracting data for indexing..."
 sleep 2
 progress_update 60
 
 # Simulate index building (step 5/6)
+step "Build indexes"
 log "Building Solr indexes..."
 
 # In production, this step takes longer
@@ -69,16 +110,23 @@ fi
 
 log "Optimizing indexes..."
 sleep 1
+output "documents_indexed" "$((RANDOM * 100))"
 progress_update 80
 
 # Simulate index activation (step 6/6)
+step "Activate"
 log "Activating new indexes..."
 sleep 1
+artifact "snapshot" "solr/$ENV/snapshot-$(date +%Y%m%d%H%M%S)"
 progress_update 90
 
 # Simulate verification
+step "Verify"
 log "Verifying search functionality..."
 sleep 1
+if [[ "$ENV" != "production" ]]; then
+    warn "Search relevance checks only run in production"
+fi
 
 # Simulate completion
 log "Solr index rebuilt successfully in $ENV environment"
@@ -0,0 +1,12 @@
+use actix_web::{web, HttpResponse, Responder, get};
+use crate::config;
+
+// List the deployment item types from the registry
+#[get("")]
+async fn get_deployment_items() -> impl Responder {
+    HttpResponse::Ok().json(config::deployment().item_types())
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_deployment_items);
+}
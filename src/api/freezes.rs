@@ -0,0 +1,173 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder, get, post, delete};
+use crate::storage::SledStorage;
+use crate::models::{Environment, FreezeWindow, User};
+use crate::models::user::UserRole;
+use crate::api::users::session_user;
+use crate::scheduler;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use log::{info, error};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateFreezeRequest {
+    pub environment: Environment,
+    #[serde(default)]
+    pub client_id: Option<String>, // Freeze every client when not set
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FreezeResponse {
+    pub success: bool,
+    pub message: Option<String>,
+    pub data: Option<FreezeWindow>,
+}
+
+// Only Admins manage freeze windows, anyone else gets the error response
+fn admin_user(req: &HttpRequest, db: &SledStorage) -> Result<User, HttpResponse> {
+    match session_user(req, db) {
+        Some(user) if user.role == UserRole::Admin => Ok(user),
+        Some(user) => Err(HttpResponse::Forbidden().json(FreezeResponse {
+            success: false,
+            message: Some(format!("{} is not allowed to manage freeze windows, only admins are", user.username)),
+            data: None,
+        })),
+        None => Err(HttpResponse::Unauthorized().json(FreezeResponse {
+            success: false,
+            message: Some("Log in to manage freeze windows".to_string()),
+            data: None,
+        })),
+    }
+}
+
+#[get("")]
+async fn get_freezes(db: web::Data<SledStorage>) -> impl Responder {
+    match db.get_all_freezes() {
+        Ok(freezes) => HttpResponse::Ok().json(freezes),
+        Err(e) => {
+            error!("Failed to get freeze windows: {}", e);
+            HttpResponse::InternalServerError().json(FreezeResponse {
+                success: false,
+                message: Some(format!("Failed to get freeze windows: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+#[post("")]
+async fn create_freeze(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    freeze_data: web::Json<CreateFreezeRequest>,
+) -> impl Responder {
+    let user = match admin_user(&req, &db) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+    
+    if freeze_data.ends_at <= freeze_data.starts_at {
+        return HttpResponse::BadRequest().json(FreezeResponse {
+            success: false,
+            message: Some("Freeze window has to end after it starts".to_string()),
+            data: None,
+        });
+    }
+    
+    if freeze_data.reason.trim().is_empty() {
+        return HttpResponse::BadRequest().json(FreezeResponse {
+            success: false,
+            message: Some("Freeze window needs a reason".to_string()),
+            data: None,
+        });
+    }
+    
+    let freeze = FreezeWindow {
+        id: Uuid::new_v4(),
+        environment: freeze_data.environment.clone(),
+        client_id: freeze_data.client_id.clone().filter(|id| !id.is_empty()),
+        starts_at: freeze_data.starts_at,
+        ends_at: freeze_data.ends_at,
+        reason: freeze_data.reason.clone(),
+        owner: user.username,
+        created_at: Utc::now(),
+    };
+    
+    match db.save_freeze(&freeze) {
+        Ok(_) => {
+            info!("{} created freeze window {} for {:?}: {}", freeze.owner, freeze.id, freeze.environment, freeze.reason);
+            HttpResponse::Created().json(FreezeResponse {
+                success: true,
+                message: Some("Freeze window created successfully".to_string()),
+                data: Some(freeze),
+            })
+        }
+        Err(e) => {
+            error!("Failed to create freeze window: {}", e);
+            HttpResponse::InternalServerError().json(FreezeResponse {
+                success: false,
+                message: Some(format!("Failed to create freeze window: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+#[delete("/{id}")]
+async fn delete_freeze(req: HttpRequest, db: web::Data<SledStorage>, path: web::Path<Uuid>) -> impl Responder {
+    let freeze_id = path.into_inner();
+    
+    let user = match admin_user(&req, &db) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+    
+    match db.get_freeze(&freeze_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(FreezeResponse {
+                success: false,
+                message: Some(format!("Freeze window with ID {} not found", freeze_id)),
+                data: None,
+            });
+        }
+        Err(e) => {
+            error!("Failed to get freeze window {}: {}", freeze_id, e);
+            return HttpResponse::InternalServerError().json(FreezeResponse {
+                success: false,
+                message: Some(format!("Failed to get freeze window: {}", e)),
+                data: None,
+            });
+        }
+    }
+    
+    match db.delete_freeze(&freeze_id) {
+        Ok(_) => {
+            info!("{} deleted freeze window: {}", user.username, freeze_id);
+            // Releases held by the freeze don't have to wait for its end anymore
+            scheduler::reschedule_releases(&db);
+            HttpResponse::Ok().json(FreezeResponse {
+                success: true,
+                message: Some("Freeze window deleted successfully".to_string()),
+                data: None,
+            })
+        }
+        Err(e) => {
+            error!("Failed to delete freeze window {}: {}", freeze_id, e);
+            HttpResponse::InternalServerError().json(FreezeResponse {
+                success: false,
+                message: Some(format!("Failed to delete freeze window: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_freezes)
+        .service(create_freeze)
+        .service(delete_freeze);
+}
@@ -0,0 +1,179 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder, get, post};
+use crate::storage::SledStorage;
+use crate::models::{Environment, InventoryEntry, InventoryRow};
+use crate::models::inventory::VERSION_OUTPUT;
+use crate::api::releases::deploying_user;
+use crate::websocket::server::broadcast_inventory_update;
+use serde::{Deserialize, Serialize};
+use chrono::Utc;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+use log::{info, error};
+
+#[derive(Debug, Deserialize)]
+pub struct InventoryQuery {
+    pub client_id: Option<String>,
+}
+
+// A deployment made outside of blend, such as a development environment updated by CI
+#[derive(Debug, Deserialize)]
+pub struct ReportInventoryRequest {
+    pub client_id: String,
+    pub environment: Environment,
+    pub item: String,
+    pub version: Option<String>,
+    #[serde(default)]
+    pub outputs: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InventoryResponse {
+    pub success: bool,
+    pub message: Option<String>,
+    pub data: Option<InventoryEntry>,
+}
+
+fn get_entries(db: &SledStorage, client_id: Option<&str>) -> Result<Vec<InventoryEntry>, HttpResponse> {
+    match db.get_all_inventory() {
+        Ok(entries) => Ok(entries.into_iter()
+            .filter(|entry| client_id.is_none_or(|id| entry.client_id == id))
+            .collect()),
+        Err(e) => {
+            error!("Failed to get the inventory: {}", e);
+            Err(HttpResponse::InternalServerError().json(InventoryResponse {
+                success: false,
+                message: Some(format!("Failed to get the inventory: {}", e)),
+                data: None,
+            }))
+        }
+    }
+}
+
+// Everything recorded as deployed, optionally for one client
+#[get("")]
+async fn get_inventory(db: web::Data<SledStorage>, query: web::Query<InventoryQuery>) -> impl Responder {
+    match get_entries(&db, query.client_id.as_deref()) {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(response) => response,
+    }
+}
+
+// Each client's items across Development, Staging and Production, with the ones that
+// run different versions marked as drifted
+#[get("/matrix")]
+async fn get_inventory_matrix(db: web::Data<SledStorage>, query: web::Query<InventoryQuery>) -> impl Responder {
+    let entries = match get_entries(&db, query.client_id.as_deref()) {
+        Ok(entries) => entries,
+        Err(response) => return response,
+    };
+
+    let client_names: HashMap<String, String> = match db.get_all_clients() {
+        Ok(clients) => clients.into_iter().map(|client| (client.id.to_string(), client.name)).collect(),
+        Err(e) => {
+            error!("Failed to get clients: {}", e);
+            return HttpResponse::InternalServerError().json(InventoryResponse {
+                success: false,
+                message: Some(format!("Failed to get clients: {}", e)),
+                data: None,
+            });
+        }
+    };
+
+    let mut rows: BTreeMap<(String, String), InventoryRow> = BTreeMap::new();
+    for entry in entries {
+        let client_name = client_names.get(&entry.client_id).cloned().unwrap_or_else(|| entry.client_id.clone());
+        rows.entry((entry.client_id.clone(), entry.item.clone()))
+            .or_insert_with(|| InventoryRow::new(entry.client_id.clone(), client_name, entry.item.clone()))
+            .set(entry);
+    }
+
+    let mut rows: Vec<InventoryRow> = rows.into_values().collect();
+    rows.sort_by(|a, b| a.client_name.cmp(&b.client_name).then_with(|| a.item.cmp(&b.item)));
+    HttpResponse::Ok().json(rows)
+}
+
+// Record a deployment blend didn't run itself
+#[post("")]
+async fn report_inventory(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    report: web::Json<ReportInventoryRequest>,
+) -> impl Responder {
+    let bad_request = |message: String| HttpResponse::BadRequest().json(InventoryResponse {
+        success: false,
+        message: Some(message),
+        data: None,
+    });
+
+    if report.item.trim().is_empty() {
+        return bad_request("Inventory report needs an item".to_string());
+    }
+
+    let client = Uuid::parse_str(&report.client_id).ok()
+        .map(|client_id| db.get_client(&client_id));
+    match client {
+        Some(Ok(Some(_))) => {}
+        Some(Err(e)) => {
+            error!("Failed to get client {}: {}", report.client_id, e);
+            return HttpResponse::InternalServerError().json(InventoryResponse {
+                success: false,
+                message: Some(format!("Failed to get client: {}", e)),
+                data: None,
+            });
+        }
+        _ => return bad_request(format!("Client {} not found", report.client_id)),
+    }
+
+    let user = match deploying_user(&req, &db, &report.environment) {
+        Ok(user) => user,
+        Err(rejection) => {
+            return HttpResponse::build(rejection.status).json(InventoryResponse {
+                success: false,
+                message: Some(rejection.message),
+                data: None,
+            });
+        }
+    };
+
+    let now = Utc::now();
+    let entry = InventoryEntry {
+        client_id: report.client_id.clone(),
+        environment: report.environment.clone(),
+        item: report.item.trim().to_string(),
+        version: report.version.clone().or_else(|| report.outputs.get(VERSION_OUTPUT).cloned()),
+        outputs: report.outputs.clone(),
+        release_id: None,
+        release_title: None,
+        reported_by: Some(user.username),
+        deployed_at: now,
+        since: now,
+        rolled_back_at: None,
+    };
+
+    match db.record_inventory(&entry) {
+        Ok(entry) => {
+            info!("Inventory report: {} of client {} in {:?} is at {}", entry.item, entry.client_id, entry.environment,
+                entry.version.as_deref().unwrap_or("an unknown version"));
+            broadcast_inventory_update(&entry);
+            HttpResponse::Ok().json(InventoryResponse {
+                success: true,
+                message: Some("Deployment recorded in the inventory".to_string()),
+                data: Some(entry),
+            })
+        }
+        Err(e) => {
+            error!("Failed to record inventory report: {}", e);
+            HttpResponse::InternalServerError().json(InventoryResponse {
+                success: false,
+                message: Some(format!("Failed to record inventory report: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_inventory)
+        .service(get_inventory_matrix)
+        .service(report_inventory);
+}
@@ -1,6 +1,12 @@
 pub mod releases;
 pub mod users;
 pub mod clients;
+pub mod deployment_items;
+pub mod freezes;
+pub mod runners;
+pub mod trains;
+pub mod recurring;
+pub mod inventory;
 
 use actix_web::web;
 
@@ -16,5 +22,29 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     .service(
         web::scope("/clients")
             .configure(clients::configure)
+    )
+    .service(
+        web::scope("/deployment-items")
+            .configure(deployment_items::configure)
+    )
+    .service(
+        web::scope("/freezes")
+            .configure(freezes::configure)
+    )
+    .service(
+        web::scope("/runners")
+            .configure(runners::configure)
+    )
+    .service(
+        web::scope("/trains")
+            .configure(trains::configure)
+    )
+    .service(
+        web::scope("/recurring")
+            .configure(recurring::configure)
+    )
+    .service(
+        web::scope("/inventory")
+            .configure(inventory::configure)
     );
 }
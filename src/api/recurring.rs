@@ -0,0 +1,312 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder, get, post, put, delete};
+use crate::storage::SledStorage;
+use crate::models::{RecurringRelease, User};
+use crate::models::recurring::{parse_schedule, parse_time_zone};
+use crate::api::releases::{deploying_user, parse_environment, validate_deployment_items, validate_deployment_path};
+use crate::websocket::server::broadcast_recurring_update;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::Utc;
+use std::collections::HashMap;
+use log::{info, error};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRecurringRequest {
+    pub title: String,
+    pub client_id: String,
+    pub schedule: String, // Five field cron expression or RRULE
+    pub time_zone: String,
+    pub current_environment: String,
+    pub target_environment: String,
+    pub deployment_items: Vec<String>,
+    #[serde(default)]
+    pub skip_staging: bool,
+    #[serde(default)]
+    pub item_timeouts: HashMap<String, u64>, // Per-item timeout overrides in seconds
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EnableRecurringRequest {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecurringResponse {
+    pub success: bool,
+    pub message: Option<String>,
+    pub data: Option<RecurringRelease>,
+}
+
+// A recurring release and the logged in user changing it, who has to be allowed to
+// deploy to its target environment just like the user creating one
+fn recurring_user(req: &HttpRequest, db: &SledStorage, recurring_id: Uuid) -> Result<(RecurringRelease, User), HttpResponse> {
+    let recurring = match db.get_recurring(&recurring_id) {
+        Ok(Some(recurring)) => recurring,
+        Ok(None) => {
+            return Err(HttpResponse::NotFound().json(RecurringResponse {
+                success: false,
+                message: Some(format!("Recurring release with ID {} not found", recurring_id)),
+                data: None,
+            }));
+        }
+        Err(e) => {
+            error!("Failed to get recurring release {}: {}", recurring_id, e);
+            return Err(HttpResponse::InternalServerError().json(RecurringResponse {
+                success: false,
+                message: Some(format!("Failed to get recurring release: {}", e)),
+                data: None,
+            }));
+        }
+    };
+
+    match deploying_user(req, db, &recurring.target_environment) {
+        Ok(user) => Ok((recurring, user)),
+        Err(rejection) => Err(HttpResponse::build(rejection.status).json(RecurringResponse {
+            success: false,
+            message: Some(rejection.message),
+            data: None,
+        })),
+    }
+}
+
+#[get("")]
+async fn get_recurring_releases(db: web::Data<SledStorage>) -> impl Responder {
+    match db.get_all_recurring() {
+        Ok(recurring) => HttpResponse::Ok().json(recurring),
+        Err(e) => {
+            error!("Failed to get recurring releases: {}", e);
+            HttpResponse::InternalServerError().json(RecurringResponse {
+                success: false,
+                message: Some(format!("Failed to get recurring releases: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+#[get("/{id}")]
+async fn get_recurring_release(db: web::Data<SledStorage>, path: web::Path<Uuid>) -> impl Responder {
+    let recurring_id = path.into_inner();
+
+    match db.get_recurring(&recurring_id) {
+        Ok(Some(recurring)) => HttpResponse::Ok().json(recurring),
+        Ok(None) => HttpResponse::NotFound().json(RecurringResponse {
+            success: false,
+            message: Some(format!("Recurring release with ID {} not found", recurring_id)),
+            data: None,
+        }),
+        Err(e) => {
+            error!("Failed to get recurring release {}: {}", recurring_id, e);
+            HttpResponse::InternalServerError().json(RecurringResponse {
+                success: false,
+                message: Some(format!("Failed to get recurring release: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+// Create a recurring release. Its releases are cleared for deployment as they're
+// created, so only users allowed to deploy to its target environment can set one up.
+#[post("")]
+async fn create_recurring_release(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    recurring_data: web::Json<CreateRecurringRequest>,
+) -> impl Responder {
+    let bad_request = |message: String| HttpResponse::BadRequest().json(RecurringResponse {
+        success: false,
+        message: Some(message),
+        data: None,
+    });
+
+    if recurring_data.title.trim().is_empty() {
+        return bad_request("Recurring release needs a title".to_string());
+    }
+
+    let current_env = match parse_environment(&recurring_data.current_environment) {
+        Ok(env) => env,
+        Err(e) => return bad_request(e),
+    };
+
+    let target_env = match parse_environment(&recurring_data.target_environment) {
+        Ok(env) => env,
+        Err(e) => return bad_request(e),
+    };
+
+    if let Err(e) = validate_deployment_items(&recurring_data.deployment_items) {
+        return bad_request(e);
+    }
+
+    if let Err(e) = validate_deployment_path(&current_env, &target_env, recurring_data.skip_staging) {
+        return bad_request(e);
+    }
+
+    if let Err(e) = parse_schedule(&recurring_data.schedule) {
+        return bad_request(e);
+    }
+
+    if let Err(e) = parse_time_zone(&recurring_data.time_zone) {
+        return bad_request(e);
+    }
+
+    let client = Uuid::parse_str(&recurring_data.client_id).ok()
+        .map(|client_id| db.get_client(&client_id));
+    match client {
+        Some(Ok(Some(_))) => {}
+        Some(Err(e)) => {
+            error!("Failed to get client {}: {}", recurring_data.client_id, e);
+            return HttpResponse::InternalServerError().json(RecurringResponse {
+                success: false,
+                message: Some(format!("Failed to get client: {}", e)),
+                data: None,
+            });
+        }
+        _ => return bad_request(format!("Client {} not found", recurring_data.client_id)),
+    }
+
+    let user = match deploying_user(&req, &db, &target_env) {
+        Ok(user) => user,
+        Err(rejection) => {
+            return HttpResponse::build(rejection.status).json(RecurringResponse {
+                success: false,
+                message: Some(rejection.message),
+                data: None,
+            });
+        }
+    };
+
+    let now = Utc::now();
+    let mut recurring = RecurringRelease {
+        id: Uuid::new_v4(),
+        title: recurring_data.title.clone(),
+        client_id: recurring_data.client_id.clone(),
+        schedule: recurring_data.schedule.trim().to_string(),
+        time_zone: recurring_data.time_zone.trim().to_string(),
+        current_environment: current_env,
+        target_environment: target_env,
+        deployment_items: recurring_data.deployment_items.clone(),
+        skip_staging: recurring_data.skip_staging,
+        item_timeouts: recurring_data.item_timeouts.clone(),
+        enabled: true,
+        next_run_at: None,
+        occurrences: Vec::new(),
+        created_by: user.username,
+        created_at: now,
+    };
+
+    recurring.next_run_at = recurring.next_occurrence(now);
+    if recurring.next_run_at.is_none() {
+        return bad_request(format!("Schedule {} never comes due", recurring.schedule));
+    }
+
+    match db.save_recurring(&recurring) {
+        Ok(_) => {
+            info!("Created recurring release {} ({} in {})", recurring.id, recurring.schedule, recurring.time_zone);
+            broadcast_recurring_update(&recurring);
+            HttpResponse::Created().json(RecurringResponse {
+                success: true,
+                message: Some("Recurring release created successfully".to_string()),
+                data: Some(recurring),
+            })
+        }
+        Err(e) => {
+            error!("Failed to create recurring release: {}", e);
+            HttpResponse::InternalServerError().json(RecurringResponse {
+                success: false,
+                message: Some(format!("Failed to create recurring release: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+// Pause or resume a recurring release. A resumed one goes on with its next occurrence
+// from now, the ones it missed while paused aren't made up for.
+#[put("/{id}/enabled")]
+async fn set_recurring_enabled(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<Uuid>,
+    enable_data: web::Json<EnableRecurringRequest>,
+) -> impl Responder {
+    let recurring_id = path.into_inner();
+    let enabled = enable_data.enabled;
+    let now = Utc::now();
+
+    // An enabled definition creates and deploys releases on its own
+    let user = match recurring_user(&req, &db, recurring_id) {
+        Ok((_, user)) => user,
+        Err(response) => return response,
+    };
+
+    match db.update_recurring(&recurring_id, |recurring| {
+        recurring.enabled = enabled;
+        recurring.next_run_at = if enabled { recurring.next_occurrence(now) } else { None };
+    }) {
+        Ok(Some(recurring)) => {
+            info!("{} {} recurring release {}", user.username, if enabled { "enabled" } else { "disabled" }, recurring.title);
+            broadcast_recurring_update(&recurring);
+            HttpResponse::Ok().json(RecurringResponse {
+                success: true,
+                message: Some(format!("Recurring release {}", if enabled { "enabled" } else { "disabled" })),
+                data: Some(recurring),
+            })
+        }
+        Ok(None) => HttpResponse::NotFound().json(RecurringResponse {
+            success: false,
+            message: Some(format!("Recurring release with ID {} not found", recurring_id)),
+            data: None,
+        }),
+        Err(e) => {
+            error!("Failed to update recurring release {}: {}", recurring_id, e);
+            HttpResponse::InternalServerError().json(RecurringResponse {
+                success: false,
+                message: Some(format!("Failed to update recurring release: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+// Delete a recurring release. Releases it already created stay.
+#[delete("/{id}")]
+async fn delete_recurring_release(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let recurring_id = path.into_inner();
+
+    let user = match recurring_user(&req, &db, recurring_id) {
+        Ok((_, user)) => user,
+        Err(response) => return response,
+    };
+
+    match db.delete_recurring(&recurring_id) {
+        Ok(_) => {
+            info!("{} deleted recurring release: {}", user.username, recurring_id);
+            HttpResponse::Ok().json(RecurringResponse {
+                success: true,
+                message: Some("Recurring release deleted successfully".to_string()),
+                data: None,
+            })
+        }
+        Err(e) => {
+            error!("Failed to delete recurring release {}: {}", recurring_id, e);
+            HttpResponse::InternalServerError().json(RecurringResponse {
+                success: false,
+                message: Some(format!("Failed to delete recurring release: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_recurring_releases)
+        .service(create_recurring_release)
+        .service(get_recurring_release)
+        .service(set_recurring_enabled)
+        .service(delete_recurring_release);
+}
@@ -1,9 +1,16 @@
-use actix_web::{web, HttpResponse, Responder, get, post, put, delete};
-use crate::models::{Release, Environment, ReleaseStatus, DeploymentItem};
+use actix_web::{web, HttpRequest, HttpResponse, Responder, get, post, put, delete};
+use actix_web::http::StatusCode;
+use crate::models::{Approval, ApprovalRequest, Release, Environment, ReleaseStatus, DeploymentItem, LogLine, User};
 use crate::storage::SledStorage;
+use crate::scheduler;
+use crate::config;
+use crate::api::users::session_user;
+use crate::models::user::UserRole;
+use crate::websocket::server::{broadcast_approval_update, broadcast_release_update};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use log::{info, error};
 
 #[derive(Debug, Deserialize)]
@@ -16,6 +23,10 @@ pub struct CreateReleaseRequest {
     pub scheduled_at: DateTime<Utc>,
     #[serde(default)]
     pub skip_staging: bool, // Added skip_staging field
+    #[serde(default)]
+    pub item_timeouts: HashMap<String, u64>, // Per-item timeout overrides in seconds
+    #[serde(default)]
+    pub override_freeze: bool, // Admins only, schedule the release into a freeze window anyway
 }
 
 #[derive(Debug, Serialize)]
@@ -25,8 +36,89 @@ pub struct ReleaseResponse {
     pub data: Option<Release>,
 }
 
+// Default and maximum number of log lines returned per page
+const DEFAULT_LOG_PAGE_SIZE: usize = 200;
+const MAX_LOG_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmItemRequest {
+    pub approved: bool, // Answer to the script's confirmation request
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompleteManualStepRequest {
+    pub done: bool, // false marks the step, and with it the item, as failed
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApprovalAnswer {
+    pub approved: bool, // false rejects the deployment
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DryRunRequest {
+    #[serde(default)]
+    pub environment: Option<Environment>, // Defaults to where the release deploys next
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogPageQuery {
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,
+    pub run: Option<u32>, // Only return lines from this run of the item
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RerunQuery {
+    #[serde(default)]
+    pub override_freeze: bool, // Admins only
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogPage {
+    pub item_name: String,
+    pub runs: u32,
+    pub total: usize, // Number of stored lines matching the query
+    pub dropped: usize, // Older lines removed by the log size cap
+    pub offset: usize,
+    pub lines: Vec<LogLine>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogPageResponse {
+    pub success: bool,
+    pub message: Option<String>,
+    pub data: Option<LogPage>,
+}
+
+// A change a release can't take, with the HTTP status to answer it with
+#[derive(Debug)]
+pub struct Rejection {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl Rejection {
+    fn new(status: StatusCode, message: String) -> Self {
+        Self { status, message }
+    }
+    
+    pub fn response(&self) -> HttpResponse {
+        HttpResponse::build(self.status).json(ReleaseResponse {
+            success: false,
+            message: Some(self.message.clone()),
+            data: None,
+        })
+    }
+}
+
 // Convert string to Environment enum
-fn parse_environment(env_str: &str) -> Result<Environment, String> {
+pub fn parse_environment(env_str: &str) -> Result<Environment, String> {
     match env_str.to_lowercase().as_str() {
         "development" => Ok(Environment::Development),
         "staging" => Ok(Environment::Staging),
@@ -35,8 +127,98 @@ fn parse_environment(env_str: &str) -> Result<Environment, String> {
     }
 }
 
+// Make sure every requested deployment item is a registered item type
+pub fn validate_deployment_items(items: &[String]) -> Result<(), String> {
+    let registry = config::deployment();
+    match items.iter().find(|name| registry.item(name).is_none()) {
+        Some(name) => Err(format!("Unknown deployment item type: {}", name)),
+        None => Ok(()),
+    }
+}
+
+// Check valid deployment path
+pub fn validate_deployment_path(current_env: &Environment, target_env: &Environment, skip_staging: bool) -> Result<(), String> {
+    match (current_env, target_env, skip_staging) {
+        // Allow direct path if skip_staging is true
+        (Environment::Development, Environment::Production, true) => Ok(()),
+        // Standard paths
+        (Environment::Development, Environment::Staging, _) => Ok(()),
+        (Environment::Development, Environment::Production, false) => Ok(()),
+        (Environment::Staging, Environment::Production, _) => Ok(()),
+        _ => Err(format!(
+            "Invalid deployment path: {:?} to {:?}{}",
+            current_env,
+            target_env,
+            if skip_staging { " (skip staging)" } else { "" }
+        )),
+    }
+}
+
+// Reject scheduling a deployment into a freeze window unless an Admin overrides it.
+// Returns the Admin who overrode the freeze, or None if the slot isn't frozen.
+pub fn check_freeze(
+    req: &HttpRequest,
+    db: &SledStorage,
+    environment: &Environment,
+    client_id: &str,
+    scheduled_at: DateTime<Utc>,
+    override_freeze: bool,
+) -> Result<Option<String>, Rejection> {
+    // Releases scheduled in the past deploy right away
+    let deploy_at = scheduled_at.max(Utc::now());
+    let freeze = match db.find_freeze(environment, client_id, deploy_at) {
+        Ok(Some(freeze)) => freeze,
+        Ok(None) => return Ok(None),
+        Err(e) => {
+            error!("Failed to check freeze windows: {}", e);
+            return Err(Rejection::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to check freeze windows: {}", e),
+            ));
+        }
+    };
+    
+    let message = format!("{:?} is frozen from {} until {}: {} (owner: {})",
+        freeze.environment,
+        freeze.starts_at.format("%Y-%m-%d %H:%M UTC"),
+        freeze.ends_at.format("%Y-%m-%d %H:%M UTC"),
+        freeze.reason,
+        freeze.owner);
+    
+    if !override_freeze {
+        return Err(Rejection::new(StatusCode::CONFLICT, message));
+    }
+    
+    match session_user(req, db) {
+        Some(user) if user.role == UserRole::Admin => {
+            info!("{} overrode freeze {}: {}", user.username, freeze.id, message);
+            Ok(Some(user.username))
+        }
+        _ => Err(Rejection::new(
+            StatusCode::FORBIDDEN,
+            format!("Only admins can override a freeze. {}", message),
+        )),
+    }
+}
+
+// The logged in user deploying to an environment. Anonymous visitors and users whose
+// role doesn't allow the environment are rejected.
+pub fn deploying_user(req: &HttpRequest, db: &SledStorage, environment: &Environment) -> Result<User, Rejection> {
+    match session_user(req, db) {
+        Some(user) if user.can_deploy_to(environment) => Ok(user),
+        Some(user) => Err(Rejection::new(
+            StatusCode::FORBIDDEN,
+            format!("{} is not allowed to deploy to {:?}", user.username, environment),
+        )),
+        None => Err(Rejection::new(
+            StatusCode::UNAUTHORIZED,
+            format!("Log in to deploy to {:?}", environment),
+        )),
+    }
+}
+
 // Check if client already has an active release in the pipeline
-async fn check_client_release_exists(
+pub async fn check_client_release_exists(
     db: &SledStorage,
     client_id: &str,
 ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
@@ -47,6 +229,7 @@ async fn check_client_release_exists(
         && (r.status == ReleaseStatus::InDevelopment || 
             r.status == ReleaseStatus::DeployingToStaging || 
             r.status == ReleaseStatus::ReadyToTestInStaging || 
+            r.status == ReleaseStatus::AwaitingApproval || 
             r.status == ReleaseStatus::DeployingToProduction || 
             r.status == ReleaseStatus::ReadyToTestInProduction)
     }))
@@ -91,6 +274,7 @@ async fn get_release(db: web::Data<SledStorage>, path: web::Path<Uuid>) -> impl
 
 #[post("")]
 async fn create_release(
+    req: HttpRequest,
     db: web::Data<SledStorage>,
     release_data: web::Json<CreateReleaseRequest>,
 ) -> impl Responder {
@@ -116,29 +300,35 @@ async fn create_release(
             });
         }
     };
+    
+    if let Err(e) = validate_deployment_items(&release_data.deployment_items) {
+        return HttpResponse::BadRequest().json(ReleaseResponse {
+            success: false,
+            message: Some(e),
+            data: None,
+        });
+    }
 
-    // Check valid deployment path
-    match (&current_env, &target_env, release_data.skip_staging) {
-        // Allow direct path if skip_staging is true
-        (Environment::Development, Environment::Production, true) => {}
-        // Standard paths
-        (Environment::Development, Environment::Staging, _) => {}
-        (Environment::Development, Environment::Production, false) => {}
-        (Environment::Staging, Environment::Production, _) => {}
-        _ => {
-            return HttpResponse::BadRequest().json(ReleaseResponse {
-                success: false,
-                message: Some(format!(
-                    "Invalid deployment path: {:?} to {:?}{}",
-                    current_env, 
-                    target_env,
-                    if release_data.skip_staging { " (skip staging)" } else { "" }
-                )),
-                data: None,
-            });
-        }
+    if let Err(e) = validate_deployment_path(&current_env, &target_env, release_data.skip_staging) {
+        return HttpResponse::BadRequest().json(ReleaseResponse {
+            success: false,
+            message: Some(e),
+            data: None,
+        });
     }
 
+    let freeze_override_by = match check_freeze(
+        &req,
+        &db,
+        &target_env,
+        &release_data.client_id,
+        release_data.scheduled_at,
+        release_data.override_freeze,
+    ) {
+        Ok(overridden_by) => overridden_by,
+        Err(rejection) => return rejection.response(),
+    };
+
     // Check if client already has an active release
     match check_client_release_exists(&db, &release_data.client_id).await {
         Ok(true) => {
@@ -163,16 +353,18 @@ async fn create_release(
     }
 
     // Create new release
-    let release = Release::new(
+    let mut release = Release::new(
         release_data.title.clone(),
         release_data.client_id.clone(),
         current_env,
         target_env,
         release_data.deployment_items.clone(),
         release_data.scheduled_at,
-        "unknown".to_string(), // TODO: Get from authenticated user
+        session_user(&req, &db).map(|user| user.username).unwrap_or_else(|| "unknown".to_string()),
         release_data.skip_staging, // Pass the skip_staging flag
     );
+    release.item_timeouts = release_data.item_timeouts.clone();
+    release.freeze_override_by = freeze_override_by;
 
     info!("NEW RELEASE SCHEDULED AT: {}", release_data.scheduled_at);
 
@@ -180,6 +372,7 @@ async fn create_release(
     match db.save_release(&release) {
         Ok(_) => {
             info!("Created new release: {}", release.id);
+            scheduler::schedule_release(&release);
             HttpResponse::Created().json(ReleaseResponse {
                 success: true,
                 message: Some(format!("Release created successfully")),
@@ -199,6 +392,7 @@ async fn create_release(
 
 #[put("/{id}")]
 async fn update_release(
+    req: HttpRequest,
     db: web::Data<SledStorage>,
     path: web::Path<Uuid>,
     release_data: web::Json<CreateReleaseRequest>,
@@ -248,6 +442,34 @@ async fn update_release(
         }
     };
     
+    if let Err(e) = validate_deployment_items(&release_data.deployment_items) {
+        return HttpResponse::BadRequest().json(ReleaseResponse {
+            success: false,
+            message: Some(e),
+            data: None,
+        });
+    }
+    
+    // Only a new slot has to be checked against the freeze windows
+    let slot_changed = existing_release.scheduled_at != release_data.scheduled_at
+        || existing_release.target_environment != target_env
+        || existing_release.client_id != release_data.client_id;
+    let freeze_override_by = if slot_changed {
+        match check_freeze(
+            &req,
+            &db,
+            &target_env,
+            &release_data.client_id,
+            release_data.scheduled_at,
+            release_data.override_freeze,
+        ) {
+            Ok(overridden_by) => overridden_by,
+            Err(rejection) => return rejection.response(),
+        }
+    } else {
+        existing_release.freeze_override_by.clone()
+    };
+    
     // Create updated release
     let updated_release = Release {
         id: release_id,
@@ -262,17 +484,27 @@ async fn update_release(
         status: existing_release.status.clone(), // Keep the current status
         progress: existing_release.progress, // Keep the current progress
         skip_staging: release_data.skip_staging, // Update the skip_staging flag
+        deployment_environment: existing_release.deployment_environment,
+        queue_position: existing_release.queue_position,
+        freeze_override_by,
+        dry_runs: existing_release.dry_runs,
+        approval_request: existing_release.approval_request,
+        approvals: existing_release.approvals,
+        health_checks: existing_release.health_checks,
+        train_id: existing_release.train_id,
+        // Keep the original timeout overrides unless new ones are provided
+        item_timeouts: if release_data.item_timeouts.is_empty() {
+            existing_release.item_timeouts
+        } else {
+            release_data.item_timeouts.clone()
+        },
         // Update deployment items if provided, otherwise keep original
         deployment_items: if release_data.deployment_items.is_empty() {
             existing_release.deployment_items
         } else {
             release_data.deployment_items.iter().map(|name| {
-                DeploymentItem {
-                    name: name.clone(),
-                    status: existing_release.status.clone(), // Set item status to match release status
-                    logs: Vec::new(),
-                    error: None,
-                }
+                // Set item status to match release status
+                DeploymentItem::new(name.clone(), existing_release.status.clone())
             }).collect()
         },
     };
@@ -281,6 +513,7 @@ async fn update_release(
     match db.save_release(&updated_release) {
         Ok(_) => {
             info!("Updated release: {}", release_id);
+            scheduler::schedule_release(&updated_release);
             HttpResponse::Ok().json(ReleaseResponse {
                 success: true,
                 message: Some(format!("Release updated successfully")),
@@ -326,6 +559,7 @@ async fn delete_release(db: web::Data<SledStorage>, path: web::Path<Uuid>) -> im
     match db.delete_release(&release_id) {
         Ok(_) => {
             info!("Deleted release: {}", release_id);
+            scheduler::unschedule_release(release_id);
             HttpResponse::Ok().json(ReleaseResponse {
                 success: true,
                 message: Some(format!("Release deleted successfully")),
@@ -343,44 +577,25 @@ async fn delete_release(db: web::Data<SledStorage>, path: web::Path<Uuid>) -> im
     }
 }
 
-// Update release status
-#[put("/{id}/status")]
-async fn update_release_status(
-    db: web::Data<SledStorage>,
-    path: web::Path<Uuid>,
-    status_update: web::Json<serde_json::Value>,
-) -> impl Responder {
-    let release_id = path.into_inner();
-    
+// Move a release to a new status, or on to its next one for "clear". A release cleared
+// into a deployment is checked against the user's role, the freeze windows and the
+// environment's approval rule.
+pub fn change_release_status(
+    req: &HttpRequest,
+    db: &SledStorage,
+    release_id: Uuid,
+    status_str: &str,
+    override_freeze: bool,
+) -> Result<Release, Rejection> {
     // Get the release
     let mut release = match db.get_release(&release_id) {
         Ok(Some(release)) => release,
         Ok(None) => {
-            return HttpResponse::NotFound().json(ReleaseResponse {
-                success: false,
-                message: Some(format!("Release with ID {} not found", release_id)),
-                data: None,
-            });
+            return Err(Rejection::new(StatusCode::NOT_FOUND, format!("Release with ID {} not found", release_id)));
         }
         Err(e) => {
             error!("Failed to get release {}: {}", release_id, e);
-            return HttpResponse::InternalServerError().json(ReleaseResponse {
-                success: false,
-                message: Some(format!("Failed to get release: {}", e)),
-                data: None,
-            });
-        }
-    };
-    
-    // Get the new status
-    let status_str = match status_update.get("status") {
-        Some(serde_json::Value::String(s)) => s.as_str(),
-        _ => {
-            return HttpResponse::BadRequest().json(ReleaseResponse {
-                success: false,
-                message: Some("Invalid or missing status in request".to_string()),
-                data: None,
-            });
+            return Err(Rejection::new(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get release: {}", e)));
         }
     };
     
@@ -398,11 +613,10 @@ async fn update_release_status(
             info!("Release {} status cleared. Moving to {:?} with progress reset to 0%", 
                   release_id, release.status);
         } else {
-            return HttpResponse::BadRequest().json(ReleaseResponse {
-                success: false,
-                message: Some("Release cannot be cleared in its current state".to_string()),
-                data: None,
-            });
+            return Err(Rejection::new(
+                StatusCode::BAD_REQUEST,
+                "Release cannot be cleared in its current state".to_string(),
+            ));
         }
     } else {
         // For direct status updates (less common)
@@ -411,27 +625,82 @@ async fn update_release_status(
             "ClearedInDevelopment" => ReleaseStatus::ClearedInDevelopment,
             "WaitingForStaging" => ReleaseStatus::WaitingForStaging,
             "WaitingForProduction" => ReleaseStatus::WaitingForProduction,
-            "DeployingToStaging" => ReleaseStatus::DeployingToStaging,
             "ReadyToTestInStaging" => ReleaseStatus::ReadyToTestInStaging,
             "ClearedInStaging" => ReleaseStatus::ClearedInStaging,
             "WaitingForProductionFromStaging" => ReleaseStatus::WaitingForProductionFromStaging,
-            "DeployingToProduction" => ReleaseStatus::DeployingToProduction,
             "ReadyToTestInProduction" => ReleaseStatus::ReadyToTestInProduction,
             "ClearedInProduction" => ReleaseStatus::ClearedInProduction,
             "Error" => ReleaseStatus::Error,
             "Blocked" => ReleaseStatus::Blocked,
+            "Cancelled" => ReleaseStatus::Cancelled,
+            // Only the scheduler starts deployments and rollbacks, a release gets there
+            // through a Waiting status and the checks below
+            "DeployingToStaging" | "DeployingToProduction" | "AwaitingApproval" | "RollingBack" | "RolledBack" => {
+                return Err(Rejection::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("{} is set by the scheduler, clear the release instead", status_str),
+                ));
+            }
             _ => {
-                return HttpResponse::BadRequest().json(ReleaseResponse {
-                    success: false,
-                    message: Some(format!("Invalid status: {}", status_str)),
-                    data: None,
-                });
+                return Err(Rejection::new(StatusCode::BAD_REQUEST, format!("Invalid status: {}", status_str)));
             }
         };
     }
     
-    // If the status changed, update all deployment items to match
     if old_status != release.status {
+        if let Some(environment) = release.pending_deployment_environment() {
+            match scheduler::train::stopped_train(db, &release) {
+                Ok(Some(train)) => {
+                    return Err(Rejection::new(
+                        StatusCode::CONFLICT,
+                        format!("Release train {} was stopped: {}", train.title,
+                            train.stopped.map(|stop| stop.reason).unwrap_or_default()),
+                    ));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    error!("Failed to get the train of release {}: {}", release_id, e);
+                    return Err(Rejection::new(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get release train: {}", e)));
+                }
+            }
+            
+            let user = deploying_user(req, db, &environment)?;
+            
+            release.freeze_override_by = check_freeze(
+                req,
+                db,
+                &environment,
+                &release.client_id,
+                release.scheduled_at,
+                override_freeze,
+            )?;
+            
+            if let Some(rule) = config::deployment().approval_rule(environment.script_name()) {
+                info!("Release {} needs {} approvals before it deploys to {:?}", release_id, rule.required, environment);
+                let target_status = release.status.clone();
+                release.request_approval(ApprovalRequest {
+                    environment,
+                    target_status,
+                    previous_status: old_status.clone(),
+                    required: rule.required,
+                    roles: rule.roles.clone(),
+                    allow_author: rule.allow_author,
+                    requested_by: Some(user.username),
+                    requested_at: Utc::now(),
+                    rerun_item: None,
+                });
+            }
+        }
+    }
+    
+    // Setting any other status drops a pending approval request
+    if release.status != ReleaseStatus::AwaitingApproval {
+        release.approval_request = None;
+    }
+    
+    // If the status changed, update all deployment items to match. Items of a release
+    // awaiting approval keep theirs until it's approved.
+    if old_status != release.status && release.status != ReleaseStatus::AwaitingApproval {
         for item in release.deployment_items.iter_mut() {
             // Only update non-error items to match the release status
             if item.status != ReleaseStatus::Error {
@@ -441,35 +710,86 @@ async fn update_release_status(
     }
 
     // Save the updated release
-    match db.save_release(&release) {
-        Ok(_) => {
-            info!("Updated release status: {} to {:?}", release_id, release.status);
+    if let Err(e) = db.save_release(&release) {
+        error!("Failed to update release status {}: {}", release_id, e);
+        return Err(Rejection::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to update release status: {}", e),
+        ));
+    }
+    
+    info!("Updated release status: {} to {:?}", release_id, release.status);
+    scheduler::schedule_release(&release);
+    if old_status != release.status
+        && (release.status == ReleaseStatus::AwaitingApproval || old_status == ReleaseStatus::AwaitingApproval)
+    {
+        broadcast_approval_update(&release);
+    }
+    Ok(release)
+}
+
+// Update release status
+#[put("/{id}/status")]
+async fn update_release_status(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<Uuid>,
+    status_update: web::Json<serde_json::Value>,
+) -> impl Responder {
+    let release_id = path.into_inner();
+    
+    // Get the new status
+    let status_str = match status_update.get("status") {
+        Some(serde_json::Value::String(s)) => s.as_str(),
+        _ => {
+            return HttpResponse::BadRequest().json(ReleaseResponse {
+                success: false,
+                message: Some("Invalid or missing status in request".to_string()),
+                data: None,
+            });
+        }
+    };
+    
+    let override_freeze = status_update.get("override_freeze")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    
+    match change_release_status(&req, &db, release_id, status_str, override_freeze) {
+        Ok(release) => {
             HttpResponse::Ok().json(ReleaseResponse {
                 success: true,
                 message: Some(format!("Release status updated successfully")),
                 data: Some(release),
             })
         }
-        Err(e) => {
-            error!("Failed to update release status {}: {}", release_id, e);
-            HttpResponse::InternalServerError().json(ReleaseResponse {
-                success: false,
-                message: Some(format!("Failed to update release status: {}", e)),
-                data: None,
-            })
-        }
+        Err(rejection) => rejection.response(),
     }
 }
 
 #[post("/{id}/rerun/{item_name}")]
 async fn rerun_deployment_item(
+    req: HttpRequest,
     db: web::Data<SledStorage>,
     path: web::Path<(Uuid, String)>,
+    query: web::Query<RerunQuery>,
 ) -> impl Responder {
     let (release_id, item_name) = path.into_inner();
     
+    // A release that is being deployed or rolled back reruns nothing until that's done.
+    // Holding the claim keeps the scheduler off the release until the rerun is stored.
+    let active = match scheduler::claim_release(release_id) {
+        Some(active) => active,
+        None => {
+            return HttpResponse::Conflict().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Release {} is being deployed, wait until it finishes", release_id)),
+                data: None,
+            });
+        }
+    };
+    
     // Get the release
-    let mut release = match db.get_release(&release_id) {
+    let release = match db.get_release(&release_id) {
         Ok(Some(release)) => release,
         Ok(None) => {
             return HttpResponse::NotFound().json(ReleaseResponse {
@@ -500,30 +820,100 @@ async fn rerun_deployment_item(
         }
     };
     
-    // Reset status to deploying based on current environment
-    let new_status = match release.current_environment {
-        Environment::Development => ReleaseStatus::DeployingToStaging,
-        Environment::Staging => ReleaseStatus::DeployingToProduction,
-        Environment::Production => ReleaseStatus::DeployingToProduction,
+    if !release.can_rerun_items() {
+        return HttpResponse::BadRequest().json(ReleaseResponse {
+            success: false,
+            message: Some(format!("Release cannot rerun items in its current state: {:?}", release.status)),
+            data: None,
+        });
+    }
+    
+    // A rerun deploys again, so it goes through the same checks as clearing the
+    // release into the environment it was last deployed to
+    let environment = release.last_deployment_environment();
+    let new_status = environment.deploying_status();
+    
+    let user = match deploying_user(&req, &db, &environment) {
+        Ok(user) => user,
+        Err(rejection) => return rejection.response(),
+    };
+    
+    let freeze_override_by = match check_freeze(&req, &db, &environment, &release.client_id, Utc::now(), query.override_freeze) {
+        Ok(freeze_override_by) => freeze_override_by,
+        Err(rejection) => return rejection.response(),
     };
     
-    // Update item status and clear logs
-    let item = &mut release.deployment_items[item_index];
-    item.status = new_status;
-    item.logs = Vec::new();
-    item.error = None;
+    let approval_rule = config::deployment().approval_rule(environment.script_name());
+    
+    // Reset the item, or wait for the approvals the environment needs first. Its log
+    // is kept so the history of earlier runs survives.
+    let updated = db.update_release(&release_id, |release| {
+        release.freeze_override_by = freeze_override_by.clone();
+        release.deployment_environment = Some(environment.clone());
+        
+        if let Some(rule) = approval_rule {
+            release.request_approval(ApprovalRequest {
+                environment: environment.clone(),
+                target_status: new_status.clone(),
+                previous_status: release.status.clone(),
+                required: rule.required,
+                roles: rule.roles.clone(),
+                allow_author: rule.allow_author,
+                requested_by: Some(user.username.clone()),
+                requested_at: Utc::now(),
+                rerun_item: Some(item_name.clone()),
+            });
+            return;
+        }
+        
+        if let Some(item) = release.deployment_items.get_mut(item_index) {
+            item.status = new_status.clone();
+            item.error = None;
+            item.progress = 0.0;
+        }
+        
+        // The release is deploying again until the rerun finishes
+        release.status = new_status.clone();
+        release.progress = release.calculate_progress();
+    });
     
     // Save the updated release
-    match db.save_release(&release) {
-        Ok(_) => {
-            info!("Rerunning deployment item {} for release {}", item_name, release_id);
+    match updated {
+        Ok(Some(release)) => {
+            // The scheduler deploys the item like any resumed deployment: it waits for a
+            // free slot and only runs the items that aren't finished
+            drop(active);
+            scheduler::schedule_release(&release);
+            
+            let message = if release.status == ReleaseStatus::AwaitingApproval {
+                info!("Rerun of deployment item {} for release {} is awaiting approval", item_name, release_id);
+                broadcast_approval_update(&release);
+                format!("Rerun of item [{}] is awaiting approval", item_name)
+            } else {
+                info!("{} is rerunning deployment item {} for release {}", user.username, item_name, release_id);
+                format!("Item [{}] rerun started", item_name)
+            };
+            
+            broadcast_release_update(
+                release.id.to_string(),
+                format!("{:?}", release.status),
+                release.progress,
+                Some(message.clone())
+            );
             
             HttpResponse::Ok().json(ReleaseResponse {
                 success: true,
-                message: Some(format!("Deployment item {} rerunning", item_name)),
+                message: Some(message),
                 data: Some(release),
             })
         }
+        Ok(None) => {
+            HttpResponse::NotFound().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Release with ID {} not found", release_id)),
+                data: None,
+            })
+        }
         Err(e) => {
             error!("Failed to save release after rerunning item: {}", e);
             HttpResponse::InternalServerError().json(ReleaseResponse {
@@ -535,6 +925,693 @@ async fn rerun_deployment_item(
     }
 }
 
+#[post("/{id}/rollback")]
+async fn rollback_release(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let release_id = path.into_inner();
+    
+    let release = match db.get_release(&release_id) {
+        Ok(Some(release)) => release,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Release with ID {} not found", release_id)),
+                data: None,
+            });
+        }
+        Err(e) => {
+            error!("Failed to get release {}: {}", release_id, e);
+            return HttpResponse::InternalServerError().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Failed to get release: {}", e)),
+                data: None,
+            });
+        }
+    };
+    
+    if !release.can_be_rolled_back() {
+        return HttpResponse::BadRequest().json(ReleaseResponse {
+            success: false,
+            message: Some(format!("Release has no deployed items to roll back in its current state: {:?}", release.status)),
+            data: None,
+        });
+    }
+    
+    // Rolling back runs commands in the environment the release was deployed to
+    let environment = release.last_deployment_environment();
+    let user = match deploying_user(&req, &db, &environment) {
+        Ok(user) => user,
+        Err(rejection) => return rejection.response(),
+    };
+    
+    let deployed_status = environment.deployed_status();
+    let registry = config::deployment();
+    let has_rollback = release.deployment_items.iter()
+        .filter(|item| item.status == deployed_status)
+        .any(|item| registry.item(&item.name).is_some_and(|config| config.rollback.is_some()));
+    if !has_rollback {
+        return HttpResponse::BadRequest().json(ReleaseResponse {
+            success: false,
+            message: Some("None of the deployed items has a rollback command".to_string()),
+            data: None,
+        });
+    }
+    
+    if !scheduler::spawn_rollback(db.clone(), release_id, user.username.clone()) {
+        return HttpResponse::Conflict().json(ReleaseResponse {
+            success: false,
+            message: Some("Release is already being processed".to_string()),
+            data: None,
+        });
+    }
+    
+    info!("{} requested a rollback of release {}", user.username, release_id);
+    HttpResponse::Ok().json(ReleaseResponse {
+        success: true,
+        message: Some("Rollback started".to_string()),
+        data: Some(release),
+    })
+}
+
+#[post("/{id}/dry-run")]
+async fn dry_run_release(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<Uuid>,
+    body: Option<web::Json<DryRunRequest>>,
+) -> impl Responder {
+    let release_id = path.into_inner();
+    
+    let release = match db.get_release(&release_id) {
+        Ok(Some(release)) => release,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Release with ID {} not found", release_id)),
+                data: None,
+            });
+        }
+        Err(e) => {
+            error!("Failed to get release {}: {}", release_id, e);
+            return HttpResponse::InternalServerError().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Failed to get release: {}", e)),
+                data: None,
+            });
+        }
+    };
+    
+    if !release.can_dry_run() {
+        return HttpResponse::BadRequest().json(ReleaseResponse {
+            success: false,
+            message: Some(format!("Release cannot be dry run in its current state: {:?}", release.status)),
+            data: None,
+        });
+    }
+    
+    let environment = body.and_then(|body| body.into_inner().environment)
+        .unwrap_or_else(|| release.next_deployment_environment());
+    if environment == Environment::Development {
+        return HttpResponse::BadRequest().json(ReleaseResponse {
+            success: false,
+            message: Some("Dry runs rehearse deployments to Staging or Production".to_string()),
+            data: None,
+        });
+    }
+    
+    // The dry run calls the environment's real scripts
+    let user = match deploying_user(&req, &db, &environment) {
+        Ok(user) => user,
+        Err(rejection) => return rejection.response(),
+    };
+    
+    if let Err(message) = scheduler::dry_run::start(db.clone(), &release, environment.clone(), user.username.clone()) {
+        return HttpResponse::Conflict().json(ReleaseResponse {
+            success: false,
+            message: Some(message),
+            data: None,
+        });
+    }
+    
+    info!("{} requested a dry run of release {} to {:?}", user.username, release_id, environment);
+    match db.get_release(&release_id) {
+        Ok(release) => HttpResponse::Ok().json(ReleaseResponse {
+            success: true,
+            message: Some(format!("Dry run to {:?} started", environment)),
+            data: release,
+        }),
+        Err(e) => {
+            error!("Failed to get release {}: {}", release_id, e);
+            HttpResponse::InternalServerError().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Failed to get release: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+#[post("/{id}/dry-run/cancel")]
+async fn cancel_dry_run(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let release_id = path.into_inner();
+    
+    let release = match db.get_release(&release_id) {
+        Ok(Some(release)) => release,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Release with ID {} not found", release_id)),
+                data: None,
+            });
+        }
+        Err(e) => {
+            error!("Failed to get release {}: {}", release_id, e);
+            return HttpResponse::InternalServerError().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Failed to get release: {}", e)),
+                data: None,
+            });
+        }
+    };
+    
+    let environment = match release.running_dry_run() {
+        Some(dry_run) => dry_run.environment.clone(),
+        None => {
+            return HttpResponse::BadRequest().json(ReleaseResponse {
+                success: false,
+                message: Some("Release has no dry run in progress".to_string()),
+                data: None,
+            });
+        }
+    };
+    
+    let user = match deploying_user(&req, &db, &environment) {
+        Ok(user) => user,
+        Err(rejection) => return rejection.response(),
+    };
+    
+    if !scheduler::dry_run::cancel(release_id) {
+        return HttpResponse::BadRequest().json(ReleaseResponse {
+            success: false,
+            message: Some("Release has no dry run in progress".to_string()),
+            data: None,
+        });
+    }
+    
+    info!("{} cancelled the dry run of release {} to {:?}", user.username, release_id, environment);
+    HttpResponse::Ok().json(ReleaseResponse {
+        success: true,
+        message: Some("Dry run cancellation requested".to_string()),
+        data: None,
+    })
+}
+
+// Approve or reject a release that is awaiting approval
+#[post("/{id}/approvals")]
+async fn answer_approval(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<Uuid>,
+    answer: web::Json<ApprovalAnswer>,
+) -> impl Responder {
+    let release_id = path.into_inner();
+    
+    let user = match session_user(&req, &db) {
+        Some(user) => user,
+        None => {
+            return HttpResponse::Unauthorized().json(ReleaseResponse {
+                success: false,
+                message: Some("Log in to approve or reject deployments".to_string()),
+                data: None,
+            });
+        }
+    };
+    
+    let release = match db.get_release(&release_id) {
+        Ok(Some(release)) => release,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Release with ID {} not found", release_id)),
+                data: None,
+            });
+        }
+        Err(e) => {
+            error!("Failed to get release {}: {}", release_id, e);
+            return HttpResponse::InternalServerError().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Failed to get release: {}", e)),
+                data: None,
+            });
+        }
+    };
+    
+    let request = match &release.approval_request {
+        Some(request) if release.status == ReleaseStatus::AwaitingApproval => request.clone(),
+        _ => {
+            return HttpResponse::Conflict().json(ReleaseResponse {
+                success: false,
+                message: Some("Release is not awaiting approval".to_string()),
+                data: None,
+            });
+        }
+    };
+    
+    if !request.roles.contains(&user.role) {
+        return HttpResponse::Forbidden().json(ReleaseResponse {
+            success: false,
+            message: Some(format!("Deployments to {:?} can only be approved by {:?}", request.environment, request.roles)),
+            data: None,
+        });
+    }
+    if !request.allow_author && user.username == release.created_by {
+        return HttpResponse::Forbidden().json(ReleaseResponse {
+            success: false,
+            message: Some("Authors can't approve their own release".to_string()),
+            data: None,
+        });
+    }
+    
+    let approval = Approval {
+        username: user.username.clone(),
+        role: user.role.clone(),
+        environment: request.environment.clone(),
+        approved: answer.approved,
+        comment: answer.comment.clone().filter(|comment| !comment.trim().is_empty()),
+        timestamp: Utc::now(),
+    };
+    
+    // The release may have been answered or changed since it was read, so the
+    // approval is recorded against whatever is stored now
+    let mut outcome = Ok(());
+    let updated = db.update_release(&release_id, |release| {
+        outcome = release.record_approval(approval.clone());
+    });
+    
+    match (updated, outcome) {
+        (Ok(Some(release)), Ok(())) => {
+            let verdict = if answer.approved { "approved" } else { "rejected" };
+            let summary = match release.status {
+                ReleaseStatus::AwaitingApproval => format!("{} of {} approvals", release.approval_count(), request.required),
+                _ => format!("now {:?}", release.status),
+            };
+            info!("{} {} the deployment of release {} to {:?}, {}", user.username, verdict, release_id, request.environment, summary);
+            
+            scheduler::schedule_release(&release);
+            broadcast_approval_update(&release);
+            broadcast_release_update(
+                release.id.to_string(),
+                format!("{:?}", release.status),
+                release.progress,
+                Some(format!("{} {} the deployment of {} to {:?}, {}", user.username, verdict, release.title, request.environment, summary))
+            );
+            
+            HttpResponse::Ok().json(ReleaseResponse {
+                success: true,
+                message: Some(format!("Deployment {}, {}", verdict, summary)),
+                data: Some(release),
+            })
+        }
+        (Ok(Some(_)), Err(message)) => {
+            HttpResponse::Conflict().json(ReleaseResponse {
+                success: false,
+                message: Some(message),
+                data: None,
+            })
+        }
+        (Ok(None), _) => {
+            HttpResponse::NotFound().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Release with ID {} not found", release_id)),
+                data: None,
+            })
+        }
+        (Err(e), _) => {
+            error!("Failed to record approval of release {}: {}", release_id, e);
+            HttpResponse::InternalServerError().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Failed to record approval: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+#[post("/{id}/cancel")]
+async fn cancel_release(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let release_id = path.into_inner();
+    
+    let release = match db.get_release(&release_id) {
+        Ok(Some(release)) => release,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Release with ID {} not found", release_id)),
+                data: None,
+            });
+        }
+        Err(e) => {
+            error!("Failed to get release {}: {}", release_id, e);
+            return HttpResponse::InternalServerError().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Failed to get release: {}", e)),
+                data: None,
+            });
+        }
+    };
+    
+    if !release.can_be_cancelled() {
+        return HttpResponse::BadRequest().json(ReleaseResponse {
+            success: false,
+            message: Some(format!("Release cannot be cancelled in its current state: {:?}", release.status)),
+            data: None,
+        });
+    }
+    
+    // Stopping a deployment can leave the environment half deployed
+    let environment = release.current_deployment_environment();
+    let user = match deploying_user(&req, &db, &environment) {
+        Ok(user) => user,
+        Err(rejection) => return rejection.response(),
+    };
+    
+    match scheduler::cancel_release(&db, release_id) {
+        Ok(Some((release, signalled))) => {
+            info!("{} cancelled the deployment of release {} to {:?}", user.username, release_id, environment);
+            HttpResponse::Ok().json(ReleaseResponse {
+                success: true,
+                message: Some(format!("Release cancellation requested, {} running items signalled", signalled)),
+                data: Some(release),
+            })
+        }
+        Ok(None) => {
+            HttpResponse::NotFound().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Release with ID {} not found", release_id)),
+                data: None,
+            })
+        }
+        Err(e) => {
+            error!("Failed to cancel release {}: {}", release_id, e);
+            HttpResponse::InternalServerError().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Failed to cancel release: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+#[post("/{id}/cancel/{item_name}")]
+async fn cancel_deployment_item(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<(Uuid, String)>,
+) -> impl Responder {
+    let (release_id, item_name) = path.into_inner();
+    
+    let release = match db.get_release(&release_id) {
+        Ok(Some(release)) => release,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Release with ID {} not found", release_id)),
+                data: None,
+            });
+        }
+        Err(e) => {
+            error!("Failed to get release {}: {}", release_id, e);
+            return HttpResponse::InternalServerError().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Failed to get release: {}", e)),
+                data: None,
+            });
+        }
+    };
+    
+    if !release.deployment_items.iter().any(|item| item.name == item_name) {
+        return HttpResponse::NotFound().json(ReleaseResponse {
+            success: false,
+            message: Some(format!("Deployment item {} not found", item_name)),
+            data: None,
+        });
+    }
+    
+    let user = match deploying_user(&req, &db, &release.current_deployment_environment()) {
+        Ok(user) => user,
+        Err(rejection) => return rejection.response(),
+    };
+    
+    if !scheduler::cancel_item(release_id, &item_name) {
+        return HttpResponse::Conflict().json(ReleaseResponse {
+            success: false,
+            message: Some(format!("Deployment item {} is not running", item_name)),
+            data: None,
+        });
+    }
+    
+    info!("{} requested cancellation of item {} of release {}", user.username, item_name, release_id);
+    HttpResponse::Ok().json(ReleaseResponse {
+        success: true,
+        message: Some(format!("Cancellation requested for deployment item {}", item_name)),
+        data: Some(release),
+    })
+}
+
+#[post("/{id}/items/{item_name}/confirm")]
+async fn confirm_deployment_item(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<(Uuid, String)>,
+    body: web::Json<ConfirmItemRequest>,
+) -> impl Responder {
+    let (release_id, item_name) = path.into_inner();
+    
+    let release = match db.get_release(&release_id) {
+        Ok(Some(release)) if release.deployment_items.iter().any(|item| item.name == item_name) => release,
+        Ok(Some(_)) => {
+            return HttpResponse::NotFound().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Deployment item {} not found", item_name)),
+                data: None,
+            });
+        }
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Release with ID {} not found", release_id)),
+                data: None,
+            });
+        }
+        Err(e) => {
+            error!("Failed to get release {}: {}", release_id, e);
+            return HttpResponse::InternalServerError().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Failed to get release: {}", e)),
+                data: None,
+            });
+        }
+    };
+    
+    // The answer decides whether the script goes on in the environment
+    let user = match deploying_user(&req, &db, &release.current_deployment_environment()) {
+        Ok(user) => user,
+        Err(rejection) => return rejection.response(),
+    };
+    
+    if let Err(message) = scheduler::answer_confirmation(&db, release_id, &item_name, body.approved, user.username).await {
+        return HttpResponse::Conflict().json(ReleaseResponse {
+            success: false,
+            message: Some(message),
+            data: None,
+        });
+    }
+    
+    match db.get_release(&release_id) {
+        Ok(release) => HttpResponse::Ok().json(ReleaseResponse {
+            success: true,
+            message: Some(format!("Answered the confirmation of {}", item_name)),
+            data: release,
+        }),
+        Err(e) => {
+            error!("Failed to get release {}: {}", release_id, e);
+            HttpResponse::InternalServerError().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Failed to get release: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+#[post("/{id}/items/{item_name}/manual")]
+async fn complete_manual_step(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<(Uuid, String)>,
+    body: web::Json<CompleteManualStepRequest>,
+) -> impl Responder {
+    let (release_id, item_name) = path.into_inner();
+    
+    let user = match session_user(&req, &db) {
+        Some(user) => user,
+        None => {
+            return HttpResponse::Unauthorized().json(ReleaseResponse {
+                success: false,
+                message: Some("Log in to complete manual steps".to_string()),
+                data: None,
+            });
+        }
+    };
+    
+    let release = match db.get_release(&release_id) {
+        Ok(Some(release)) if release.deployment_items.iter().any(|item| item.name == item_name) => release,
+        Ok(Some(_)) => {
+            return HttpResponse::NotFound().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Deployment item {} not found", item_name)),
+                data: None,
+            });
+        }
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Release with ID {} not found", release_id)),
+                data: None,
+            });
+        }
+        Err(e) => {
+            error!("Failed to get release {}: {}", release_id, e);
+            return HttpResponse::InternalServerError().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Failed to get release: {}", e)),
+                data: None,
+            });
+        }
+    };
+    
+    // The item type names the roles allowed to do the step, otherwise it's whoever may
+    // deploy to the environment
+    let environment = release.last_deployment_environment();
+    let roles = config::deployment().item(&item_name)
+        .and_then(|item| item.manual.as_ref())
+        .map(|manual| manual.roles.clone())
+        .unwrap_or_default();
+    let allowed = if roles.is_empty() {
+        user.can_deploy_to(&environment)
+    } else {
+        roles.contains(&user.role)
+    };
+    if !allowed {
+        return HttpResponse::Forbidden().json(ReleaseResponse {
+            success: false,
+            message: Some(format!("{} is not allowed to complete manual steps in {:?}", user.username, environment)),
+            data: None,
+        });
+    }
+    
+    let note = body.note.as_deref().map(str::trim).filter(|note| !note.is_empty()).map(str::to_string);
+    if let Err(message) = scheduler::complete_manual_step(&db, release_id, &item_name, body.done, user.username, note) {
+        return HttpResponse::Conflict().json(ReleaseResponse {
+            success: false,
+            message: Some(message),
+            data: None,
+        });
+    }
+    
+    match db.get_release(&release_id) {
+        Ok(release) => HttpResponse::Ok().json(ReleaseResponse {
+            success: true,
+            message: Some(format!("Marked the manual step {} as {}", item_name, if body.done { "done" } else { "failed" })),
+            data: release,
+        }),
+        Err(e) => {
+            error!("Failed to get release {}: {}", release_id, e);
+            HttpResponse::InternalServerError().json(ReleaseResponse {
+                success: false,
+                message: Some(format!("Failed to get release: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+#[get("/{id}/items/{item_name}/logs")]
+async fn get_item_logs(
+    db: web::Data<SledStorage>,
+    path: web::Path<(Uuid, String)>,
+    query: web::Query<LogPageQuery>,
+) -> impl Responder {
+    let (release_id, item_name) = path.into_inner();
+    
+    let release = match db.get_release(&release_id) {
+        Ok(Some(release)) => release,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(LogPageResponse {
+                success: false,
+                message: Some(format!("Release with ID {} not found", release_id)),
+                data: None,
+            });
+        }
+        Err(e) => {
+            error!("Failed to get release {}: {}", release_id, e);
+            return HttpResponse::InternalServerError().json(LogPageResponse {
+                success: false,
+                message: Some(format!("Failed to get release: {}", e)),
+                data: None,
+            });
+        }
+    };
+    
+    let item = match release.deployment_items.into_iter().find(|item| item.name == item_name) {
+        Some(item) => item,
+        None => {
+            return HttpResponse::NotFound().json(LogPageResponse {
+                success: false,
+                message: Some(format!("Deployment item {} not found", item_name)),
+                data: None,
+            });
+        }
+    };
+    
+    let limit = query.limit.unwrap_or(DEFAULT_LOG_PAGE_SIZE).min(MAX_LOG_PAGE_SIZE);
+    let (lines, total, dropped) = match db.get_log_lines(&release_id, &item_name, query.run, query.offset, limit) {
+        Ok(page) => page,
+        Err(e) => {
+            error!("Failed to get logs of {} for release {}: {}", item_name, release_id, e);
+            return HttpResponse::InternalServerError().json(LogPageResponse {
+                success: false,
+                message: Some(format!("Failed to get logs: {}", e)),
+                data: None,
+            });
+        }
+    };
+    
+    HttpResponse::Ok().json(LogPageResponse {
+        success: true,
+        message: None,
+        data: Some(LogPage {
+            item_name,
+            runs: item.runs,
+            total,
+            dropped,
+            offset: query.offset,
+            lines,
+        }),
+    })
+}
+
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(get_releases)
         .service(get_release)
@@ -542,5 +1619,14 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         .service(update_release)
         .service(delete_release)
         .service(update_release_status)
-        .service(rerun_deployment_item);
+        .service(rerun_deployment_item)
+        .service(get_item_logs)
+        .service(rollback_release)
+        .service(dry_run_release)
+        .service(cancel_dry_run)
+        .service(answer_approval)
+        .service(cancel_release)
+        .service(cancel_deployment_item)
+        .service(confirm_deployment_item)
+        .service(complete_manual_step);
 }
@@ -0,0 +1,165 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder, get, post};
+use crate::models::{JobOutput, JobResult, RunnerRegistration};
+use crate::scheduler::runners::{self, RunnerError};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::time::Duration;
+use uuid::Uuid;
+use log::{info, warn};
+
+// How long a poll waits for a job before the runner has to ask again
+const POLL_WAIT: Duration = Duration::from_secs(25);
+
+lazy_static::lazy_static! {
+    // Shared secret runners authenticate with, runners are disabled without it
+    static ref RUNNER_TOKEN: Option<String> = std::env::var("RUNNER_TOKEN").ok().filter(|token| !token.is_empty());
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunnerResponse<T> {
+    pub success: bool,
+    pub message: Option<String>,
+    pub data: Option<T>,
+}
+
+impl<T> RunnerResponse<T> {
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            message: Some(message.into()),
+            data: None,
+        }
+    }
+}
+
+// Check the runner's bearer token, returns the response to send when it's missing or wrong
+fn authorize(req: &HttpRequest) -> Result<(), HttpResponse> {
+    let expected = match RUNNER_TOKEN.as_deref() {
+        Some(token) => token,
+        None => {
+            return Err(HttpResponse::Forbidden().json(RunnerResponse::<()>::error(
+                "Runners are disabled, set RUNNER_TOKEN on the server to enable them")));
+        }
+    };
+
+    // Digests are compared instead of the tokens, so the time the comparison takes
+    // doesn't tell how much of a guessed token was right
+    let token = req.headers().get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let valid = token.is_some_and(|token| Sha256::digest(token.as_bytes()) == Sha256::digest(expected.as_bytes()));
+    if !valid {
+        warn!("Rejected runner request to {} with a missing or wrong token", req.path());
+        return Err(HttpResponse::Unauthorized().json(RunnerResponse::<()>::error("Invalid runner token")));
+    }
+    Ok(())
+}
+
+fn runner_error(error: RunnerError) -> HttpResponse {
+    match error {
+        // The runner has to register again, e.g. after the server restarted
+        RunnerError::UnknownRunner => HttpResponse::NotFound().json(RunnerResponse::<()>::error("Unknown runner")),
+        RunnerError::UnknownJob => HttpResponse::Gone().json(RunnerResponse::<()>::error("The job is no longer assigned to this runner")),
+    }
+}
+
+#[get("")]
+async fn get_runners() -> impl Responder {
+    HttpResponse::Ok().json(runners::list())
+}
+
+#[post("/register")]
+async fn register_runner(
+    req: HttpRequest,
+    registration: web::Json<RunnerRegistration>,
+) -> impl Responder {
+    if let Err(response) = authorize(&req) {
+        return response;
+    }
+
+    if registration.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json(RunnerResponse::<()>::error("Runner needs a name"));
+    }
+
+    let runner_id = runners::register(registration.into_inner());
+    HttpResponse::Ok().json(RunnerResponse {
+        success: true,
+        message: Some("Runner registered".to_string()),
+        data: Some(runner_id),
+    })
+}
+
+// Long poll for the next job, 204 when none came up in time
+#[post("/{id}/poll")]
+async fn poll_job(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    if let Err(response) = authorize(&req) {
+        return response;
+    }
+
+    let runner_id = path.into_inner();
+    match runners::poll(runner_id, POLL_WAIT).await {
+        Ok(Some(job)) => {
+            info!("Runner {} got job {} for {} of release {}", runner_id, job.id, job.item_name, job.release_id);
+            HttpResponse::Ok().json(RunnerResponse {
+                success: true,
+                message: None,
+                data: Some(job),
+            })
+        }
+        Ok(None) => HttpResponse::NoContent().finish(),
+        Err(e) => runner_error(e),
+    }
+}
+
+#[post("/{id}/jobs/{job_id}/output")]
+async fn report_output(
+    req: HttpRequest,
+    path: web::Path<(Uuid, Uuid)>,
+    output: web::Json<JobOutput>,
+) -> impl Responder {
+    if let Err(response) = authorize(&req) {
+        return response;
+    }
+
+    let (runner_id, job_id) = path.into_inner();
+    match runners::report_output(runner_id, job_id, output.into_inner().lines) {
+        Ok(ack) => HttpResponse::Ok().json(RunnerResponse {
+            success: true,
+            message: None,
+            data: Some(ack),
+        }),
+        Err(e) => runner_error(e),
+    }
+}
+
+#[post("/{id}/jobs/{job_id}/finish")]
+async fn finish_job(
+    req: HttpRequest,
+    path: web::Path<(Uuid, Uuid)>,
+    result: web::Json<JobResult>,
+) -> impl Responder {
+    if let Err(response) = authorize(&req) {
+        return response;
+    }
+
+    let (runner_id, job_id) = path.into_inner();
+    match runners::finish(runner_id, job_id, result.into_inner()) {
+        Ok(()) => HttpResponse::Ok().json(RunnerResponse::<()> {
+            success: true,
+            message: Some("Job finished".to_string()),
+            data: None,
+        }),
+        Err(e) => runner_error(e),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_runners)
+        .service(register_runner)
+        .service(poll_job)
+        .service(report_output)
+        .service(finish_job);
+}
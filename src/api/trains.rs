@@ -0,0 +1,613 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder, get, post};
+use crate::storage::SledStorage;
+use crate::models::{Release, ReleaseStatus, ReleaseTrain, User};
+use crate::scheduler;
+use crate::api::releases::{
+    change_release_status, check_client_release_exists, check_freeze, deploying_user, parse_environment,
+    validate_deployment_items, validate_deployment_path, Rejection,
+};
+use crate::api::users::session_user;
+use crate::websocket::server::broadcast_train_update;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use log::{info, error};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTrainRequest {
+    pub title: String,
+    pub client_ids: Vec<String>,
+    pub current_environment: String,
+    pub target_environment: String,
+    pub deployment_items: Vec<String>,
+    pub scheduled_at: DateTime<Utc>,
+    #[serde(default)]
+    pub skip_staging: bool,
+    #[serde(default)]
+    pub item_timeouts: HashMap<String, u64>, // Per-item timeout overrides in seconds
+    #[serde(default)]
+    pub max_failures: Option<usize>, // Stop the train after this many client failures
+    #[serde(default)]
+    pub override_freeze: bool, // Admins only, schedule the train into a freeze window anyway
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ClearTrainRequest {
+    #[serde(default)]
+    pub override_freeze: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RescheduleTrainRequest {
+    pub scheduled_at: DateTime<Utc>,
+    #[serde(default)]
+    pub override_freeze: bool,
+}
+
+// What a bulk action did to one of the train's releases
+#[derive(Debug, Serialize)]
+pub struct TrainActionResult {
+    pub release_id: Uuid,
+    pub client_id: String,
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrainDetails {
+    pub train: ReleaseTrain,
+    pub releases: Vec<Release>,
+    pub results: Vec<TrainActionResult>, // Per-release outcome of a bulk action
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrainResponse {
+    pub success: bool,
+    pub message: Option<String>,
+    pub data: Option<TrainDetails>,
+}
+
+fn get_train_details(db: &SledStorage, train_id: Uuid) -> Result<(ReleaseTrain, Vec<Release>), HttpResponse> {
+    let train = match db.get_train(&train_id) {
+        Ok(Some(train)) => train,
+        Ok(None) => {
+            return Err(HttpResponse::NotFound().json(TrainResponse {
+                success: false,
+                message: Some(format!("Release train with ID {} not found", train_id)),
+                data: None,
+            }));
+        }
+        Err(e) => {
+            error!("Failed to get release train {}: {}", train_id, e);
+            return Err(HttpResponse::InternalServerError().json(TrainResponse {
+                success: false,
+                message: Some(format!("Failed to get release train: {}", e)),
+                data: None,
+            }));
+        }
+    };
+
+    match db.get_train_releases(&train_id) {
+        Ok(mut releases) => {
+            releases.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+            Ok((train, releases))
+        }
+        Err(e) => {
+            error!("Failed to get the releases of train {}: {}", train_id, e);
+            Err(HttpResponse::InternalServerError().json(TrainResponse {
+                success: false,
+                message: Some(format!("Failed to get the releases of the train: {}", e)),
+                data: None,
+            }))
+        }
+    }
+}
+
+// The logged in user changing a train, who has to be allowed to deploy each of its
+// releases to the environment the train takes them to
+fn train_user(req: &HttpRequest, db: &SledStorage, train: &ReleaseTrain, releases: &[Release]) -> Result<User, HttpResponse> {
+    let rejected = |rejection: Rejection| HttpResponse::build(rejection.status).json(TrainResponse {
+        success: false,
+        message: Some(rejection.message),
+        data: None,
+    });
+
+    let user = deploying_user(req, db, &train.target_environment).map_err(rejected)?;
+    for release in releases {
+        deploying_user(req, db, &release.target_environment).map_err(rejected)?;
+    }
+
+    Ok(user)
+}
+
+// Answer a bulk action with the train's current state and what happened to each release
+fn action_response(db: &SledStorage, train_id: Uuid, action: &str, results: Vec<TrainActionResult>) -> HttpResponse {
+    let (train, releases) = match get_train_details(db, train_id) {
+        Ok(details) => details,
+        Err(response) => return response,
+    };
+
+    let succeeded = results.iter().filter(|result| result.success).count();
+    info!("{} {} of {} releases of train {}", action, succeeded, results.len(), train.title);
+    HttpResponse::Ok().json(TrainResponse {
+        success: true,
+        message: Some(format!("{} {} of {} releases", action, succeeded, results.len())),
+        data: Some(TrainDetails {
+            train,
+            releases,
+            results,
+        }),
+    })
+}
+
+#[get("")]
+async fn get_trains(db: web::Data<SledStorage>) -> impl Responder {
+    match db.get_all_trains() {
+        Ok(trains) => HttpResponse::Ok().json(trains),
+        Err(e) => {
+            error!("Failed to get release trains: {}", e);
+            HttpResponse::InternalServerError().json(TrainResponse {
+                success: false,
+                message: Some(format!("Failed to get release trains: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+#[get("/{id}")]
+async fn get_train(db: web::Data<SledStorage>, path: web::Path<Uuid>) -> impl Responder {
+    match get_train_details(&db, path.into_inner()) {
+        Ok((train, releases)) => HttpResponse::Ok().json(TrainDetails {
+            train,
+            releases,
+            results: Vec::new(),
+        }),
+        Err(response) => response,
+    }
+}
+
+// Create a train with a release for every client. Nothing is created unless every
+// client can take the release.
+#[post("")]
+async fn create_train(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    train_data: web::Json<CreateTrainRequest>,
+) -> impl Responder {
+    let bad_request = |message: String| HttpResponse::BadRequest().json(TrainResponse {
+        success: false,
+        message: Some(message),
+        data: None,
+    });
+
+    if train_data.title.trim().is_empty() {
+        return bad_request("Release train needs a title".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    if train_data.client_ids.is_empty() || !train_data.client_ids.iter().all(|id| seen.insert(id)) {
+        return bad_request("Release train needs at least one client, each listed once".to_string());
+    }
+
+    if train_data.max_failures == Some(0) {
+        return bad_request("Failure limit has to be at least 1".to_string());
+    }
+
+    let current_env = match parse_environment(&train_data.current_environment) {
+        Ok(env) => env,
+        Err(e) => return bad_request(e),
+    };
+
+    let target_env = match parse_environment(&train_data.target_environment) {
+        Ok(env) => env,
+        Err(e) => return bad_request(e),
+    };
+
+    if let Err(e) = validate_deployment_items(&train_data.deployment_items) {
+        return bad_request(e);
+    }
+
+    if let Err(e) = validate_deployment_path(&current_env, &target_env, train_data.skip_staging) {
+        return bad_request(e);
+    }
+
+    let clients = match db.get_all_clients() {
+        Ok(clients) => clients,
+        Err(e) => {
+            error!("Failed to get clients: {}", e);
+            return HttpResponse::InternalServerError().json(TrainResponse {
+                success: false,
+                message: Some(format!("Failed to get clients: {}", e)),
+                data: None,
+            });
+        }
+    };
+
+    // Every client is checked before anything is saved
+    let mut freeze_overrides = HashMap::new();
+    for client_id in train_data.client_ids.iter() {
+        let client_name = match clients.iter().find(|client| client.id.to_string() == *client_id) {
+            Some(client) => client.name.clone(),
+            None => return bad_request(format!("Client {} not found", client_id)),
+        };
+
+        match check_freeze(
+            &req,
+            &db,
+            &target_env,
+            client_id,
+            train_data.scheduled_at,
+            train_data.override_freeze,
+        ) {
+            Ok(overridden_by) => {
+                freeze_overrides.insert(client_id.clone(), overridden_by);
+            }
+            Err(rejection) => {
+                return HttpResponse::build(rejection.status).json(TrainResponse {
+                    success: false,
+                    message: Some(format!("{}: {}", client_name, rejection.message)),
+                    data: None,
+                });
+            }
+        }
+
+        match check_client_release_exists(&db, client_id).await {
+            Ok(true) => {
+                return HttpResponse::Conflict().json(TrainResponse {
+                    success: false,
+                    message: Some(format!("Client {} already has an active release in progress", client_name)),
+                    data: None,
+                });
+            }
+            Err(e) => {
+                error!("Failed to check client releases: {}", e);
+                return HttpResponse::InternalServerError().json(TrainResponse {
+                    success: false,
+                    message: Some(format!("Failed to check client releases: {}", e)),
+                    data: None,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let created_by = session_user(&req, &db).map(|user| user.username).unwrap_or_else(|| "unknown".to_string());
+    let train = ReleaseTrain {
+        id: Uuid::new_v4(),
+        title: train_data.title.clone(),
+        client_ids: train_data.client_ids.clone(),
+        current_environment: current_env.clone(),
+        target_environment: target_env.clone(),
+        deployment_items: train_data.deployment_items.clone(),
+        scheduled_at: train_data.scheduled_at,
+        skip_staging: train_data.skip_staging,
+        item_timeouts: train_data.item_timeouts.clone(),
+        max_failures: train_data.max_failures,
+        failures: Vec::new(),
+        stopped: None,
+        created_by: created_by.clone(),
+        created_at: Utc::now(),
+    };
+
+    if let Err(e) = db.save_train(&train) {
+        error!("Failed to save release train: {}", e);
+        return HttpResponse::InternalServerError().json(TrainResponse {
+            success: false,
+            message: Some(format!("Failed to save release train: {}", e)),
+            data: None,
+        });
+    }
+
+    let mut releases = Vec::new();
+    for client_id in train.client_ids.iter() {
+        let mut release = Release::new(
+            train.title.clone(),
+            client_id.clone(),
+            current_env.clone(),
+            target_env.clone(),
+            train.deployment_items.clone(),
+            train.scheduled_at,
+            created_by.clone(),
+            train.skip_staging,
+        );
+        release.item_timeouts = train.item_timeouts.clone();
+        release.freeze_override_by = freeze_overrides.remove(client_id).flatten();
+        release.train_id = Some(train.id);
+
+        if let Err(e) = db.save_release(&release) {
+            error!("Failed to save release of train {} for client {}: {}", train.id, client_id, e);
+            return HttpResponse::InternalServerError().json(TrainResponse {
+                success: false,
+                message: Some(format!("Failed to save release for client {}: {}", client_id, e)),
+                data: None,
+            });
+        }
+        scheduler::schedule_release(&release);
+        releases.push(release);
+    }
+
+    info!("Created release train {} with {} releases", train.id, releases.len());
+    broadcast_train_update(&train);
+    HttpResponse::Created().json(TrainResponse {
+        success: true,
+        message: Some(format!("Release train created with {} releases", releases.len())),
+        data: Some(TrainDetails {
+            train,
+            releases,
+            results: Vec::new(),
+        }),
+    })
+}
+
+// Clear every release of the train that can move on to its next stage
+#[post("/{id}/clear")]
+async fn clear_train(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<Uuid>,
+    clear_data: Option<web::Json<ClearTrainRequest>>,
+) -> impl Responder {
+    let train_id = path.into_inner();
+    let override_freeze = clear_data.is_some_and(|data| data.override_freeze);
+
+    // Each release is checked against the user's role as well, anonymous visitors are
+    // turned away before anything is cleared
+    if session_user(&req, &db).is_none() {
+        return HttpResponse::Unauthorized().json(TrainResponse {
+            success: false,
+            message: Some("Log in to clear a release train".to_string()),
+            data: None,
+        });
+    }
+
+    let (train, releases) = match get_train_details(&db, train_id) {
+        Ok(details) => details,
+        Err(response) => return response,
+    };
+
+    if let Some(stop) = &train.stopped {
+        return HttpResponse::Conflict().json(TrainResponse {
+            success: false,
+            message: Some(format!("Release train was stopped: {}", stop.reason)),
+            data: None,
+        });
+    }
+
+    let results = releases.iter()
+        .filter(|release| release.next_status_when_cleared().is_some())
+        .map(|release| {
+            match change_release_status(&req, &db, release.id, "clear", override_freeze) {
+                Ok(cleared) => TrainActionResult {
+                    release_id: release.id,
+                    client_id: release.client_id.clone(),
+                    success: true,
+                    message: format!("Moved to {:?}", cleared.status),
+                },
+                Err(rejection) => TrainActionResult {
+                    release_id: release.id,
+                    client_id: release.client_id.clone(),
+                    success: false,
+                    message: rejection.message,
+                },
+            }
+        })
+        .collect();
+
+    action_response(&db, train_id, "Cleared", results)
+}
+
+// Move the train and its releases that haven't started deploying to a new slot
+#[post("/{id}/reschedule")]
+async fn reschedule_train(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<Uuid>,
+    reschedule_data: web::Json<RescheduleTrainRequest>,
+) -> impl Responder {
+    let train_id = path.into_inner();
+    let scheduled_at = reschedule_data.scheduled_at;
+
+    let (train, releases) = match get_train_details(&db, train_id) {
+        Ok(details) => details,
+        Err(response) => return response,
+    };
+
+    let user = match train_user(&req, &db, &train, &releases) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    match db.update_train(&train_id, |train| train.scheduled_at = scheduled_at) {
+        Ok(Some(train)) => broadcast_train_update(&train),
+        Ok(None) => {}
+        Err(e) => {
+            error!("Failed to reschedule release train {}: {}", train_id, e);
+            return HttpResponse::InternalServerError().json(TrainResponse {
+                success: false,
+                message: Some(format!("Failed to reschedule release train: {}", e)),
+                data: None,
+            });
+        }
+    }
+
+    let mut results = Vec::new();
+    for release in releases.iter() {
+        let started = matches!(release.status,
+            ReleaseStatus::DeployingToStaging |
+            ReleaseStatus::DeployingToProduction |
+            ReleaseStatus::RollingBack |
+            ReleaseStatus::ClearedInProduction |
+            ReleaseStatus::Cancelled |
+            ReleaseStatus::RolledBack);
+        if started {
+            continue;
+        }
+
+        let mut result = TrainActionResult {
+            release_id: release.id,
+            client_id: release.client_id.clone(),
+            success: false,
+            message: String::new(),
+        };
+
+        // The slot is checked for the environment the release deploys to next
+        let environment = release.pending_deployment_environment()
+            .unwrap_or_else(|| release.target_environment.clone());
+        let freeze_override_by = match check_freeze(
+            &req,
+            &db,
+            &environment,
+            &release.client_id,
+            scheduled_at,
+            reschedule_data.override_freeze,
+        ) {
+            Ok(overridden_by) => overridden_by,
+            Err(rejection) => {
+                result.message = rejection.message;
+                results.push(result);
+                continue;
+            }
+        };
+
+        match db.update_release(&release.id, |release| {
+            release.scheduled_at = scheduled_at;
+            release.freeze_override_by = freeze_override_by.clone();
+        }) {
+            Ok(Some(release)) => {
+                scheduler::schedule_release(&release);
+                result.success = true;
+                result.message = format!("Scheduled at {}", scheduled_at.format("%Y-%m-%d %H:%M UTC"));
+            }
+            Ok(None) => result.message = "Release not found".to_string(),
+            Err(e) => {
+                error!("Failed to reschedule release {}: {}", release.id, e);
+                result.message = format!("Failed to reschedule release: {}", e);
+            }
+        }
+        results.push(result);
+    }
+
+    info!("{} rescheduled release train {} to {}", user.username, train.title, scheduled_at);
+    action_response(&db, train_id, "Rescheduled", results)
+}
+
+// Stop the train and cancel all of its releases that are waiting or deploying
+#[post("/{id}/cancel")]
+async fn cancel_train(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let train_id = path.into_inner();
+
+    let (train, releases) = match get_train_details(&db, train_id) {
+        Ok(details) => details,
+        Err(response) => return response,
+    };
+
+    let user = match train_user(&req, &db, &train, &releases) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let reason = format!("Cancelled by {}", user.username);
+    if let Err(e) = scheduler::train::stop_train(&db, train_id, reason, Some(user.username.clone())) {
+        error!("Failed to stop release train {}: {}", train_id, e);
+        return HttpResponse::InternalServerError().json(TrainResponse {
+            success: false,
+            message: Some(format!("Failed to stop release train: {}", e)),
+            data: None,
+        });
+    }
+
+    let results = match scheduler::train::cancel_train_releases(&db, train_id, true) {
+        Ok(cancelled) => cancelled.into_iter()
+            .map(|release| TrainActionResult {
+                release_id: release.id,
+                client_id: release.client_id,
+                success: true,
+                message: "Cancellation requested".to_string(),
+            })
+            .collect(),
+        Err(e) => {
+            error!("Failed to cancel the releases of train {}: {}", train_id, e);
+            return HttpResponse::InternalServerError().json(TrainResponse {
+                success: false,
+                message: Some(format!("Failed to cancel the releases of the train: {}", e)),
+                data: None,
+            });
+        }
+    };
+
+    action_response(&db, train_id, "Cancelled", results)
+}
+
+// Let a stopped train go on, with a fresh failure budget
+#[post("/{id}/resume")]
+async fn resume_train(
+    req: HttpRequest,
+    db: web::Data<SledStorage>,
+    path: web::Path<Uuid>,
+) -> impl Responder {
+    let train_id = path.into_inner();
+
+    // Resuming lets the train's releases deploy again
+    let (train, releases) = match get_train_details(&db, train_id) {
+        Ok(details) => details,
+        Err(response) => return response,
+    };
+
+    let user = match train_user(&req, &db, &train, &releases) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let mut was_stopped = false;
+    match db.update_train(&train_id, |train| {
+        was_stopped = train.stopped.is_some();
+        if was_stopped {
+            train.stopped = None;
+            train.failures.clear();
+        }
+    }) {
+        Ok(Some(train)) if was_stopped => {
+            info!("{} resumed release train {}", user.username, train.title);
+            broadcast_train_update(&train);
+            HttpResponse::Ok().json(TrainResponse {
+                success: true,
+                message: Some("Release train resumed".to_string()),
+                data: None,
+            })
+        }
+        Ok(Some(_)) => HttpResponse::BadRequest().json(TrainResponse {
+            success: false,
+            message: Some("Release train isn't stopped".to_string()),
+            data: None,
+        }),
+        Ok(None) => HttpResponse::NotFound().json(TrainResponse {
+            success: false,
+            message: Some(format!("Release train with ID {} not found", train_id)),
+            data: None,
+        }),
+        Err(e) => {
+            error!("Failed to resume release train {}: {}", train_id, e);
+            HttpResponse::InternalServerError().json(TrainResponse {
+                success: false,
+                message: Some(format!("Failed to resume release train: {}", e)),
+                data: None,
+            })
+        }
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_trains)
+        .service(create_train)
+        .service(get_train)
+        .service(clear_train)
+        .service(reschedule_train)
+        .service(cancel_train)
+        .service(resume_train);
+}
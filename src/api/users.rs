@@ -71,6 +71,13 @@ async fn get_current_user(
     }))
 }
 
+// The user logged in with the request's session cookie, if any
+pub fn session_user(req: &HttpRequest, db: &SledStorage) -> Option<User> {
+    let cookie = req.cookie("session_id")?;
+    let user_id = db.get_session(cookie.value()).ok()??;
+    db.get_user(&user_id).ok()?
+}
+
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(get_users)
         .service(get_current_user);
@@ -0,0 +1,919 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use log::{info, warn, error};
+use crate::models::user::UserRole;
+
+// Default location of the deployment configuration file
+const DEFAULT_CONFIG_PATH: &str = "deployment.json";
+
+// Default location of the secrets file
+const DEFAULT_SECRETS_PATH: &str = "secrets.json";
+
+// Client or environment key in the secrets file, health checks and rollouts that matches all of them
+const WILDCARD: &str = "*";
+
+// Timeout used for items that don't configure their own
+const DEFAULT_ITEM_TIMEOUT_SECS: u64 = 3600;
+
+// Placeholder in item arguments that is replaced with the environment name
+const ENVIRONMENT_PLACEHOLDER: &str = "{environment}";
+
+// Placeholder in item arguments that is replaced with the rollout target
+const TARGET_PLACEHOLDER: &str = "{target}";
+
+fn default_item_timeout_secs() -> u64 {
+    DEFAULT_ITEM_TIMEOUT_SECS
+}
+
+fn default_item_args() -> Vec<String> {
+    vec![ENVIRONMENT_PLACEHOLDER.to_string()]
+}
+
+fn default_auto_rollback() -> bool {
+    true
+}
+
+// Replace the environment placeholder in command arguments
+fn expand_args(args: &[String], env_name: &str) -> Vec<String> {
+    args.iter()
+        .map(|arg| arg.replace(ENVIRONMENT_PLACEHOLDER, env_name))
+        .collect()
+}
+
+// Replace the target placeholder in the arguments of a run on a rollout target
+pub fn expand_target(args: Vec<String>, target: &str) -> Vec<String> {
+    args.into_iter()
+        .map(|arg| arg.replace(TARGET_PLACEHOLDER, target))
+        .collect()
+}
+
+fn default_max_attempts() -> u32 {
+    1
+}
+
+fn default_backoff_multiplier() -> f64 {
+    2.0
+}
+
+fn default_health_retries() -> u32 {
+    3
+}
+
+fn default_health_interval_secs() -> u64 {
+    10
+}
+
+fn default_health_timeout_secs() -> u64 {
+    10
+}
+
+fn default_batch_size() -> usize {
+    1
+}
+
+fn default_required_approvals() -> u32 {
+    1
+}
+
+fn default_approver_roles() -> Vec<UserRole> {
+    vec![UserRole::Admin]
+}
+
+// How often and when a failing deployment item is retried
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetryPolicy {
+    // Total number of attempts, including the first one
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    // Delay before the first retry, multiplied by backoff_multiplier for every further retry
+    #[serde(default)]
+    pub backoff_secs: u64,
+    #[serde(default = "default_backoff_multiplier")]
+    pub backoff_multiplier: f64,
+    // Exit codes worth retrying. When empty every failed exit and timeout is retried.
+    #[serde(default)]
+    pub retryable_exit_codes: Vec<i32>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            backoff_secs: 0,
+            backoff_multiplier: default_backoff_multiplier(),
+            retryable_exit_codes: Vec::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Delay before starting the given attempt (the first retry is attempt 2)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let retries = attempt.saturating_sub(2) as i32;
+        let secs = self.backoff_secs as f64 * self.backoff_multiplier.max(1.0).powi(retries);
+        Duration::from_secs_f64(secs.min(u32::MAX as f64))
+    }
+
+    pub fn is_retryable_exit_code(&self, code: i32) -> bool {
+        self.retryable_exit_codes.is_empty() || self.retryable_exit_codes.contains(&code)
+    }
+
+    // Timeouts have no exit code, so they are only retried when every failure is
+    pub fn retries_timeouts(&self) -> bool {
+        self.retryable_exit_codes.is_empty()
+    }
+}
+
+// Command that undoes a deployment of an item type. It runs with the item's
+// working directory and environment variables.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RollbackConfig {
+    pub command: String,
+    #[serde(default = "default_item_args")]
+    pub args: Vec<String>,
+}
+
+impl RollbackConfig {
+    pub fn args_for(&self, env_name: &str) -> Vec<String> {
+        expand_args(&self.args, env_name)
+    }
+}
+
+// Settings for a deployment item type that can differ per environment
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EnvironmentOverride {
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+// Limits and isolation for the processes of an item type's scripts. Only applied
+// to scripts the server runs itself, on Linux.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SandboxConfig {
+    // CPU time in seconds, the script gets SIGXCPU when it's used up
+    #[serde(default)]
+    pub cpu_secs: Option<u64>,
+    // Address space in megabytes
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+    #[serde(default)]
+    pub open_files: Option<u64>,
+    // Counted over all processes of the user the script runs as
+    #[serde(default)]
+    pub max_processes: Option<u64>,
+    // Niceness from -20 to 19, lowering it needs root
+    #[serde(default)]
+    pub nice: Option<i32>,
+    // Unprivileged user to run the script as, needs blend to run as root
+    #[serde(default)]
+    pub user: Option<String>,
+    // Don't pass on the server's environment, only a minimal PATH/HOME/USER and the
+    // item's own variables
+    #[serde(default)]
+    pub clean_env: bool,
+}
+
+impl SandboxConfig {
+    pub fn is_empty(&self) -> bool {
+        self.cpu_secs.is_none()
+            && self.memory_mb.is_none()
+            && self.open_files.is_none()
+            && self.max_processes.is_none()
+            && self.nice.is_none()
+            && self.user.is_none()
+            && !self.clean_env
+    }
+}
+
+// A step carried out by a person instead of a script, e.g. notifying the client's IT
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManualStepConfig {
+    pub instructions: String,
+    // Roles that may mark the step done or failed, anyone who can deploy to the
+    // environment when empty
+    #[serde(default)]
+    pub roles: Vec<UserRole>,
+}
+
+// Settings for one deployment item type (e.g. "data", "solr", "app")
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemConfig {
+    pub name: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    // Not needed for manual steps
+    #[serde(default)]
+    pub command: String,
+    #[serde(default = "default_item_args")]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    // Item types that have to succeed before this one starts
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    #[serde(default)]
+    pub rollback: Option<RollbackConfig>,
+    // How many items of this type may run at once across all releases
+    #[serde(default)]
+    pub max_concurrent: Option<usize>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    // Keyed by environment name ("staging", "production")
+    #[serde(default)]
+    pub environments: HashMap<String, EnvironmentOverride>,
+    // Run the item on a `blend runner` matching its client, environment and type
+    #[serde(default)]
+    pub remote: bool,
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+    // Run the item once per environment even when it has rollout targets (e.g. migrations)
+    #[serde(default)]
+    pub run_once: bool,
+    // Pause the release until someone marks the step done instead of running a command
+    #[serde(default)]
+    pub manual: Option<ManualStepConfig>,
+}
+
+impl ItemConfig {
+    // Built-in item type running one of the bundled scripts
+    fn script(name: &str, display_name: &str, script: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            display_name: Some(display_name.to_string()),
+            command: format!("scripts/{}", script),
+            args: default_item_args(),
+            working_dir: None,
+            env: HashMap::new(),
+            depends_on: Vec::new(),
+            retry: RetryPolicy::default(),
+            rollback: None,
+            max_concurrent: None,
+            timeout_secs: None,
+            environments: HashMap::new(),
+            remote: false,
+            sandbox: SandboxConfig::default(),
+            run_once: false,
+            manual: None,
+        }
+    }
+
+    pub fn display_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+
+    // Arguments for a run in the given environment
+    pub fn args_for(&self, env_name: &str) -> Vec<String> {
+        expand_args(&self.args, env_name)
+    }
+}
+
+// What the API exposes about a deployment item type
+#[derive(Debug, Clone, Serialize)]
+pub struct DeploymentItemType {
+    pub name: String,
+    pub display_name: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub working_dir: Option<String>,
+    pub depends_on: Vec<String>,
+    pub can_roll_back: bool,
+    pub manual_instructions: Option<String>, // Set for manual steps
+}
+
+impl From<&ItemConfig> for DeploymentItemType {
+    fn from(item: &ItemConfig) -> Self {
+        Self {
+            name: item.name.clone(),
+            display_name: item.display_name().to_string(),
+            command: item.command.clone(),
+            args: item.args.clone(),
+            working_dir: item.working_dir.clone(),
+            depends_on: item.depends_on.clone(),
+            can_roll_back: item.rollback.is_some(),
+            manual_instructions: item.manual.as_ref().map(|manual| manual.instructions.clone()),
+        }
+    }
+}
+
+// What happens to deployments that were interrupted by a restart
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecoveryPolicy {
+    // Run the unfinished items again, keeping the ones that already succeeded
+    Resume,
+    // Mark the unfinished items as failed
+    #[default]
+    Fail,
+}
+
+// How many releases may deploy at once, unset limits are unlimited
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ConcurrencyLimits {
+    #[serde(default)]
+    pub max_releases: Option<usize>,
+    // Keyed by environment name ("staging", "production")
+    #[serde(default)]
+    pub max_releases_per_environment: HashMap<String, usize>,
+}
+
+// What a health check probes. `{environment}` is replaced in urls, addresses and arguments.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthProbe {
+    // GET the url, any 2xx status passes unless expect_status is set
+    Http {
+        url: String,
+        #[serde(default)]
+        expect_status: Option<u16>,
+        // Text the response body has to contain
+        #[serde(default)]
+        expect_body: Option<String>,
+    },
+    // Open a TCP connection to host:port
+    Tcp { address: String },
+    // Run a command, exit code 0 passes
+    Command {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        env: HashMap<String, String>,
+    },
+}
+
+impl HealthProbe {
+    // The probe with the environment placeholder replaced
+    pub fn for_environment(&self, env_name: &str) -> HealthProbe {
+        match self {
+            HealthProbe::Http { url, expect_status, expect_body } => HealthProbe::Http {
+                url: url.replace(ENVIRONMENT_PLACEHOLDER, env_name),
+                expect_status: *expect_status,
+                expect_body: expect_body.clone(),
+            },
+            HealthProbe::Tcp { address } => HealthProbe::Tcp {
+                address: address.replace(ENVIRONMENT_PLACEHOLDER, env_name),
+            },
+            HealthProbe::Command { command, args, env } => HealthProbe::Command {
+                command: command.clone(),
+                args: expand_args(args, env_name),
+                env: env.clone(),
+            },
+        }
+    }
+
+    // Short description for logs, e.g. "GET https://example.com/health"
+    pub fn describe(&self) -> String {
+        match self {
+            HealthProbe::Http { url, .. } => format!("GET {}", url),
+            HealthProbe::Tcp { address } => format!("TCP {}", address),
+            HealthProbe::Command { command, args, .. } => format!("{} {}", command, args.join(" ")).trim_end().to_string(),
+        }
+    }
+}
+
+// A check that has to pass after a release's items deployed before it's ready to test
+#[derive(Debug, Clone, Deserialize)]
+pub struct HealthCheckConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub probe: HealthProbe,
+    // Further attempts after the first one fails
+    #[serde(default = "default_health_retries")]
+    pub retries: u32,
+    #[serde(default = "default_health_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default = "default_health_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+// Hosts a client's environment is rolled out to, a batch at a time. Items run once
+// per target and a failed batch halts the ones after it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RolloutConfig {
+    pub targets: Vec<String>,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    // Pause between two batches
+    #[serde(default)]
+    pub pause_secs: u64,
+}
+
+impl RolloutConfig {
+    // Batch a target is deployed in, starting at 1
+    pub fn batch_of(&self, index: usize) -> usize {
+        index / self.batch_size.max(1) + 1
+    }
+}
+
+// Approvals a deployment to an environment needs before it's queued
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApprovalRule {
+    #[serde(default = "default_required_approvals")]
+    pub required: u32,
+    // Roles whose approvals count
+    #[serde(default = "default_approver_roles")]
+    pub roles: Vec<UserRole>,
+    // Whether the release's author may approve it
+    #[serde(default)]
+    pub allow_author: bool,
+}
+
+// Deployment settings loaded from the configuration file
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeploymentConfig {
+    #[serde(default = "default_item_timeout_secs")]
+    pub default_timeout_secs: u64,
+    #[serde(default)]
+    pub recovery_policy: RecoveryPolicy,
+    // Roll back the succeeded items of a failed production deployment
+    #[serde(default = "default_auto_rollback")]
+    pub auto_rollback: bool,
+    #[serde(default)]
+    pub concurrency: ConcurrencyLimits,
+    // Keyed by environment name ("staging", "production"), environments without a rule need none
+    #[serde(default)]
+    pub approvals: HashMap<String, ApprovalRule>,
+    // Keyed by client id, then environment name, "*" matching all of them
+    #[serde(default)]
+    pub health_checks: HashMap<String, HashMap<String, Vec<HealthCheckConfig>>>,
+    // Keyed by client id, then environment name, "*" matching all of them
+    #[serde(default)]
+    pub rollouts: HashMap<String, HashMap<String, RolloutConfig>>,
+    #[serde(default)]
+    pub items: Vec<ItemConfig>,
+}
+
+impl Default for DeploymentConfig {
+    // The bundled data, solr and app scripts
+    fn default() -> Self {
+        Self {
+            default_timeout_secs: DEFAULT_ITEM_TIMEOUT_SECS,
+            recovery_policy: RecoveryPolicy::default(),
+            auto_rollback: default_auto_rollback(),
+            concurrency: ConcurrencyLimits::default(),
+            approvals: HashMap::new(),
+            health_checks: HashMap::new(),
+            rollouts: HashMap::new(),
+            items: vec![
+                ItemConfig::script("data", "Data", "deploy_data.sh"),
+                ItemConfig::script("solr", "Solr", "deploy_solr.sh"),
+                ItemConfig::script("app", "App", "deploy_app.sh"),
+            ],
+        }
+    }
+}
+
+impl DeploymentConfig {
+    // Load the configuration from a JSON file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path_ref = path.as_ref();
+        let content = std::fs::read_to_string(path_ref)
+            .map_err(|e| format!("Failed to read {:?}: {}", path_ref, e))?;
+        let config: Self = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {:?}: {}", path_ref, e))?;
+        config.validate()
+            .map_err(|e| format!("Invalid deployment config {:?}: {}", path_ref, e))?;
+        
+        Ok(config)
+    }
+
+    // Check that approval rules and sandbox settings are sane, item names are unique and
+    // that dependencies form a DAG
+    fn validate(&self) -> Result<(), String> {
+        // Item names identify items on releases, so they have to be unique
+        for (index, item) in self.items.iter().enumerate() {
+            if self.items[..index].iter().any(|other| other.name == item.name) {
+                return Err(format!("duplicate deployment item type '{}'", item.name));
+            }
+        }
+        
+        for (env_name, rule) in &self.approvals {
+            if !["staging", "production"].contains(&env_name.as_str()) {
+                return Err(format!("approval rule for unknown environment '{}'", env_name));
+            }
+            if rule.required == 0 || rule.roles.is_empty() {
+                return Err(format!("approval rule for {} needs at least one approval and one role", env_name));
+            }
+        }
+        
+        for (client, environments) in &self.health_checks {
+            for (env_name, checks) in environments {
+                for (index, check) in checks.iter().enumerate() {
+                    if check.name.trim().is_empty() {
+                        return Err(format!("health check for {}/{} needs a name", client, env_name));
+                    }
+                    if checks[..index].iter().any(|other| other.name == check.name) {
+                        return Err(format!("duplicate health check '{}' for {}/{}", check.name, client, env_name));
+                    }
+                }
+            }
+        }
+        
+        for (client, environments) in &self.rollouts {
+            for (env_name, rollout) in environments {
+                if rollout.targets.is_empty() || rollout.batch_size == 0 {
+                    return Err(format!("rollout for {}/{} needs at least one target and a batch size", client, env_name));
+                }
+                for (index, target) in rollout.targets.iter().enumerate() {
+                    if target.trim().is_empty() {
+                        return Err(format!("rollout for {}/{} has an empty target", client, env_name));
+                    }
+                    if rollout.targets[..index].contains(target) {
+                        return Err(format!("duplicate rollout target '{}' for {}/{}", target, client, env_name));
+                    }
+                }
+            }
+        }
+        
+        for item in &self.items {
+            match &item.manual {
+                Some(manual) if manual.instructions.trim().is_empty() => {
+                    return Err(format!("manual step '{}' needs instructions", item.name));
+                }
+                Some(_) if item.remote || item.rollback.is_some() => {
+                    return Err(format!("manual step '{}' can't run on a runner or have a rollback", item.name));
+                }
+                None if item.command.trim().is_empty() => {
+                    return Err(format!("'{}' needs a command", item.name));
+                }
+                _ => {}
+            }
+            if let Some(nice) = item.sandbox.nice.filter(|nice| !(-20..=19).contains(nice)) {
+                return Err(format!("'{}' has niceness {}, it has to be between -20 and 19", item.name, nice));
+            }
+            if item.sandbox.user.as_deref().is_some_and(|user| user.trim().is_empty()) {
+                return Err(format!("'{}' has an empty sandbox user", item.name));
+            }
+            if let Some(missing) = item.depends_on.iter().find(|dep| self.item(dep).is_none()) {
+                return Err(format!("'{}' depends on unknown item type '{}'", item.name, missing));
+            }
+        }
+        
+        // Depth-first search, an item reached again while still on the path is a cycle
+        fn visit<'a>(
+            config: &'a DeploymentConfig,
+            name: &'a str,
+            path: &mut Vec<&'a str>,
+            done: &mut Vec<&'a str>,
+        ) -> Result<(), String> {
+            if done.contains(&name) {
+                return Ok(());
+            }
+            if path.contains(&name) {
+                path.push(name);
+                return Err(format!("dependency cycle: {}", path.join(" -> ")));
+            }
+            
+            path.push(name);
+            if let Some(item) = config.item(name) {
+                for dep in &item.depends_on {
+                    visit(config, dep, path, done)?;
+                }
+            }
+            path.pop();
+            done.push(name);
+            Ok(())
+        }
+        
+        let mut done = Vec::new();
+        for item in &self.items {
+            visit(self, &item.name, &mut Vec::new(), &mut done)?;
+        }
+        
+        Ok(())
+    }
+
+    // Look up a deployment item type by name
+    pub fn item(&self, name: &str) -> Option<&ItemConfig> {
+        self.items.iter().find(|item| item.name == name)
+    }
+
+    // Registered item types in configuration order
+    pub fn item_types(&self) -> Vec<DeploymentItemType> {
+        self.items.iter().map(DeploymentItemType::from).collect()
+    }
+
+    // Prerequisites of an item type that are part of the given set of items.
+    // Dependencies on item types a release doesn't deploy are ignored.
+    pub fn dependencies_within(&self, item_name: &str, items: &[String]) -> Vec<String> {
+        self.item(item_name)
+            .map(|item| item.depends_on.iter()
+                .filter(|dep| items.contains(dep))
+                .cloned()
+                .collect())
+            .unwrap_or_default()
+    }
+
+    // Retry policy of an item type, unknown types are never retried
+    pub fn retry_policy(&self, item_name: &str) -> RetryPolicy {
+        self.item(item_name)
+            .map(|item| item.retry.clone())
+            .unwrap_or_default()
+    }
+
+    // Release slot limits keyed like the scheduler asks for them:
+    // "releases" for the global cap, "environment:<name>" per environment
+    pub fn release_limits(&self) -> HashMap<String, usize> {
+        let mut limits: HashMap<String, usize> = self.concurrency.max_releases_per_environment.iter()
+            .map(|(env_name, limit)| (format!("environment:{}", env_name), *limit))
+            .collect();
+        if let Some(limit) = self.concurrency.max_releases {
+            limits.insert("releases".to_string(), limit);
+        }
+        limits
+    }
+
+    // Item slot limits keyed "item:<name>" for item types with max_concurrent
+    pub fn item_limits(&self) -> HashMap<String, usize> {
+        self.items.iter()
+            .filter_map(|item| item.max_concurrent.map(|limit| (format!("item:{}", item.name), limit)))
+            .collect()
+    }
+
+    // Approval rule for deployments to an environment, if it has one
+    pub fn approval_rule(&self, env_name: &str) -> Option<&ApprovalRule> {
+        self.approvals.get(env_name)
+    }
+
+    // Health checks of a client's deployment to an environment. Checks configured for
+    // a specific client or environment replace "*" ones with the same name.
+    pub fn health_checks_for(&self, client_id: &str, env_name: &str) -> Vec<HealthCheckConfig> {
+        let mut checks: Vec<HealthCheckConfig> = Vec::new();
+        for client in [WILDCARD, client_id] {
+            for environment in [WILDCARD, env_name] {
+                let configured = self.health_checks.get(client).and_then(|envs| envs.get(environment));
+                for check in configured.into_iter().flatten() {
+                    checks.retain(|existing| existing.name != check.name);
+                    checks.push(check.clone());
+                }
+            }
+        }
+        checks
+    }
+
+    // Rollout of a client's environment, if it has targets. A specific client or
+    // environment wins over "*".
+    pub fn rollout_for(&self, client_id: &str, env_name: &str) -> Option<&RolloutConfig> {
+        [(client_id, env_name), (client_id, WILDCARD), (WILDCARD, env_name), (WILDCARD, WILDCARD)]
+            .into_iter()
+            .find_map(|(client, environment)| self.rollouts.get(client).and_then(|envs| envs.get(environment)))
+    }
+
+    // Timeout for an item type in an environment. The environment override wins
+    // over the item's own timeout, which wins over the global default.
+    pub fn item_timeout(&self, item_name: &str, env_name: &str) -> Duration {
+        let item = self.item(item_name);
+        let secs = item
+            .and_then(|item| item.environments.get(env_name))
+            .and_then(|env| env.timeout_secs)
+            .or_else(|| item.and_then(|item| item.timeout_secs))
+            .unwrap_or(self.default_timeout_secs);
+
+        Duration::from_secs(secs)
+    }
+}
+
+// A missing file means the defaults. A file that can't be read or is invalid is an
+// error rather than falling back to them, which would quietly drop its approval
+// rules, limits, rollouts, health checks and manual steps.
+fn load() -> Result<DeploymentConfig, String> {
+    let path = std::env::var("DEPLOYMENT_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+
+    if !Path::new(&path).exists() {
+        warn!("Deployment config not found at {}, using defaults", path);
+        return Ok(DeploymentConfig::default());
+    }
+
+    let config = DeploymentConfig::from_file(&path)?;
+    info!("Loaded deployment config from {} ({} item types)", path, config.items.len());
+    for item in config.items.iter().filter(|item| item.remote && !item.sandbox.is_empty()) {
+        warn!("'{}' runs on remote runners, its sandbox settings are ignored", item.name);
+    }
+    Ok(config)
+}
+
+lazy_static::lazy_static! {
+    static ref CONFIG: Result<DeploymentConfig, String> = load();
+}
+
+// Load the deployment configuration, startup stops when it's broken
+pub fn load_deployment() -> Result<&'static DeploymentConfig, String> {
+    CONFIG.as_ref().map_err(|e| e.clone())
+}
+
+// The deployment configuration. Startup made sure it loaded.
+pub fn deployment() -> &'static DeploymentConfig {
+    match CONFIG.as_ref() {
+        Ok(config) => config,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+// Secret variables for deployment scripts by client id, then environment, then
+// variable name. They live in their own file so it can be kept out of version
+// control and locked down separately from deployment.json.
+#[derive(Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Secrets(HashMap<String, HashMap<String, HashMap<String, String>>>);
+
+impl Secrets {
+    // Variables for a client's deployment to an environment. Entries for a specific
+    // client or environment win over the "*" ones.
+    pub fn for_deployment(&self, client_id: &str, env_name: &str) -> HashMap<String, String> {
+        let mut variables = HashMap::new();
+        for client in [WILDCARD, client_id] {
+            for environment in [WILDCARD, env_name] {
+                if let Some(values) = self.0.get(client).and_then(|envs| envs.get(environment)) {
+                    variables.extend(values.iter().map(|(name, value)| (name.clone(), value.clone())));
+                }
+            }
+        }
+        variables
+    }
+}
+
+fn load_secrets() -> Secrets {
+    let path = std::env::var("SECRETS_CONFIG").unwrap_or_else(|_| DEFAULT_SECRETS_PATH.to_string());
+
+    // Secrets are optional
+    if !Path::new(&path).exists() {
+        return Secrets::default();
+    }
+
+    let secrets = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str::<Secrets>(&content).map_err(|e| e.to_string()));
+    match secrets {
+        Ok(secrets) => {
+            info!("Loaded secrets from {} for {} clients", path, secrets.0.len());
+            secrets
+        }
+        Err(e) => {
+            error!("Failed to load secrets from {}: {}, scripts get no secrets", path, e);
+            Secrets::default()
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref SECRETS: Secrets = load_secrets();
+}
+
+// The secrets for deployment scripts, loaded on first use
+pub fn secrets() -> &'static Secrets {
+    &SECRETS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(json: &str) -> DeploymentConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn retry_policy(backoff_secs: u64, backoff_multiplier: f64, retryable_exit_codes: Vec<i32>) -> RetryPolicy {
+        RetryPolicy { max_attempts: 5, backoff_secs, backoff_multiplier, retryable_exit_codes }
+    }
+
+    #[test]
+    fn backoff_grows_with_every_retry() {
+        let policy = retry_policy(30, 2.0, Vec::new());
+
+        assert_eq!(policy.backoff(2), Duration::from_secs(30));
+        assert_eq!(policy.backoff(3), Duration::from_secs(60));
+        assert_eq!(policy.backoff(4), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn backoff_never_shrinks() {
+        let policy = retry_policy(30, 0.5, Vec::new());
+
+        assert_eq!(policy.backoff(2), Duration::from_secs(30));
+        assert_eq!(policy.backoff(5), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = retry_policy(u64::MAX, 10.0, Vec::new());
+
+        assert_eq!(policy.backoff(40), Duration::from_secs(u32::MAX as u64));
+    }
+
+    #[test]
+    fn every_exit_code_is_retried_without_a_list() {
+        let policy = retry_policy(0, 1.0, Vec::new());
+
+        assert!(policy.is_retryable_exit_code(1));
+        assert!(policy.is_retryable_exit_code(75));
+        assert!(policy.retries_timeouts());
+    }
+
+    #[test]
+    fn only_listed_exit_codes_are_retried() {
+        let policy = retry_policy(0, 1.0, vec![75, 111]);
+
+        assert!(policy.is_retryable_exit_code(75));
+        assert!(policy.is_retryable_exit_code(111));
+        assert!(!policy.is_retryable_exit_code(1));
+        assert!(!policy.retries_timeouts());
+    }
+
+    fn rollout(targets: usize, batch_size: usize) -> RolloutConfig {
+        RolloutConfig {
+            targets: (1..=targets).map(|index| format!("app-{}", index)).collect(),
+            batch_size,
+            pause_secs: 0,
+        }
+    }
+
+    #[test]
+    fn targets_are_batched_in_order() {
+        let rollout = rollout(5, 2);
+        let batches: Vec<usize> = (0..rollout.targets.len()).map(|index| rollout.batch_of(index)).collect();
+
+        assert_eq!(batches, vec![1, 1, 2, 2, 3]);
+    }
+
+    #[test]
+    fn batches_of_one_deploy_a_target_at_a_time() {
+        let rollout = rollout(3, 1);
+        let batches: Vec<usize> = (0..rollout.targets.len()).map(|index| rollout.batch_of(index)).collect();
+
+        assert_eq!(batches, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn an_empty_batch_size_counts_as_one() {
+        let rollout = rollout(2, 0);
+
+        assert_eq!(rollout.batch_of(0), 1);
+        assert_eq!(rollout.batch_of(1), 2);
+    }
+
+    #[test]
+    fn dependencies_within_keeps_prerequisites_in_the_release() {
+        let config = config(r#"{"items": [
+            {"name": "data", "command": "true"},
+            {"name": "solr", "command": "true", "depends_on": ["data"]},
+            {"name": "app", "command": "true", "depends_on": ["data", "solr"]}
+        ]}"#);
+
+        assert_eq!(config.dependencies_within("app", &names(&["data", "solr", "app"])), names(&["data", "solr"]));
+        assert_eq!(config.dependencies_within("app", &names(&["solr", "app"])), names(&["solr"]));
+        assert!(config.dependencies_within("app", &names(&["app"])).is_empty());
+        assert!(config.dependencies_within("data", &names(&["data", "app"])).is_empty());
+        assert!(config.dependencies_within("unknown", &names(&["data"])).is_empty());
+    }
+
+    #[test]
+    fn validate_accepts_shared_prerequisites() {
+        let config = config(r#"{"items": [
+            {"name": "data", "command": "true"},
+            {"name": "solr", "command": "true", "depends_on": ["data"]},
+            {"name": "cache", "command": "true", "depends_on": ["data"]},
+            {"name": "app", "command": "true", "depends_on": ["solr", "cache"]}
+        ]}"#);
+
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_rejects_dependency_cycles() {
+        let config = config(r#"{"items": [
+            {"name": "a", "command": "true", "depends_on": ["b"]},
+            {"name": "b", "command": "true", "depends_on": ["c"]},
+            {"name": "c", "command": "true", "depends_on": ["a"]}
+        ]}"#);
+
+        assert_eq!(config.validate(), Err("dependency cycle: a -> b -> c -> a".to_string()));
+    }
+
+    #[test]
+    fn validate_rejects_items_depending_on_themselves() {
+        let config = config(r#"{"items": [
+            {"name": "data", "command": "true", "depends_on": ["data"]}
+        ]}"#);
+
+        assert_eq!(config.validate(), Err("dependency cycle: data -> data".to_string()));
+    }
+
+    #[test]
+    fn validate_rejects_unknown_prerequisites() {
+        let config = config(r#"{"items": [
+            {"name": "app", "command": "true", "depends_on": ["data"]}
+        ]}"#);
+
+        assert_eq!(config.validate(), Err("'app' depends on unknown item type 'data'".to_string()));
+    }
+}
@@ -1,15 +1,17 @@
 use actix_web::{web, App, HttpServer, middleware::Logger};
 use actix_files as fs;
 use dotenv::dotenv;
-use log::info;
+use log::{info, error};
 use std::env;
 
 mod api;
 mod auth;
+mod config;
 mod models;
 mod storage;
 mod websocket;
 mod scheduler;
+mod runner;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -17,6 +19,11 @@ async fn main() -> std::io::Result<()> {
     dotenv().ok();
     env_logger::init();
     
+    // `blend runner` runs deployment items for a server elsewhere instead of serving
+    if env::args().nth(1).as_deref() == Some("runner") {
+        return runner::run().await;
+    }
+    
     // Print banner to confirm logger is working
     info!("============================================");
     info!("BLEND RELEASE MANAGER STARTING");
@@ -31,6 +38,14 @@ async fn main() -> std::io::Result<()> {
     let db = storage::init().expect("Failed to initialize storage");
     let db_data = web::Data::new(db);
     
+    // Load the deployment item registry up front, a broken config stops blend instead of
+    // deploying without the rules in it
+    let deployment_config = config::load_deployment().map_err(|e| {
+        error!("{}", e);
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+    })?;
+    info!("{} deployment item types registered", deployment_config.items.len());
+    
     // Start scheduler
     let scheduler_db = db_data.clone();
     let _scheduler = scheduler::start_scheduler(scheduler_db);
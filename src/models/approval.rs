@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use super::{Environment, ReleaseStatus};
+use super::user::UserRole;
+
+// A deployment waiting for the approvals its environment's rule asks for
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApprovalRequest {
+    pub environment: Environment,
+    pub target_status: ReleaseStatus, // Status the release moves to once approved
+    pub previous_status: ReleaseStatus, // Status the release goes back to when rejected
+    pub required: u32,
+    pub roles: Vec<UserRole>, // Roles allowed to approve
+    pub allow_author: bool,
+    pub requested_by: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    #[serde(default)]
+    pub rerun_item: Option<String>, // Item that is rerun once approved, instead of the whole release
+}
+
+// An approval or rejection of a deployment
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Approval {
+    pub username: String,
+    pub role: UserRole,
+    pub environment: Environment,
+    pub approved: bool,
+    #[serde(default)]
+    pub comment: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
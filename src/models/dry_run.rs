@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use super::Environment;
+
+// Releases keep this many dry runs, the oldest are dropped
+pub const MAX_DRY_RUNS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum DryRunStatus {
+    Pending,
+    Running,
+    Passed,
+    Failed,
+    Skipped, // Item not rehearsed because a prerequisite failed its dry run
+    Cancelled,
+}
+
+// How one deployment item fared in a dry run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DryRunItem {
+    pub name: String,
+    pub run: u32, // Run of the item its dry run log lines belong to
+    pub wave: usize, // Items of the same wave would deploy in parallel, starting at 1
+    pub command: Option<String>, // Script as resolved on the server
+    pub status: DryRunStatus,
+    #[serde(default)]
+    pub problems: Vec<String>, // Found before the script was called
+    pub exit_code: Option<i32>,
+    pub duration_ms: Option<u64>,
+}
+
+// A rehearsal of a release's deployment: the scripts are resolved and checked, then
+// called with BLEND_DRY_RUN=1 in the order the scheduler would use. It never changes
+// the status of the release or its items.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DryRun {
+    pub id: Uuid,
+    pub environment: Environment,
+    pub requested_by: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub status: DryRunStatus,
+    pub items: Vec<DryRunItem>,
+}
+
+impl DryRun {
+    pub fn item_mut(&mut self, name: &str) -> Option<&mut DryRunItem> {
+        self.items.iter_mut().find(|item| item.name == name)
+    }
+
+    // Passed when every item passed, Cancelled when any was cancelled, Failed otherwise
+    pub fn outcome(&self) -> DryRunStatus {
+        if self.items.iter().all(|item| item.status == DryRunStatus::Passed) {
+            DryRunStatus::Passed
+        } else if self.items.iter().any(|item| item.status == DryRunStatus::Cancelled) {
+            DryRunStatus::Cancelled
+        } else {
+            DryRunStatus::Failed
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use super::Environment;
+
+// A period in which deployments to an environment are forbidden (quarter close, holidays, ...)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FreezeWindow {
+    pub id: Uuid,
+    pub environment: Environment,
+    #[serde(default)]
+    pub client_id: Option<String>, // Only freeze this client's releases, every client when unset
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub reason: String,
+    pub owner: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FreezeWindow {
+    // Check if a deployment of the client's release to the environment at the given time is frozen
+    pub fn covers(&self, environment: &Environment, client_id: &str, at: DateTime<Utc>) -> bool {
+        self.environment == *environment
+            && self.client_id.as_deref().is_none_or(|id| id == client_id)
+            && self.starts_at <= at
+            && at < self.ends_at
+    }
+}
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use super::Environment;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum HealthCheckStatus {
+    Pending,
+    Running,
+    Passed,
+    Failed,
+}
+
+// Outcome of one of the health checks run after a release's items deployed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HealthCheckResult {
+    pub name: String,
+    pub probe: String, // What was probed, e.g. "GET https://example.com/health"
+    pub environment: Environment,
+    pub status: HealthCheckStatus,
+    pub attempts: u32,
+    pub max_attempts: u32,
+    pub output: Option<String>, // What the latest attempt saw
+    pub checked_at: Option<DateTime<Utc>>, // End of the latest attempt
+}
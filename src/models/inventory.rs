@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use super::Environment;
+
+// Output key scripts report the deployed version under, [OUTPUT:version=...]
+pub const VERSION_OUTPUT: &str = "version";
+
+// What is deployed of an item in one of a client's environments
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InventoryEntry {
+    pub client_id: String,
+    pub environment: Environment,
+    pub item: String,
+    pub version: Option<String>,
+    #[serde(default)]
+    pub outputs: BTreeMap<String, String>, // Everything the script reported
+    pub release_id: Option<Uuid>, // None when reported through the API
+    pub release_title: Option<String>,
+    #[serde(default)]
+    pub reported_by: Option<String>, // User who reported it through the API
+    pub deployed_at: DateTime<Utc>, // Latest deployment of the item
+    pub since: DateTime<Utc>, // When the deployed version arrived in the environment
+    #[serde(default)]
+    pub rolled_back_at: Option<DateTime<Utc>>, // The deployment was undone, what's there now isn't known
+}
+
+impl InventoryEntry {
+    // Take over a newer deployment of the same item. A redeployment of the version
+    // that's already there keeps the time it arrived.
+    pub fn replace_with(&mut self, newer: InventoryEntry) {
+        let since = if self.rolled_back_at.is_none() && self.fingerprint().is_some() && self.fingerprint() == newer.fingerprint() {
+            self.since
+        } else {
+            newer.since
+        };
+        *self = newer;
+        self.since = since;
+    }
+
+    // What tells deployments of the item apart: its version, or its outputs for scripts
+    // that don't report one. None when nothing is known.
+    pub fn fingerprint(&self) -> Option<String> {
+        if self.rolled_back_at.is_some() {
+            return None;
+        }
+        if let Some(version) = &self.version {
+            return Some(version.clone());
+        }
+        if self.outputs.is_empty() {
+            return None;
+        }
+        Some(self.outputs.iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(","))
+    }
+}
+
+// An item of a client across its environments
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InventoryRow {
+    pub client_id: String,
+    pub client_name: String,
+    pub item: String,
+    pub development: Option<InventoryEntry>,
+    pub staging: Option<InventoryEntry>,
+    pub production: Option<InventoryEntry>,
+    pub drifted: bool, // The environments run different versions of the item
+}
+
+impl InventoryRow {
+    pub fn new(client_id: String, client_name: String, item: String) -> Self {
+        Self {
+            client_id,
+            client_name,
+            item,
+            development: None,
+            staging: None,
+            production: None,
+            drifted: false,
+        }
+    }
+
+    pub fn set(&mut self, entry: InventoryEntry) {
+        match entry.environment {
+            Environment::Development => self.development = Some(entry),
+            Environment::Staging => self.staging = Some(entry),
+            Environment::Production => self.production = Some(entry),
+        }
+
+        let mut fingerprints: Vec<String> = [&self.development, &self.staging, &self.production]
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.fingerprint())
+            .collect();
+        fingerprints.sort();
+        fingerprints.dedup();
+        self.drifted = fingerprints.len() > 1;
+    }
+}
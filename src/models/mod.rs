@@ -1,7 +1,25 @@
 pub mod release;
 pub mod user;
 pub mod client;
+pub mod freeze;
+pub mod runner;
+pub mod dry_run;
+pub mod approval;
+pub mod health;
+pub mod rollout;
+pub mod train;
+pub mod recurring;
+pub mod inventory;
 
-pub use release::{Release, Environment, ReleaseStatus, DeploymentItem};
+pub use release::{Release, Environment, ReleaseStatus, DeploymentItem, ItemEvent, ItemEventKind, LogLine, LogStream};
 pub use user::User;
 pub use client::Client;
+pub use freeze::FreezeWindow;
+pub use runner::{RunnerLabels, RunnerRegistration, RunnerInfo, RunnerJob, JobOutputLine, JobOutput, JobOutputAck, JobResult};
+pub use dry_run::{DryRun, DryRunItem, DryRunStatus, MAX_DRY_RUNS};
+pub use approval::{Approval, ApprovalRequest};
+pub use health::{HealthCheckResult, HealthCheckStatus};
+pub use rollout::{TargetRun, TargetStatus};
+pub use train::{ReleaseTrain, TrainFailure, TrainStop};
+pub use recurring::{RecurringRelease, RecurringOccurrence};
+pub use inventory::{InventoryEntry, InventoryRow};
@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::str::FromStr;
+use super::Environment;
+
+// Recurring releases keep this many occurrences, the oldest are dropped
+pub const MAX_OCCURRENCES: usize = 50;
+
+const DAY_NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+// What the scheduler did when a recurring release came due
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecurringOccurrence {
+    pub scheduled_for: DateTime<Utc>,
+    pub generated_at: DateTime<Utc>,
+    pub release_id: Option<Uuid>, // The release created for the occurrence
+    pub skipped: Option<String>, // Why no release was created
+}
+
+// A release that is created again on every occurrence of its schedule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecurringRelease {
+    pub id: Uuid,
+    pub title: String,
+    pub client_id: String,
+    pub schedule: String, // Five field cron expression or RRULE
+    pub time_zone: String, // IANA name the schedule is read in
+    pub current_environment: Environment,
+    pub target_environment: Environment,
+    pub deployment_items: Vec<String>,
+    #[serde(default)]
+    pub skip_staging: bool,
+    #[serde(default)]
+    pub item_timeouts: HashMap<String, u64>, // Per-item timeout overrides in seconds
+    pub enabled: bool,
+    pub next_run_at: Option<DateTime<Utc>>, // None while disabled
+    #[serde(default)]
+    pub occurrences: Vec<RecurringOccurrence>, // Oldest first
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RecurringRelease {
+    // The first occurrence of the schedule after the given time
+    pub fn next_occurrence(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let schedule = parse_schedule(&self.schedule).ok()?;
+        let time_zone = parse_time_zone(&self.time_zone).ok()?;
+        schedule.after(&after.with_timezone(&time_zone))
+            .next()
+            .map(|at| at.with_timezone(&Utc))
+    }
+
+    // Record an occurrence, dropping the oldest ones beyond MAX_OCCURRENCES
+    pub fn record_occurrence(&mut self, occurrence: RecurringOccurrence) {
+        self.occurrences.push(occurrence);
+        if self.occurrences.len() > MAX_OCCURRENCES {
+            let excess = self.occurrences.len() - MAX_OCCURRENCES;
+            self.occurrences.drain(..excess);
+        }
+    }
+}
+
+pub fn parse_time_zone(time_zone: &str) -> Result<Tz, String> {
+    Tz::from_str(time_zone.trim()).map_err(|_| format!("Unknown time zone: {}", time_zone))
+}
+
+// Parse a five field cron expression (minute hour day-of-month month day-of-week) or
+// an RRULE with FREQ=DAILY, WEEKLY or MONTHLY and BYDAY, BYMONTHDAY, BYHOUR and BYMINUTE
+pub fn parse_schedule(schedule: &str) -> Result<cron::Schedule, String> {
+    let schedule = schedule.trim();
+    let expression = if schedule.to_uppercase().starts_with("RRULE:") || schedule.to_uppercase().contains("FREQ=") {
+        rrule_to_cron(schedule)?
+    } else {
+        cron_with_seconds(schedule)?
+    };
+
+    cron::Schedule::from_str(&expression).map_err(|e| format!("Invalid schedule {}: {}", schedule, e))
+}
+
+// The cron crate wants a seconds field and counts weekdays from 1 for Sunday, so
+// numeric weekdays of the usual 0-7 form are given by name
+fn cron_with_seconds(schedule: &str) -> Result<String, String> {
+    let fields: Vec<&str> = schedule.split_whitespace().collect();
+    if fields.len() != 5 {
+        return Err(format!("Cron schedule needs 5 fields (minute hour day month weekday), got {}", fields.len()));
+    }
+
+    let weekdays = fields[4].split(',')
+        .map(|part| {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, Some(step)),
+                None => (part, None),
+            };
+            let range = range.split('-')
+                .map(|day| match day.parse::<usize>() {
+                    Ok(day) if day <= 7 => Ok(DAY_NAMES[day % 7].to_string()),
+                    Ok(day) => Err(format!("Invalid weekday {} in schedule", day)),
+                    Err(_) => Ok(day.to_string()),
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .join("-");
+            Ok(match step {
+                Some(step) => format!("{}/{}", range, step),
+                None => range,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?
+        .join(",");
+
+    Ok(format!("0 {} {} {} {} {}", fields[0], fields[1], fields[2], fields[3], weekdays))
+}
+
+fn rrule_to_cron(schedule: &str) -> Result<String, String> {
+    let rule = schedule.trim_start_matches("RRULE:").trim_start_matches("rrule:");
+
+    let mut freq = None;
+    let mut days = "*".to_string();
+    let mut month_days = "*".to_string();
+    let mut hours = "0".to_string();
+    let mut minutes = "0".to_string();
+    for part in rule.split(';').filter(|part| !part.trim().is_empty()) {
+        let (key, value) = part.split_once('=')
+            .ok_or_else(|| format!("Invalid RRULE part: {}", part))?;
+        let value = value.trim();
+        match key.trim().to_uppercase().as_str() {
+            "FREQ" => freq = Some(value.to_uppercase()),
+            "BYDAY" => {
+                days = value.split(',')
+                    .map(|day| match day.trim().to_uppercase().as_str() {
+                        "SU" => Ok(DAY_NAMES[0]),
+                        "MO" => Ok(DAY_NAMES[1]),
+                        "TU" => Ok(DAY_NAMES[2]),
+                        "WE" => Ok(DAY_NAMES[3]),
+                        "TH" => Ok(DAY_NAMES[4]),
+                        "FR" => Ok(DAY_NAMES[5]),
+                        "SA" => Ok(DAY_NAMES[6]),
+                        _ => Err(format!("Unsupported BYDAY value: {}", day)),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(",");
+            }
+            "BYMONTHDAY" => month_days = value.to_string(),
+            "BYHOUR" => hours = value.to_string(),
+            "BYMINUTE" => minutes = value.to_string(),
+            "INTERVAL" if value == "1" => {}
+            other => return Err(format!("RRULE part {} isn't supported", other)),
+        }
+    }
+
+    match freq.as_deref() {
+        Some("DAILY") => {}
+        Some("WEEKLY") if days == "*" => return Err("Weekly RRULE needs BYDAY".to_string()),
+        Some("WEEKLY") => {}
+        Some("MONTHLY") if month_days == "*" => return Err("Monthly RRULE needs BYMONTHDAY".to_string()),
+        Some("MONTHLY") => {}
+        Some(freq) => return Err(format!("RRULE frequency {} isn't supported, use DAILY, WEEKLY or MONTHLY", freq)),
+        None => return Err("RRULE needs a FREQ".to_string()),
+    }
+
+    Ok(format!("0 {} {} {} * {}", minutes, hours, month_days, days))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, TimeZone, Weekday};
+
+    #[test]
+    fn cron_gets_a_seconds_field() {
+        assert_eq!(cron_with_seconds("30 2 * * *"), Ok("0 30 2 * * *".to_string()));
+        assert_eq!(cron_with_seconds("*/15 * 1 * MON"), Ok("0 */15 * 1 * MON".to_string()));
+    }
+
+    #[test]
+    fn sunday_is_both_zero_and_seven() {
+        assert_eq!(cron_with_seconds("0 9 * * 0"), Ok("0 0 9 * * Sun".to_string()));
+        assert_eq!(cron_with_seconds("0 9 * * 7"), Ok("0 0 9 * * Sun".to_string()));
+        assert_eq!(cron_with_seconds("0 9 * * 1-5"), Ok("0 0 9 * * Mon-Fri".to_string()));
+        assert_eq!(cron_with_seconds("0 9 * * 6,0"), Ok("0 0 9 * * Sat,Sun".to_string()));
+        assert_eq!(cron_with_seconds("0 9 * * */2"), Ok("0 0 9 * * */2".to_string()));
+    }
+
+    #[test]
+    fn numeric_sunday_schedules_run_on_sunday() {
+        // 2026-03-02 is a Monday
+        let monday = Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap();
+        for schedule in ["0 9 * * 0", "0 9 * * 7"] {
+            let next = parse_schedule(schedule).unwrap().after(&monday).next().unwrap();
+            assert_eq!(next.weekday(), Weekday::Sun, "{}", schedule);
+        }
+    }
+
+    #[test]
+    fn invalid_cron_schedules_are_rejected() {
+        assert!(cron_with_seconds("0 9 * *").is_err());
+        assert!(cron_with_seconds("0 0 9 * * *").is_err());
+        assert_eq!(cron_with_seconds("0 9 * * 8"), Err("Invalid weekday 8 in schedule".to_string()));
+    }
+
+    #[test]
+    fn rrules_become_cron_expressions() {
+        assert_eq!(rrule_to_cron("RRULE:FREQ=DAILY;BYHOUR=2"), Ok("0 0 2 * * *".to_string()));
+        assert_eq!(rrule_to_cron("FREQ=WEEKLY;BYDAY=MO,FR;BYHOUR=9;BYMINUTE=30"), Ok("0 30 9 * * Mon,Fri".to_string()));
+        assert_eq!(rrule_to_cron("FREQ=WEEKLY;BYDAY=SU;INTERVAL=1"), Ok("0 0 0 * * Sun".to_string()));
+        assert_eq!(rrule_to_cron("FREQ=MONTHLY;BYMONTHDAY=1,15;BYHOUR=6"), Ok("0 0 6 1,15 * *".to_string()));
+    }
+
+    #[test]
+    fn weekly_rrules_need_a_day() {
+        assert_eq!(rrule_to_cron("FREQ=WEEKLY;BYHOUR=9"), Err("Weekly RRULE needs BYDAY".to_string()));
+    }
+
+    #[test]
+    fn unsupported_rrules_are_rejected() {
+        assert_eq!(rrule_to_cron("FREQ=MONTHLY"), Err("Monthly RRULE needs BYMONTHDAY".to_string()));
+        assert_eq!(rrule_to_cron("BYHOUR=9"), Err("RRULE needs a FREQ".to_string()));
+        assert!(rrule_to_cron("FREQ=YEARLY").is_err());
+        assert!(rrule_to_cron("FREQ=DAILY;INTERVAL=2").is_err());
+        assert!(rrule_to_cron("FREQ=WEEKLY;BYDAY=1MO").is_err());
+    }
+}
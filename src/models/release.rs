@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use super::{Approval, ApprovalRequest, DryRun, DryRunStatus, HealthCheckResult, TargetRun, TargetStatus, MAX_DRY_RUNS};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Environment {
@@ -9,6 +11,35 @@ pub enum Environment {
     Production,
 }
 
+impl Environment {
+    // Lowercase name passed to the deployment scripts
+    pub fn script_name(&self) -> &'static str {
+        match self {
+            Environment::Development => "development",
+            Environment::Staging => "staging",
+            Environment::Production => "production",
+        }
+    }
+
+    // Status used while items are being deployed to this environment
+    pub fn deploying_status(&self) -> ReleaseStatus {
+        match self {
+            Environment::Development |
+            Environment::Staging => ReleaseStatus::DeployingToStaging,
+            Environment::Production => ReleaseStatus::DeployingToProduction,
+        }
+    }
+
+    // Status of items that were deployed to this environment successfully
+    pub fn deployed_status(&self) -> ReleaseStatus {
+        match self {
+            Environment::Development |
+            Environment::Staging => ReleaseStatus::ReadyToTestInStaging,
+            Environment::Production => ReleaseStatus::ReadyToTestInProduction,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ReleaseStatus {
     // Development phase
@@ -28,17 +59,254 @@ pub enum ReleaseStatus {
     ReadyToTestInProduction,
     ClearedInProduction,
     
+    // Waiting for the approvals the target environment requires
+    AwaitingApproval,
+    
     // Error states
     Error,
     Blocked,
+    Cancelled, // Stopped by a user, as opposed to failing on its own
+    Skipped,   // Deployment item not started because a prerequisite didn't succeed
+    
+    // Rollback
+    RollingBack,
+    RolledBack, // Deployed items were undone with their rollback commands
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+    System, // Messages from the scheduler itself (exit codes, timeouts, ...)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LogLine {
+    pub timestamp: DateTime<Utc>,
+    pub stream: LogStream,
+    pub item_name: String,
+    pub line: String,
+    #[serde(default)]
+    pub run: u32, // Which run of the item produced this line, starting at 1
+    #[serde(default)]
+    pub dry_run: bool, // The run was a dry run
+}
+
+// Structured events reported by a deployment script through its output
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum ItemEventKind {
+    Step { name: String },
+    Warning { message: String },
+    Output { key: String, value: String },
+    Artifact { name: String, location: String },
+    ConfirmationRequested { prompt: String },
+    ConfirmationAnswered { approved: bool, answered_by: String },
+    ManualStepRequested { instructions: String },
+    ManualStepCompleted { done: bool, completed_by: String, note: Option<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ItemEvent {
+    pub timestamp: DateTime<Utc>,
+    pub run: u32, // Which run of the item reported the event
+    pub kind: ItemEventKind,
+}
+
+// Who marked a manual step done or failed, and when
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ManualCompletion {
+    pub done: bool,
+    pub completed_by: String,
+    pub completed_at: DateTime<Utc>,
+    pub note: Option<String>,
+}
+
+// Oldest events are dropped once an item has this many
+const MAX_ITEM_EVENTS: usize = 500;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct DeploymentItem {
     pub name: String, // "data", "solr", or "app"
     pub status: ReleaseStatus,
-    pub logs: Vec<String>,
     pub error: Option<String>,
+    #[serde(default)]
+    pub runs: u32, // Number of times the item has been started
+    #[serde(default)]
+    pub attempt: u32, // Attempt of the current or latest deployment, starting at 1
+    #[serde(default)]
+    pub max_attempts: u32, // Attempts allowed by the item's retry policy
+    #[serde(default)]
+    pub progress: f32, // 0.0 to 100.0 as reported by the current or latest run
+    #[serde(default)]
+    pub started_at: Option<DateTime<Utc>>, // Start of the current or latest run
+    #[serde(default)]
+    pub finished_at: Option<DateTime<Utc>>, // End of the latest run, None while it's running
+    #[serde(default)]
+    pub duration_ms: Option<u64>, // How long the latest finished run took
+    #[serde(default)]
+    pub exit_code: Option<i32>, // Exit code of the latest run, None if the script was stopped
+    #[serde(default)]
+    pub events: Vec<ItemEvent>,
+    #[serde(default)]
+    pub current_step: Option<String>, // Latest step the running script announced
+    #[serde(default)]
+    pub outputs: BTreeMap<String, String>, // Key/value outputs of the latest run
+    #[serde(default)]
+    pub pending_confirmation: Option<String>, // Prompt the script is waiting on a human for
+    #[serde(default)]
+    pub pending_manual_step: Option<String>, // Instructions of the manual step waiting on a human
+    #[serde(default)]
+    pub manual_completion: Option<ManualCompletion>, // Outcome of the latest run's manual step
+    #[serde(default)]
+    pub targets: Vec<TargetRun>, // Hosts of a phased rollout, empty when the item runs once
+}
+
+impl DeploymentItem {
+    pub fn new(name: String, status: ReleaseStatus) -> Self {
+        Self {
+            name,
+            status,
+            error: None,
+            runs: 0,
+            attempt: 0,
+            max_attempts: 0,
+            progress: 0.0,
+            started_at: None,
+            finished_at: None,
+            duration_ms: None,
+            exit_code: None,
+            events: Vec::new(),
+            current_step: None,
+            outputs: BTreeMap::new(),
+            pending_confirmation: None,
+            pending_manual_step: None,
+            manual_completion: None,
+            targets: Vec::new(),
+        }
+    }
+
+    // Reset the run details for a new run of the item's script
+    pub fn start_run(&mut self) {
+        self.runs += 1;
+        self.error = None;
+        self.progress = 0.0;
+        self.started_at = Some(Utc::now());
+        self.finished_at = None;
+        self.duration_ms = None;
+        self.exit_code = None;
+        self.current_step = None;
+        self.outputs.clear();
+        self.pending_confirmation = None;
+        self.pending_manual_step = None;
+        self.manual_completion = None;
+    }
+
+    // A dry run is numbered like any other run but leaves the item's state alone
+    pub fn start_dry_run(&mut self) -> u32 {
+        self.runs += 1;
+        self.runs
+    }
+
+    // Record the end of the current run
+    pub fn finish_run(&mut self, exit_code: Option<i32>) {
+        let finished_at = Utc::now();
+        self.duration_ms = self.started_at
+            .map(|started_at| (finished_at - started_at).num_milliseconds().max(0) as u64);
+        self.finished_at = Some(finished_at);
+        self.exit_code = exit_code;
+        self.pending_confirmation = None;
+        self.pending_manual_step = None;
+        if exit_code == Some(0) {
+            self.progress = 100.0;
+        }
+    }
+
+    // Lay out the targets of a rollout run. Targets that already succeeded are kept
+    // when `keep_succeeded` is set, so a retry only deploys the others.
+    pub fn plan_targets(&mut self, targets: Vec<TargetRun>, keep_succeeded: bool) {
+        let previous = std::mem::take(&mut self.targets);
+        self.targets = targets.into_iter()
+            .map(|target| match previous.iter().find(|old| old.name == target.name) {
+                Some(old) if keep_succeeded && old.status == TargetStatus::Succeeded => old.clone(),
+                _ => target,
+            })
+            .collect();
+        self.progress = self.target_progress();
+    }
+
+    // Lay out the targets of a rollback. Only the targets the item's last run deployed
+    // to are rolled back, the others are skipped.
+    pub fn plan_rollback_targets(&mut self, targets: Vec<TargetRun>) {
+        let deployed: Vec<String> = self.targets.iter()
+            .filter(|target| target.status == TargetStatus::Succeeded)
+            .map(|target| target.name.clone())
+            .collect();
+        self.targets = targets.into_iter()
+            .map(|mut target| {
+                if !deployed.contains(&target.name) {
+                    target.status = TargetStatus::Skipped;
+                    target.error = Some("Not rolled back because it wasn't deployed".to_string());
+                }
+                target
+            })
+            .collect();
+        self.progress = self.target_progress();
+    }
+
+    pub fn target_mut(&mut self, name: &str) -> Option<&mut TargetRun> {
+        self.targets.iter_mut().find(|target| target.name == name)
+    }
+
+    // Progress over all targets, each target weighing the same
+    pub fn target_progress(&self) -> f32 {
+        if self.targets.is_empty() {
+            return self.progress;
+        }
+        self.targets.iter().map(|target| target.progress.clamp(0.0, 100.0)).sum::<f32>() / self.targets.len() as f32
+    }
+
+    // Store an event and apply it to the item's current state
+    pub fn record_event(&mut self, event: ItemEvent) {
+        match &event.kind {
+            ItemEventKind::Step { name } => self.current_step = Some(name.clone()),
+            ItemEventKind::Output { key, value } => {
+                self.outputs.insert(key.clone(), value.clone());
+            }
+            ItemEventKind::ConfirmationRequested { prompt } => self.pending_confirmation = Some(prompt.clone()),
+            ItemEventKind::ConfirmationAnswered { .. } => self.pending_confirmation = None,
+            ItemEventKind::ManualStepRequested { instructions } => self.pending_manual_step = Some(instructions.clone()),
+            ItemEventKind::ManualStepCompleted { done, completed_by, note } => {
+                self.pending_manual_step = None;
+                self.manual_completion = Some(ManualCompletion {
+                    done: *done,
+                    completed_by: completed_by.clone(),
+                    completed_at: event.timestamp,
+                    note: note.clone(),
+                });
+            }
+            ItemEventKind::Warning { .. } |
+            ItemEventKind::Artifact { .. } => {}
+        }
+        
+        self.events.push(event);
+        if self.events.len() > MAX_ITEM_EVENTS {
+            let excess = self.events.len() - MAX_ITEM_EVENTS;
+            self.events.drain(..excess);
+        }
+    }
+
+    // Progress counted towards the release, deployed items are always complete
+    pub fn effective_progress(&self) -> f32 {
+        match self.status {
+            ReleaseStatus::ReadyToTestInStaging |
+            ReleaseStatus::ReadyToTestInProduction |
+            ReleaseStatus::ClearedInStaging |
+            ReleaseStatus::ClearedInProduction => 100.0,
+            ReleaseStatus::Skipped => 0.0,
+            _ => self.progress.clamp(0.0, 100.0),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -56,6 +324,24 @@ pub struct Release {
     pub progress: f32, // 0.0 to 100.0
     #[serde(default)]
     pub skip_staging: bool, // Flag to indicate if staging should be skipped
+    #[serde(default)]
+    pub deployment_environment: Option<Environment>, // Environment of the latest deployment run
+    #[serde(default)]
+    pub item_timeouts: HashMap<String, u64>, // Per-item timeout overrides in seconds
+    #[serde(default)]
+    pub queue_position: Option<usize>, // Position while waiting for a free deployment slot
+    #[serde(default)]
+    pub freeze_override_by: Option<String>, // Admin who let the release deploy during a freeze
+    #[serde(default)]
+    pub dry_runs: Vec<DryRun>, // Rehearsals of the deployment, oldest first
+    #[serde(default)]
+    pub approval_request: Option<ApprovalRequest>, // Set while the release is AwaitingApproval
+    #[serde(default)]
+    pub approvals: Vec<Approval>, // Every approval and rejection, oldest first
+    #[serde(default)]
+    pub health_checks: Vec<HealthCheckResult>, // Checks run after the latest deployment
+    #[serde(default)]
+    pub train_id: Option<Uuid>, // Release train the release belongs to
 }
 
 impl Release {
@@ -71,12 +357,7 @@ impl Release {
     ) -> Self {
         let deployment_items = deployment_items
             .into_iter()
-            .map(|name| DeploymentItem {
-                name,
-                status: ReleaseStatus::InDevelopment,
-                logs: Vec::new(),
-                error: None,
-            })
+            .map(|name| DeploymentItem::new(name, ReleaseStatus::InDevelopment))
             .collect();
 
         Self {
@@ -92,28 +373,30 @@ impl Release {
             created_by,
             progress: 0.0,
             skip_staging,
+            deployment_environment: None,
+            item_timeouts: HashMap::new(),
+            queue_position: None,
+            freeze_override_by: None,
+            dry_runs: Vec::new(),
+            approval_request: None,
+            approvals: Vec::new(),
+            health_checks: Vec::new(),
+            train_id: None,
         }
     }
 
-    // Calculate aggregated progress from deployment items
+    // Calculate aggregated progress from deployment items, each item weighing the same
     pub fn calculate_progress(&self) -> f32 {
         if self.deployment_items.is_empty() {
             return self.progress;
         }
 
         let item_count = self.deployment_items.len() as f32;
-        let completed_progress: f32 = self.deployment_items.iter()
-            .map(|item| match item.status {
-                ReleaseStatus::ReadyToTestInStaging | 
-                ReleaseStatus::ReadyToTestInProduction | 
-                ReleaseStatus::ClearedInStaging |
-                ReleaseStatus::ClearedInProduction => 100.0,
-                ReleaseStatus::Error => 0.0,
-                _ => 0.0 // For items still in progress, we'll rely on the individual progress
-            })
+        let total_progress: f32 = self.deployment_items.iter()
+            .map(DeploymentItem::effective_progress)
             .sum();
 
-        completed_progress / item_count
+        total_progress / item_count
     }
 
     // Check if all deployment items are completed
@@ -132,6 +415,50 @@ impl Release {
         })
     }
 
+    // Move the release to its post-deployment status once every item has finished.
+    // Returns None while items are still running, otherwise the new release status.
+    pub fn apply_deployment_outcome(&mut self) -> Option<ReleaseStatus> {
+        let all_completed = self.deployment_items.iter().all(|item| {
+            matches!(item.status,
+                ReleaseStatus::ReadyToTestInStaging |
+                ReleaseStatus::ReadyToTestInProduction |
+                ReleaseStatus::Error |
+                ReleaseStatus::Cancelled |
+                ReleaseStatus::Skipped)
+        });
+
+        if !all_completed {
+            return None;
+        }
+
+        if self.has_errors() {
+            self.status = ReleaseStatus::Error;
+            self.progress = self.calculate_progress();
+        } else if self.deployment_items.iter().any(|item| item.status == ReleaseStatus::Cancelled) {
+            self.status = ReleaseStatus::Cancelled;
+            self.progress = self.calculate_progress();
+        } else if self.deployment_items.iter().any(|item| item.status == ReleaseStatus::Skipped) {
+            // Skipped items never got deployed, so the release didn't fully succeed
+            self.status = ReleaseStatus::Error;
+            self.progress = self.calculate_progress();
+        } else {
+            // Set next status based on current status
+            self.status = match self.status {
+                ReleaseStatus::DeployingToStaging => ReleaseStatus::ReadyToTestInStaging,
+                ReleaseStatus::DeployingToProduction => ReleaseStatus::ReadyToTestInProduction,
+                _ => self.status.clone(),
+            };
+            self.progress = 100.0;
+
+            // Update all deployment items to match the release status
+            for item in self.deployment_items.iter_mut() {
+                item.status = self.status.clone();
+            }
+        }
+
+        Some(self.status.clone())
+    }
+
     // Check if any deployment items have errors
     pub fn has_errors(&self) -> bool {
         self.deployment_items.iter().any(|item| matches!(item.status, ReleaseStatus::Error))
@@ -157,9 +484,21 @@ impl Release {
             ReleaseStatus::ReadyToTestInProduction | 
             ReleaseStatus::ClearedInProduction => Environment::Production,
             
-            // Error and blocked states - stay in current environment
+            // Waits where it was cleared from
+            ReleaseStatus::AwaitingApproval => match &self.approval_request {
+                Some(request) if matches!(request.previous_status,
+                    ReleaseStatus::ReadyToTestInStaging |
+                    ReleaseStatus::ClearedInStaging) => Environment::Staging,
+                _ => self.current_environment.clone(),
+            },
+            
+            // Error, blocked and cancelled states - stay in current environment
             ReleaseStatus::Error |
-            ReleaseStatus::Blocked => self.current_environment.clone(),
+            ReleaseStatus::Blocked |
+            ReleaseStatus::Cancelled |
+            ReleaseStatus::Skipped |
+            ReleaseStatus::RollingBack |
+            ReleaseStatus::RolledBack => self.current_environment.clone(),
         }
     }
     
@@ -176,6 +515,107 @@ impl Release {
         }
     }
     
+    // Keep a new dry run, dropping the oldest beyond the limit
+    pub fn add_dry_run(&mut self, dry_run: DryRun) {
+        self.dry_runs.push(dry_run);
+        if self.dry_runs.len() > MAX_DRY_RUNS {
+            let excess = self.dry_runs.len() - MAX_DRY_RUNS;
+            self.dry_runs.drain(..excess);
+        }
+    }
+    
+    pub fn dry_run_mut(&mut self, id: Uuid) -> Option<&mut DryRun> {
+        self.dry_runs.iter_mut().find(|dry_run| dry_run.id == id)
+    }
+    
+    // The dry run still in progress, if any
+    pub fn running_dry_run(&self) -> Option<&DryRun> {
+        self.dry_runs.iter().rev().find(|dry_run| dry_run.status == DryRunStatus::Running)
+    }
+    
+    // Approvals and rejections of the pending approval request
+    pub fn current_approvals(&self) -> Vec<&Approval> {
+        match &self.approval_request {
+            Some(request) => self.approvals.iter()
+                .filter(|approval| approval.environment == request.environment
+                    && approval.timestamp >= request.requested_at)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+    
+    // Hold a deployment that was just cleared until the approvals it needs are in
+    pub fn request_approval(&mut self, request: ApprovalRequest) {
+        self.status = ReleaseStatus::AwaitingApproval;
+        self.approval_request = Some(request);
+    }
+    
+    // Record an approval or rejection of the pending approval request. A rejection
+    // sends the release back to where it was cleared from, enough approvals send it
+    // on to its deployment.
+    pub fn record_approval(&mut self, approval: Approval) -> Result<(), String> {
+        let request = match &self.approval_request {
+            Some(request) if self.status == ReleaseStatus::AwaitingApproval => request.clone(),
+            _ => return Err("Release is not awaiting approval".to_string()),
+        };
+        if self.current_approvals().iter().any(|existing| existing.username == approval.username) {
+            return Err(format!("{} already answered this approval request", approval.username));
+        }
+        
+        let approved = approval.approved;
+        self.approvals.push(approval);
+        
+        if !approved {
+            self.status = request.previous_status;
+            self.approval_request = None;
+        } else if self.approval_count() >= request.required {
+            self.status = request.target_status;
+            self.approval_request = None;
+            match &request.rerun_item {
+                // Only the rerun item deploys again, the others keep their outcome
+                Some(item_name) => {
+                    if let Some(item) = self.deployment_items.iter_mut().find(|item| item.name == *item_name) {
+                        item.status = self.status.clone();
+                        item.error = None;
+                        item.progress = 0.0;
+                    }
+                    self.progress = self.calculate_progress();
+                }
+                None => {
+                    self.progress = 0.0;
+                    for item in self.deployment_items.iter_mut() {
+                        if item.status != ReleaseStatus::Error {
+                            item.status = self.status.clone();
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    // Number of approvals the pending approval request got so far
+    pub fn approval_count(&self) -> u32 {
+        self.current_approvals().iter().filter(|approval| approval.approved).count() as u32
+    }
+    
+    // Environment a waiting release is going to be deployed to
+    pub fn pending_deployment_environment(&self) -> Option<Environment> {
+        match self.status {
+            ReleaseStatus::WaitingForStaging => Some(Environment::Staging),
+            ReleaseStatus::WaitingForProduction |
+            ReleaseStatus::WaitingForProductionFromStaging => Some(Environment::Production),
+            _ => None,
+        }
+    }
+    
+    // Environment of the deployment that is waiting or running now, the one that
+    // cancelling or answering its scripts affects
+    pub fn current_deployment_environment(&self) -> Environment {
+        self.pending_deployment_environment()
+            .unwrap_or_else(|| self.last_deployment_environment())
+    }
+    
     // Get next status after deployment completes successfully
     pub fn next_status_after_deployment(&self) -> ReleaseStatus {
         match self.status {
@@ -190,6 +630,105 @@ impl Release {
         }
     }
     
+    // Environment the release's next deployment goes to, what a dry run rehearses by default
+    pub fn next_deployment_environment(&self) -> Environment {
+        if let Some(environment) = self.pending_deployment_environment() {
+            return environment;
+        }
+        
+        match self.status {
+            ReleaseStatus::InDevelopment |
+            ReleaseStatus::ClearedInDevelopment if !self.skip_staging => Environment::Staging,
+            ReleaseStatus::InDevelopment |
+            ReleaseStatus::ClearedInDevelopment |
+            ReleaseStatus::ReadyToTestInStaging |
+            ReleaseStatus::ClearedInStaging => Environment::Production,
+            _ => match self.last_deployment_environment() {
+                Environment::Development => Environment::Staging,
+                environment => environment,
+            },
+        }
+    }
+    
+    // Environment the release was last deployed to, used when rerunning single items.
+    // Older records don't store it, so fall back to the status and then the pipeline.
+    pub fn last_deployment_environment(&self) -> Environment {
+        if let Some(env) = &self.deployment_environment {
+            return env.clone();
+        }
+
+        match self.status {
+            ReleaseStatus::WaitingForStaging |
+            ReleaseStatus::DeployingToStaging |
+            ReleaseStatus::ReadyToTestInStaging |
+            ReleaseStatus::ClearedInStaging => Environment::Staging,
+
+            ReleaseStatus::WaitingForProduction |
+            ReleaseStatus::WaitingForProductionFromStaging |
+            ReleaseStatus::DeployingToProduction |
+            ReleaseStatus::ReadyToTestInProduction |
+            ReleaseStatus::ClearedInProduction => Environment::Production,
+
+            _ => match self.current_environment {
+                Environment::Development if !self.skip_staging => Environment::Staging,
+                _ => Environment::Production,
+            },
+        }
+    }
+
+    // Check if a single item of this release can be rerun
+    pub fn can_rerun_items(&self) -> bool {
+        matches!(self.status,
+            ReleaseStatus::DeployingToStaging |
+            ReleaseStatus::DeployingToProduction |
+            ReleaseStatus::ReadyToTestInStaging |
+            ReleaseStatus::ReadyToTestInProduction |
+            ReleaseStatus::Error |
+            ReleaseStatus::Cancelled |
+            ReleaseStatus::RolledBack)
+    }
+
+    // Check if the release is still on its way, rather than finished or given up on
+    pub fn is_active(&self) -> bool {
+        !matches!(self.status,
+            ReleaseStatus::ClearedInProduction |
+            ReleaseStatus::Error |
+            ReleaseStatus::Blocked |
+            ReleaseStatus::Cancelled |
+            ReleaseStatus::Skipped |
+            ReleaseStatus::RolledBack)
+    }
+
+    // Check if the release is waiting for or running a deployment that can be cancelled
+    pub fn can_be_cancelled(&self) -> bool {
+        matches!(self.status,
+            ReleaseStatus::WaitingForStaging |
+            ReleaseStatus::WaitingForProduction |
+            ReleaseStatus::WaitingForProductionFromStaging |
+            ReleaseStatus::DeployingToStaging |
+            ReleaseStatus::DeployingToProduction)
+    }
+
+    // Check if the release's deployment can be rehearsed, not while its scripts run for real
+    pub fn can_dry_run(&self) -> bool {
+        !self.deployment_items.is_empty() && !matches!(self.status,
+            ReleaseStatus::DeployingToStaging |
+            ReleaseStatus::DeployingToProduction |
+            ReleaseStatus::RollingBack)
+    }
+
+    // Check if the items deployed by the latest run can be rolled back
+    pub fn can_be_rolled_back(&self) -> bool {
+        let finished = matches!(self.status,
+            ReleaseStatus::ReadyToTestInStaging |
+            ReleaseStatus::ReadyToTestInProduction |
+            ReleaseStatus::Error |
+            ReleaseStatus::Cancelled);
+        let deployed_status = self.last_deployment_environment().deployed_status();
+        
+        finished && self.deployment_items.iter().any(|item| item.status == deployed_status)
+    }
+
     // Get next status after deployment fails
     pub fn error_status(&self) -> ReleaseStatus {
         ReleaseStatus::Error
@@ -227,3 +766,177 @@ impl Release {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::UserRole;
+
+    fn release_awaiting(required: u32, rerun_item: Option<&str>) -> Release {
+        let mut release = Release::new(
+            "Release 1.5".to_string(),
+            "client".to_string(),
+            Environment::Staging,
+            Environment::Production,
+            vec!["data".to_string(), "app".to_string()],
+            Utc::now(),
+            "author".to_string(),
+            false,
+        );
+        release.status = ReleaseStatus::ReadyToTestInProduction;
+        for item in release.deployment_items.iter_mut() {
+            item.status = ReleaseStatus::ReadyToTestInProduction;
+        }
+        let target_status = if rerun_item.is_some() { ReleaseStatus::DeployingToProduction } else { ReleaseStatus::WaitingForProduction };
+        release.request_approval(ApprovalRequest {
+            environment: Environment::Production,
+            target_status,
+            previous_status: ReleaseStatus::ReadyToTestInProduction,
+            required,
+            roles: vec![UserRole::Admin],
+            allow_author: false,
+            requested_by: Some("author".to_string()),
+            requested_at: Utc::now(),
+            rerun_item: rerun_item.map(str::to_string),
+        });
+        release
+    }
+
+    fn release_with_items(items: &[&str]) -> Release {
+        Release::new(
+            "Release 1.5".to_string(),
+            "client".to_string(),
+            Environment::Development,
+            Environment::Staging,
+            items.iter().map(|item| item.to_string()).collect(),
+            Utc::now(),
+            "author".to_string(),
+            false,
+        )
+    }
+
+    fn answer(username: &str, approved: bool) -> Approval {
+        Approval {
+            username: username.to_string(),
+            role: UserRole::Admin,
+            environment: Environment::Production,
+            approved,
+            comment: None,
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn item_statuses(release: &Release) -> Vec<ReleaseStatus> {
+        release.deployment_items.iter().map(|item| item.status.clone()).collect()
+    }
+
+    #[test]
+    fn enough_approvals_send_the_release_on() {
+        let mut release = release_awaiting(2, None);
+        release.deployment_items[0].status = ReleaseStatus::Error;
+
+        assert_eq!(release.record_approval(answer("alice", true)), Ok(()));
+        assert_eq!(release.status, ReleaseStatus::AwaitingApproval);
+        assert_eq!(release.approval_count(), 1);
+
+        assert_eq!(release.record_approval(answer("bob", true)), Ok(()));
+        assert_eq!(release.status, ReleaseStatus::WaitingForProduction);
+        assert_eq!(release.approval_request, None);
+        assert_eq!(item_statuses(&release), vec![ReleaseStatus::Error, ReleaseStatus::WaitingForProduction]);
+        assert_eq!(release.approvals.len(), 2);
+    }
+
+    #[test]
+    fn a_rejection_sends_the_release_back() {
+        let mut release = release_awaiting(2, None);
+
+        assert_eq!(release.record_approval(answer("alice", true)), Ok(()));
+        assert_eq!(release.record_approval(answer("bob", false)), Ok(()));
+        assert_eq!(release.status, ReleaseStatus::ReadyToTestInProduction);
+        assert_eq!(release.approval_request, None);
+        assert_eq!(item_statuses(&release), vec![ReleaseStatus::ReadyToTestInProduction; 2]);
+    }
+
+    #[test]
+    fn every_user_answers_once() {
+        let mut release = release_awaiting(2, None);
+
+        assert_eq!(release.record_approval(answer("alice", true)), Ok(()));
+        assert_eq!(release.record_approval(answer("alice", true)), Err("alice already answered this approval request".to_string()));
+        assert_eq!(release.approval_count(), 1);
+        assert_eq!(release.approvals.len(), 1);
+    }
+
+    #[test]
+    fn answers_to_an_earlier_request_do_not_count() {
+        let mut release = release_awaiting(1, None);
+        let mut earlier = answer("alice", true);
+        earlier.timestamp = Utc::now() - chrono::Duration::hours(1);
+        release.approvals.push(earlier);
+
+        assert_eq!(release.approval_count(), 0);
+        assert_eq!(release.record_approval(answer("alice", true)), Ok(()));
+        assert_eq!(release.status, ReleaseStatus::WaitingForProduction);
+    }
+
+    #[test]
+    fn only_releases_awaiting_approval_take_answers() {
+        let mut release = release_awaiting(1, None);
+        release.status = ReleaseStatus::Cancelled;
+
+        assert_eq!(release.record_approval(answer("alice", true)), Err("Release is not awaiting approval".to_string()));
+        assert!(release.approvals.is_empty());
+    }
+
+    #[test]
+    fn an_approved_rerun_only_deploys_its_item() {
+        let mut release = release_awaiting(1, Some("app"));
+        release.deployment_items[1].error = Some("exit code 1".to_string());
+
+        assert_eq!(release.record_approval(answer("alice", true)), Ok(()));
+        assert_eq!(release.status, ReleaseStatus::DeployingToProduction);
+        assert_eq!(item_statuses(&release), vec![ReleaseStatus::ReadyToTestInProduction, ReleaseStatus::DeployingToProduction]);
+        assert_eq!(release.deployment_items[1].error, None);
+    }
+
+    #[test]
+    fn release_progress_averages_its_items() {
+        let mut release = release_with_items(&["data", "solr", "app", "search-index"]);
+        let items = &mut release.deployment_items;
+        // Finished items are complete whatever they reported last
+        items[0].status = ReleaseStatus::ReadyToTestInStaging;
+        items[0].progress = 40.0;
+        items[1].status = ReleaseStatus::DeployingToStaging;
+        items[1].progress = 50.0;
+        // A failed item counts as far as it got, a skipped one not at all
+        items[2].status = ReleaseStatus::Error;
+        items[2].progress = 30.0;
+        items[3].status = ReleaseStatus::Skipped;
+        items[3].progress = 80.0;
+
+        assert_eq!(release.deployment_items.iter().map(DeploymentItem::effective_progress).collect::<Vec<_>>(),
+            vec![100.0, 50.0, 30.0, 0.0]);
+        assert_eq!(release.calculate_progress(), 45.0);
+    }
+
+    #[test]
+    fn item_progress_stays_within_bounds() {
+        let mut release = release_with_items(&["data", "app"]);
+        release.deployment_items[0].status = ReleaseStatus::DeployingToStaging;
+        release.deployment_items[0].progress = 150.0;
+        release.deployment_items[1].status = ReleaseStatus::DeployingToStaging;
+        release.deployment_items[1].progress = -10.0;
+
+        assert_eq!(release.deployment_items[0].effective_progress(), 100.0);
+        assert_eq!(release.deployment_items[1].effective_progress(), 0.0);
+        assert_eq!(release.calculate_progress(), 50.0);
+    }
+
+    #[test]
+    fn a_release_without_items_keeps_its_progress() {
+        let mut release = release_with_items(&[]);
+        release.progress = 70.0;
+
+        assert_eq!(release.calculate_progress(), 70.0);
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum TargetStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    Skipped, // Not started because an earlier batch failed
+}
+
+// A deployment item's run on one of the targets of a phased rollout
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TargetRun {
+    pub name: String,
+    pub batch: usize, // Batch the target is deployed in, starting at 1
+    pub status: TargetStatus,
+    #[serde(default)]
+    pub progress: f32,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+impl TargetRun {
+    pub fn new(name: String, batch: usize) -> Self {
+        Self {
+            name,
+            batch,
+            status: TargetStatus::Pending,
+            progress: 0.0,
+            started_at: None,
+            finished_at: None,
+            exit_code: None,
+            error: None,
+        }
+    }
+}
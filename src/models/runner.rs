@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use super::LogStream;
+
+// What a runner is allowed to pick up. Empty lists match everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RunnerLabels {
+    #[serde(default)]
+    pub clients: Vec<String>, // Client ids or names
+    #[serde(default)]
+    pub environments: Vec<String>, // "staging", "production", ...
+    #[serde(default)]
+    pub items: Vec<String>, // Deployment item types
+}
+
+impl RunnerLabels {
+    // Check if a job for the client, environment and item type may run on the runner
+    pub fn matches(&self, client_id: &str, client_name: &str, env_name: &str, item_name: &str) -> bool {
+        (self.clients.is_empty() || self.clients.iter().any(|client| client == client_id || client == client_name))
+            && (self.environments.is_empty() || self.environments.iter().any(|env| env == env_name))
+            && (self.items.is_empty() || self.items.iter().any(|item| item == item_name))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunnerRegistration {
+    pub name: String,
+    #[serde(default)]
+    pub labels: RunnerLabels,
+}
+
+// A registered runner as shown by the API
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunnerInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub labels: RunnerLabels,
+    pub registered_at: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub job: Option<Uuid>, // Job the runner is working on
+}
+
+// A deployment item run handed to a runner
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunnerJob {
+    pub id: Uuid,
+    pub release_id: Uuid,
+    pub item_name: String,
+    pub env_name: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub working_dir: Option<String>,
+    pub env: HashMap<String, String>, // Includes the client's secrets
+    pub run: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JobOutputLine {
+    pub stream: LogStream,
+    pub line: String,
+}
+
+// Output a runner sends while its job runs, also serves as its heartbeat
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct JobOutput {
+    #[serde(default)]
+    pub lines: Vec<JobOutputLine>,
+}
+
+// The server's reply to a batch of output
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct JobOutputAck {
+    pub cancelled: bool, // Stop the script
+    #[serde(default)]
+    pub answers: Vec<String>, // Lines to write to the script's stdin
+}
+
+// How a job ended, either the script's exit code or why it couldn't run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JobResult {
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use super::Environment;
+
+// A client release of the train that failed to deploy
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrainFailure {
+    pub release_id: Uuid,
+    pub client_id: String,
+    pub environment: Environment,
+    pub error: Option<String>,
+    pub failed_at: DateTime<Utc>,
+}
+
+// Why a train stopped sending its releases on
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrainStop {
+    pub reason: String,
+    pub stopped_by: Option<String>, // None when the failure limit stopped it
+    pub stopped_at: DateTime<Utc>,
+}
+
+// Releases of the same change for many clients, sharing their schedule, deployment
+// items and pipeline. Every client keeps its own release on the board.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReleaseTrain {
+    pub id: Uuid,
+    pub title: String,
+    pub client_ids: Vec<String>,
+    pub current_environment: Environment,
+    pub target_environment: Environment,
+    pub deployment_items: Vec<String>,
+    pub scheduled_at: DateTime<Utc>,
+    #[serde(default)]
+    pub skip_staging: bool,
+    #[serde(default)]
+    pub item_timeouts: HashMap<String, u64>, // Per-item timeout overrides in seconds
+    #[serde(default)]
+    pub max_failures: Option<usize>, // Stop the train after this many client failures
+    #[serde(default)]
+    pub failures: Vec<TrainFailure>, // Oldest first
+    #[serde(default)]
+    pub stopped: Option<TrainStop>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ReleaseTrain {
+    // Record a failed client release once per deployment. Returns true when the
+    // failure was new.
+    pub fn record_failure(&mut self, failure: TrainFailure) -> bool {
+        let known = self.failures.iter()
+            .any(|known| known.release_id == failure.release_id && known.environment == failure.environment);
+        if known {
+            return false;
+        }
+        self.failures.push(failure);
+        true
+    }
+
+    // Check if the train has reached the failures it was allowed
+    pub fn failure_limit_reached(&self) -> bool {
+        self.max_failures.is_some_and(|max| self.failures.len() >= max)
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use super::Environment;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum UserRole {
@@ -33,4 +34,13 @@ impl User {
     pub fn can_deploy_to_production(&self) -> bool {
         matches!(self.role, UserRole::Admin)
     }
+    
+    // Check if user can move releases into the environment
+    pub fn can_deploy_to(&self, environment: &Environment) -> bool {
+        match environment {
+            Environment::Production => self.can_deploy_to_production(),
+            Environment::Development |
+            Environment::Staging => self.can_deploy_to_staging(),
+        }
+    }
 }
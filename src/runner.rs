@@ -0,0 +1,280 @@
+// `blend runner`: an agent that runs deployment items on its own host. It registers
+// with the blend server, long-polls for jobs matching its labels, runs them and
+// streams their output and exit status back.
+use std::process::Stdio;
+use log::{info, warn, error};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+use uuid::Uuid;
+use crate::models::{JobOutput, JobOutputAck, JobOutputLine, JobResult, LogStream, RunnerJob, RunnerLabels, RunnerRegistration};
+use crate::scheduler::terminate_process_group;
+
+// Server the runner connects to when RUNNER_SERVER_URL isn't set
+const DEFAULT_SERVER_URL: &str = "http://127.0.0.1:8080";
+
+// How often output is sent while a job runs, doubles as the runner's heartbeat
+const OUTPUT_INTERVAL: Duration = Duration::from_secs(1);
+
+// Wait before registering again after the server couldn't be reached
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+// Long polls are answered by the server within 25s
+const POLL_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    data: Option<T>,
+}
+
+enum PollError {
+    UnknownRunner, // The server forgot us, e.g. because it restarted
+    Failed(String),
+}
+
+struct Runner {
+    client: reqwest::Client,
+    server_url: String,
+    token: String,
+    registration: RunnerRegistration,
+}
+
+// Comma separated list from an environment variable, empty when unset
+fn list_from_env(name: &str) -> Vec<String> {
+    std::env::var(name)
+        .map(|value| value.split(',')
+            .map(|entry| entry.trim().to_string())
+            .filter(|entry| !entry.is_empty())
+            .collect())
+        .unwrap_or_default()
+}
+
+// Run as a runner until the process is stopped
+pub async fn run() -> std::io::Result<()> {
+    let token = std::env::var("RUNNER_TOKEN").unwrap_or_default();
+    if token.is_empty() {
+        error!("RUNNER_TOKEN has to be set to the token configured on the server");
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "RUNNER_TOKEN is not set"));
+    }
+
+    let name = std::env::var("RUNNER_NAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "runner".to_string());
+    let runner = Runner {
+        client: reqwest::Client::new(),
+        server_url: std::env::var("RUNNER_SERVER_URL")
+            .unwrap_or_else(|_| DEFAULT_SERVER_URL.to_string())
+            .trim_end_matches('/')
+            .to_string(),
+        token,
+        registration: RunnerRegistration {
+            name,
+            labels: RunnerLabels {
+                clients: list_from_env("RUNNER_CLIENTS"),
+                environments: list_from_env("RUNNER_ENVIRONMENTS"),
+                items: list_from_env("RUNNER_ITEMS"),
+            },
+        },
+    };
+
+    info!("Runner {} connecting to {} with labels {:?}",
+        runner.registration.name, runner.server_url, runner.registration.labels);
+
+    loop {
+        let runner_id = match runner.register().await {
+            Ok(runner_id) => runner_id,
+            Err(e) => {
+                warn!("Failed to register with {}: {}, retrying in {}s", runner.server_url, e, RETRY_DELAY.as_secs());
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+        info!("Registered as runner {}", runner_id);
+
+        loop {
+            match runner.poll(runner_id).await {
+                Ok(Some(job)) => runner.run_job(runner_id, job).await,
+                Ok(None) => {}
+                Err(PollError::UnknownRunner) => {
+                    warn!("The server doesn't know runner {} anymore, registering again", runner_id);
+                    break;
+                }
+                Err(PollError::Failed(e)) => {
+                    warn!("Failed to poll for jobs: {}, retrying in {}s", e, RETRY_DELAY.as_secs());
+                    tokio::time::sleep(RETRY_DELAY).await;
+                }
+            }
+        }
+    }
+}
+
+impl Runner {
+    fn url(&self, path: &str) -> String {
+        format!("{}/api/runners{}", self.server_url, path)
+    }
+
+    async fn post<B: serde::Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<Option<T>, String> {
+        let response = self.client.post(self.url(path))
+            .bearer_auth(&self.token)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if !response.status().is_success() {
+            return Err(format!("server replied {}", response.status()));
+        }
+        let response: ApiResponse<T> = response.json().await.map_err(|e| e.to_string())?;
+        Ok(response.data)
+    }
+
+    async fn register(&self) -> Result<Uuid, String> {
+        self.post::<_, Uuid>("/register", &self.registration).await?
+            .ok_or_else(|| "the server didn't return a runner id".to_string())
+    }
+
+    async fn poll(&self, runner_id: Uuid) -> Result<Option<RunnerJob>, PollError> {
+        let response = self.client.post(self.url(&format!("/{}/poll", runner_id)))
+            .bearer_auth(&self.token)
+            .timeout(POLL_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| PollError::Failed(e.to_string()))?;
+
+        match response.status() {
+            reqwest::StatusCode::NO_CONTENT => Ok(None),
+            reqwest::StatusCode::NOT_FOUND => Err(PollError::UnknownRunner),
+            status if status.is_success() => {
+                let response: ApiResponse<RunnerJob> = response.json().await
+                    .map_err(|e| PollError::Failed(e.to_string()))?;
+                Ok(response.data)
+            }
+            status => Err(PollError::Failed(format!("server replied {}", status))),
+        }
+    }
+
+    async fn report_output(&self, runner_id: Uuid, job_id: Uuid, lines: Vec<JobOutputLine>) -> Result<JobOutputAck, String> {
+        self.post::<_, JobOutputAck>(&format!("/{}/jobs/{}/output", runner_id, job_id), &JobOutput { lines }).await
+            .map(Option::unwrap_or_default)
+    }
+
+    async fn finish(&self, runner_id: Uuid, job_id: Uuid, result: JobResult) {
+        if let Err(e) = self.post::<_, ()>(&format!("/{}/jobs/{}/finish", runner_id, job_id), &result).await {
+            error!("Failed to report the result of job {}: {}", job_id, e);
+        }
+    }
+
+    // Run a job's script and keep the server posted until it exits
+    async fn run_job(&self, runner_id: Uuid, job: RunnerJob) {
+        info!("Running job {}: {} of release {} in {}", job.id, job.item_name, job.release_id, job.env_name);
+
+        let mut command = Command::new(&job.command);
+        command
+            .args(&job.args)
+            .envs(&job.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(working_dir) = &job.working_dir {
+            command.current_dir(working_dir);
+        }
+        #[cfg(unix)]
+        command.process_group(0);
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let message = format!("Failed to start {} script on runner {}: {}", job.item_name, self.registration.name, e);
+                error!("{}", message);
+                self.finish(runner_id, job.id, JobResult { exit_code: None, error: Some(message) }).await;
+                return;
+            }
+        };
+        let mut stdin = child.stdin.take();
+
+        // Both output streams end up in one channel so lines are sent in order
+        let (lines, mut received) = mpsc::unbounded_channel();
+        for (stream, reader) in [
+            (LogStream::Stdout, child.stdout.take().map(|out| Box::new(out) as Box<dyn tokio::io::AsyncRead + Unpin + Send>)),
+            (LogStream::Stderr, child.stderr.take().map(|err| Box::new(err) as Box<dyn tokio::io::AsyncRead + Unpin + Send>)),
+        ] {
+            let Some(reader) = reader else {
+                continue;
+            };
+            let lines = lines.clone();
+            tokio::spawn(async move {
+                let mut reader = BufReader::new(reader).lines();
+                while let Ok(Some(line)) = reader.next_line().await {
+                    if lines.send(JobOutputLine { stream: stream.clone(), line }).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(lines);
+
+        let mut ticker = tokio::time::interval(OUTPUT_INTERVAL);
+        let mut cancelled = false;
+        let status = loop {
+            tokio::select! {
+                status = child.wait() => break Some(status),
+                _ = ticker.tick() => {
+                    let mut batch = Vec::new();
+                    while let Ok(line) = received.try_recv() {
+                        batch.push(line);
+                    }
+
+                    match self.report_output(runner_id, job.id, batch).await {
+                        Ok(ack) => {
+                            if let Some(stdin) = stdin.as_mut() {
+                                for answer in ack.answers {
+                                    if let Err(e) = stdin.write_all(format!("{}\n", answer).as_bytes()).await {
+                                        warn!("Failed to answer job {}: {}", job.id, e);
+                                    }
+                                }
+                                let _ = stdin.flush().await;
+                            }
+                            if ack.cancelled {
+                                info!("Job {} was cancelled, stopping its script", job.id);
+                                cancelled = true;
+                                break None;
+                            }
+                        }
+                        Err(e) => warn!("Failed to send output of job {}: {}", job.id, e),
+                    }
+                }
+            }
+        };
+
+        drop(stdin);
+        let status = match status {
+            Some(status) => status,
+            None => {
+                terminate_process_group(&mut child, &job.item_name).await;
+                child.wait().await
+            }
+        };
+
+        // Send whatever output is left once the streams are closed
+        let mut rest = Vec::new();
+        while let Some(line) = received.recv().await {
+            rest.push(line);
+        }
+        if !rest.is_empty() {
+            if let Err(e) = self.report_output(runner_id, job.id, rest).await {
+                warn!("Failed to send output of job {}: {}", job.id, e);
+            }
+        }
+
+        let result = match status {
+            _ if cancelled => JobResult { exit_code: None, error: Some("Cancelled".to_string()) },
+            Ok(status) => JobResult { exit_code: Some(status.code().unwrap_or(-1)), error: None },
+            Err(e) => JobResult { exit_code: None, error: Some(format!("Failed to wait for the script: {}", e)) },
+        };
+        info!("Job {} finished: {:?}", job.id, result);
+        self.finish(runner_id, job.id, result).await;
+    }
+}
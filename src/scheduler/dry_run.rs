@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+use actix_web::web;
+use chrono::Utc;
+use log::{info, warn, error};
+use tokio::time::Duration;
+use uuid::Uuid;
+use crate::config::{self, ItemConfig};
+use crate::models::{DryRun, DryRunItem, DryRunStatus, Environment, LogStream, Release};
+use crate::storage::SledStorage;
+use crate::websocket::server::{broadcast_app_log, broadcast_dry_run_update};
+use super::script_env::{RunContext, ScriptEnvironment};
+use super::{client_name, exit_code, log_line, process_deployment_item, resolve_script, runners, sandbox, store_log_line};
+use super::{ItemControl, ItemError, ItemRun};
+
+// Dry runs in progress by release, with the control of each item's script
+lazy_static::lazy_static! {
+    static ref DRY_RUNS: Mutex<HashMap<Uuid, HashMap<String, ItemControl>>> = Mutex::new(HashMap::new());
+}
+
+// Marks a release as having a dry run until dropped
+struct DryRunGuard {
+    release_id: Uuid,
+}
+
+impl Drop for DryRunGuard {
+    fn drop(&mut self) {
+        if let Ok(mut dry_runs) = DRY_RUNS.lock() {
+            dry_runs.remove(&self.release_id);
+        }
+    }
+}
+
+// Stop a release's dry run, returns false if it has none
+pub fn cancel(release_id: Uuid) -> bool {
+    let Ok(dry_runs) = DRY_RUNS.lock() else {
+        return false;
+    };
+
+    match dry_runs.get(&release_id) {
+        Some(controls) => {
+            info!("Cancelling the dry run of release {}", release_id);
+            controls.values().for_each(ItemControl::cancel);
+            true
+        }
+        None => false,
+    }
+}
+
+// Group items the way the scheduler starts them: an item's wave comes after the
+// waves of its prerequisites within the release, starting at 1
+fn waves(item_names: &[String]) -> HashMap<String, usize> {
+    let mut waves: HashMap<String, usize> = HashMap::new();
+    // Dependencies form a DAG, so every pass places at least one item
+    while waves.len() < item_names.len() {
+        for name in item_names {
+            if waves.contains_key(name) {
+                continue;
+            }
+            let dependencies = config::deployment().dependencies_within(name, item_names);
+            let placed: Option<Vec<usize>> = dependencies.iter().map(|dep| waves.get(dep).copied()).collect();
+            if let Some(placed) = placed {
+                waves.insert(name.clone(), placed.into_iter().max().unwrap_or(0) + 1);
+            }
+        }
+    }
+    waves
+}
+
+// Find the file a command runs and check that it can be executed. Bare command
+// names are searched on the PATH the script would get.
+fn find_executable(script_path: &Path, item: &ItemConfig) -> Result<PathBuf, String> {
+    let path = if script_path.components().count() > 1 {
+        script_path.to_path_buf()
+    } else {
+        let search_path = item.env.get("PATH").cloned()
+            .or_else(|| sandbox::script_path(&item.sandbox))
+            .unwrap_or_default();
+        std::env::split_paths(&search_path)
+            .map(|dir| dir.join(script_path))
+            .find(|candidate| candidate.is_file())
+            .ok_or_else(|| format!("{} was not found on the script's PATH", script_path.display()))?
+    };
+
+    let metadata = std::fs::metadata(&path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    if !metadata.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(format!("{} is not executable", path.display()));
+        }
+    }
+
+    Ok(path)
+}
+
+// Resolve an item's script and collect whatever would keep it from running
+fn preflight(item_name: &str, env_name: &str, client_id: &str, client_name: &str) -> (Option<String>, Vec<String>) {
+    let item = match config::deployment().item(item_name) {
+        Some(item) => item,
+        None => return (None, vec![format!("Unknown deployment item type: {}", item_name)]),
+    };
+    let mut problems = Vec::new();
+
+    // Remote scripts are resolved on the runner's host
+    if item.remote {
+        if !runners::any_matching(client_id, client_name, env_name, item_name) {
+            problems.push(format!("No registered runner takes {} jobs for {} in {}", item_name, client_name, env_name));
+        }
+        return (Some(item.command.clone()), problems);
+    }
+
+    let command = match resolve_script(&item.command).and_then(|path| find_executable(&path, item)) {
+        Ok(path) => Some(path.display().to_string()),
+        Err(e) => {
+            problems.push(e);
+            None
+        }
+    };
+    if let Some(working_dir) = &item.working_dir {
+        if !Path::new(working_dir).is_dir() {
+            problems.push(format!("Working directory {} does not exist", working_dir));
+        }
+    }
+    if let Err(e) = sandbox::check(&item.sandbox) {
+        problems.push(e);
+    }
+
+    (command.or_else(|| Some(item.command.clone())), problems)
+}
+
+// Change a stored dry run and tell the browsers about it
+fn update_dry_run(db: &SledStorage, release_id: Uuid, dry_run_id: Uuid, mut change: impl FnMut(&mut DryRun)) {
+    let result = db.update_release(&release_id, |release| {
+        if let Some(dry_run) = release.dry_run_mut(dry_run_id) {
+            change(dry_run);
+        }
+    });
+
+    match result {
+        Ok(Some(release)) => {
+            if let Some(dry_run) = release.dry_runs.iter().find(|dry_run| dry_run.id == dry_run_id) {
+                broadcast_dry_run_update(release_id.to_string(), dry_run);
+            }
+        }
+        Ok(None) => warn!("Release {} was removed during its dry run", release_id),
+        Err(e) => error!("Failed to store dry run {} of release {}: {}", dry_run_id, release_id, e),
+    }
+}
+
+fn set_item_status(db: &SledStorage, release_id: Uuid, dry_run_id: Uuid, item_name: &str, status: DryRunStatus) {
+    update_dry_run(db, release_id, dry_run_id, |dry_run| {
+        if let Some(item) = dry_run.item_mut(item_name) {
+            item.status = status.clone();
+        }
+    });
+}
+
+fn log(db: &SledStorage, release_id: Uuid, item: &DryRunItem, line: &str) {
+    store_log_line(db, &release_id, log_line(&item.name, LogStream::System, line, item.run, true));
+}
+
+// Start a dry run of a release's deployment to an environment. The items are checked
+// right away, their scripts then run in the background.
+pub fn start(
+    db: web::Data<SledStorage>,
+    release: &Release,
+    environment: Environment,
+    requested_by: String,
+) -> Result<DryRun, String> {
+    let release_id = release.id;
+    let env_name = environment.script_name();
+    let item_names: Vec<String> = release.deployment_items.iter().map(|item| item.name.clone()).collect();
+
+    let controls: HashMap<String, ItemControl> = item_names.iter()
+        .map(|name| (name.clone(), ItemControl::default()))
+        .collect();
+    {
+        let mut dry_runs = DRY_RUNS.lock().map_err(|_| "Dry run registry is unavailable".to_string())?;
+        if dry_runs.contains_key(&release_id) {
+            return Err("A dry run of this release is already in progress".to_string());
+        }
+        dry_runs.insert(release_id, controls.clone());
+    }
+    let guard = DryRunGuard { release_id };
+
+    let client_name = client_name(&db, &release.client_id);
+    let waves = waves(&item_names);
+    let items = item_names.iter().map(|name| {
+        let (command, problems) = preflight(name, env_name, &release.client_id, &client_name);
+        DryRunItem {
+            name: name.clone(),
+            run: 0,
+            wave: waves[name],
+            command,
+            status: if problems.is_empty() { DryRunStatus::Pending } else { DryRunStatus::Failed },
+            problems,
+            exit_code: None,
+            duration_ms: None,
+        }
+    }).collect();
+
+    let mut dry_run = DryRun {
+        id: Uuid::new_v4(),
+        environment,
+        requested_by: Some(requested_by.clone()),
+        started_at: Utc::now(),
+        finished_at: None,
+        status: DryRunStatus::Running,
+        items,
+    };
+
+    // Every item gets a run of its own for the dry run's log lines
+    let stored = db.update_release(&release_id, |release| {
+        for item in dry_run.items.iter_mut() {
+            if let Some(deployment_item) = release.deployment_items.iter_mut().find(|it| it.name == item.name) {
+                item.run = deployment_item.start_dry_run();
+            }
+        }
+        release.add_dry_run(dry_run.clone());
+    }).map_err(|e| format!("Failed to store the dry run: {}", e))?;
+    let release = stored.ok_or_else(|| format!("Release {} not found", release_id))?;
+
+    // Write down the plan: order, script and environment of every item
+    let wave_count = waves.values().copied().max().unwrap_or(0);
+    for item in &dry_run.items {
+        let dependencies = config::deployment().dependencies_within(&item.name, &item_names);
+        let order = if dependencies.is_empty() {
+            format!("wave {} of {}", item.wave, wave_count)
+        } else {
+            format!("wave {} of {}, after {}", item.wave, wave_count, dependencies.join(", "))
+        };
+        log(&db, release_id, item, &format!("Dry run {} of the deployment to {} requested by {}: {}",
+            item.run, env_name, requested_by, order));
+        if let Some(command) = &item.command {
+            log(&db, release_id, item, &format!("Script: {}", command));
+        }
+
+        if let Some(item_config) = config::deployment().item(&item.name) {
+            let script_env = ScriptEnvironment::new(&RunContext {
+                release: &release,
+                client_name: &client_name,
+                item_name: &item.name,
+                env_name,
+                run: item.run,
+                attempt: 1,
+                rollback: false,
+                dry_run: true,
+                target: None,
+            }, &item_config.env);
+            let mut variables: Vec<&String> = script_env.variables.keys().collect();
+            variables.sort();
+            log(&db, release_id, item, &format!("Script environment: {}",
+                variables.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(", ")));
+        }
+
+        for problem in &item.problems {
+            log(&db, release_id, item, &format!("Problem: {}", problem));
+        }
+    }
+
+    info!("Dry run {} of release {} to {} started", dry_run.id, release_id, env_name);
+    broadcast_app_log("info", &format!("Dry run of {} to {} started", release.title, env_name));
+    broadcast_dry_run_update(release_id.to_string(), &dry_run);
+
+    let timeouts: HashMap<String, Duration> = item_names.iter()
+        .map(|name| {
+            let timeout = release.item_timeouts.get(name).copied()
+                .map(Duration::from_secs)
+                .unwrap_or_else(|| config::deployment().item_timeout(name, env_name));
+            (name.clone(), timeout)
+        })
+        .collect();
+
+    let plan = dry_run.clone();
+    tokio::spawn(async move {
+        let _guard = guard;
+        run(db, release_id, plan, timeouts, controls).await;
+    });
+
+    Ok(dry_run)
+}
+
+// Call the scripts wave by wave, like the scheduler would
+async fn run(
+    db: web::Data<SledStorage>,
+    release_id: Uuid,
+    plan: DryRun,
+    timeouts: HashMap<String, Duration>,
+    controls: HashMap<String, ItemControl>,
+) {
+    let env_name = plan.environment.script_name();
+    let item_names: Vec<String> = plan.items.iter().map(|item| item.name.clone()).collect();
+    let mut outcomes: HashMap<String, DryRunStatus> = HashMap::new();
+    let wave_count = plan.items.iter().map(|item| item.wave).max().unwrap_or(0);
+
+    for wave in 1..=wave_count {
+        let mut running = tokio::task::JoinSet::new();
+
+        for item in plan.items.iter().filter(|item| item.wave == wave) {
+            if item.status == DryRunStatus::Failed {
+                outcomes.insert(item.name.clone(), DryRunStatus::Failed);
+                continue;
+            }
+
+            let control = controls[&item.name].clone();
+            if control.is_cancelled() {
+                log(&db, release_id, item, "Dry run cancelled before the script was started");
+                set_item_status(&db, release_id, plan.id, &item.name, DryRunStatus::Cancelled);
+                outcomes.insert(item.name.clone(), DryRunStatus::Cancelled);
+                continue;
+            }
+
+            let dependencies = config::deployment().dependencies_within(&item.name, &item_names);
+            if let Some(failed) = dependencies.iter().find(|dep| outcomes.get(*dep) != Some(&DryRunStatus::Passed)) {
+                log(&db, release_id, item, &format!("Skipped because prerequisite {} did not pass its dry run", failed));
+                set_item_status(&db, release_id, plan.id, &item.name, DryRunStatus::Skipped);
+                outcomes.insert(item.name.clone(), DryRunStatus::Skipped);
+                continue;
+            }
+
+            set_item_status(&db, release_id, plan.id, &item.name, DryRunStatus::Running);
+            let item_run = ItemRun {
+                db: db.clone(),
+                release_id,
+                item_name: item.name.clone(),
+                env_name: env_name.to_string(),
+                run: item.run,
+                attempt: 1,
+                max_attempts: 1,
+                timeout: timeouts[&item.name],
+                control,
+                rollback: false,
+                dry_run: true,
+                target: None,
+            };
+            running.spawn(async move {
+                let started = Instant::now();
+                let result = process_deployment_item(&item_run).await;
+                (item_run.item_name, result, started.elapsed())
+            });
+        }
+
+        while let Some(joined) = running.join_next().await {
+            let (item_name, result, elapsed) = match joined {
+                Ok(finished) => finished,
+                Err(e) => {
+                    error!("Dry run task of release {} failed: {}", release_id, e);
+                    continue;
+                }
+            };
+
+            let status = match &result {
+                Ok(()) => DryRunStatus::Passed,
+                Err(ItemError::Cancelled) => DryRunStatus::Cancelled,
+                Err(_) => DryRunStatus::Failed,
+            };
+            update_dry_run(&db, release_id, plan.id, |dry_run| {
+                if let Some(item) = dry_run.item_mut(&item_name) {
+                    item.status = status.clone();
+                    item.exit_code = exit_code(&result);
+                    item.duration_ms = Some(elapsed.as_millis() as u64);
+                }
+            });
+            outcomes.insert(item_name, status);
+        }
+    }
+
+    let mut outcome = DryRunStatus::Failed;
+    update_dry_run(&db, release_id, plan.id, |dry_run| {
+        dry_run.status = dry_run.outcome();
+        dry_run.finished_at = Some(Utc::now());
+        outcome = dry_run.status.clone();
+    });
+
+    let message = format!("Dry run of release {} to {} finished: {:?}", release_id, env_name, outcome);
+    info!("{}", message);
+    broadcast_app_log(if outcome == DryRunStatus::Passed { "info" } else { "warn" }, &message);
+}
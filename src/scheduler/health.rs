@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use chrono::Utc;
+use log::{info, warn, error};
+use tokio::process::Command;
+use tokio::time::Duration;
+use uuid::Uuid;
+use crate::config::{self, HealthCheckConfig, HealthProbe};
+use crate::models::{Environment, HealthCheckResult, HealthCheckStatus, Release};
+use crate::storage::SledStorage;
+use crate::websocket::server::{broadcast_health_check_update, broadcast_release_update};
+use super::script_env::{RunContext, ScriptEnvironment};
+use super::{client_name, resolve_script};
+
+// Probe output kept on a result, longer output keeps its end
+const MAX_OUTPUT_CHARS: usize = 2000;
+
+// Keep the end of long output, that's where errors usually are
+fn truncate(output: &str) -> String {
+    let output = output.trim();
+    let count = output.chars().count();
+    if count <= MAX_OUTPUT_CHARS {
+        return output.to_string();
+    }
+    let tail: String = output.chars().skip(count - MAX_OUTPUT_CHARS).collect();
+    format!("...{}", tail)
+}
+
+fn update_result(db: &SledStorage, release_id: Uuid, name: &str, mut change: impl FnMut(&mut HealthCheckResult)) {
+    let result = db.update_release(&release_id, |release| {
+        if let Some(result) = release.health_checks.iter_mut().find(|result| result.name == name) {
+            change(result);
+        }
+    });
+
+    match result {
+        Ok(Some(release)) => broadcast_health_check_update(release_id.to_string(), &release.health_checks),
+        Ok(None) => warn!("Release {} was removed during its health checks", release_id),
+        Err(e) => error!("Failed to store health check {} of release {}: {}", name, release_id, e),
+    }
+}
+
+fn log(release: &Release, check_name: &str, message: &str) {
+    info!("Release {} health check {}: {}", release.id, check_name, message);
+    broadcast_release_update(
+        release.id.to_string(),
+        "InProgress".to_string(),
+        release.progress,
+        Some(format!("[health:{}] {}", check_name, message))
+    );
+}
+
+// Probe once, Ok and Err both carry what the probe saw
+async fn probe(
+    probe: &HealthProbe,
+    timeout: Duration,
+    script_env: &ScriptEnvironment,
+) -> Result<String, String> {
+    match probe {
+        HealthProbe::Http { url, expect_status, expect_body } => {
+            let client = reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .map_err(|e| format!("Failed to set up the HTTP client: {}", e))?;
+            let response = client.get(url).send().await
+                .map_err(|e| format!("GET {} failed: {}", url, e))?;
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            let seen = format!("HTTP {}: {}", status, truncate(&body));
+
+            let status_ok = match expect_status {
+                Some(expected) => status.as_u16() == *expected,
+                None => status.is_success(),
+            };
+            if !status_ok {
+                let expected = expect_status.map(|code| code.to_string()).unwrap_or_else(|| "2xx".to_string());
+                return Err(format!("expected status {}, got {}", expected, seen));
+            }
+            if let Some(expected) = expect_body.as_deref().filter(|expected| !body.contains(*expected)) {
+                return Err(format!("response doesn't contain {:?}, got {}", expected, seen));
+            }
+            Ok(seen)
+        }
+        HealthProbe::Tcp { address } => {
+            match tokio::time::timeout(timeout, tokio::net::TcpStream::connect(address.as_str())).await {
+                Ok(Ok(_)) => Ok(format!("Connected to {}", address)),
+                Ok(Err(e)) => Err(format!("Failed to connect to {}: {}", address, e)),
+                Err(_) => Err(format!("Connecting to {} timed out after {}s", address, timeout.as_secs())),
+            }
+        }
+        HealthProbe::Command { command, args, .. } => {
+            let script_path = resolve_script(command)?;
+            let child = Command::new(&script_path)
+                .args(args)
+                .envs(&script_env.variables)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(|e| format!("Failed to start {:?}: {}", script_path, e))?;
+
+            let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+                Ok(Ok(output)) => output,
+                Ok(Err(e)) => return Err(format!("Failed to wait for {:?}: {}", script_path, e)),
+                Err(_) => return Err(format!("{} timed out after {}s", command, timeout.as_secs())),
+            };
+
+            let printed = format!("{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr));
+            let printed = truncate(&script_env.redact(&printed));
+            match output.status.code() {
+                Some(0) => Ok(printed),
+                Some(code) => Err(format!("exited with code {}: {}", code, printed)),
+                None => Err(format!("was killed by a signal: {}", printed)),
+            }
+        }
+    }
+}
+
+// Run one check until it passes or is out of retries, returns the failure otherwise
+async fn run_check(
+    db: &SledStorage,
+    release: &Release,
+    check: &HealthCheckConfig,
+    probe_config: &HealthProbe,
+    script_env: &ScriptEnvironment,
+) -> Result<(), String> {
+    let max_attempts = check.retries + 1;
+    let timeout = Duration::from_secs(check.timeout_secs);
+    let mut last_output = String::new();
+
+    for attempt in 1..=max_attempts {
+        update_result(db, release.id, &check.name, |result| {
+            result.status = HealthCheckStatus::Running;
+            result.attempts = attempt;
+        });
+
+        let outcome = probe(probe_config, timeout, script_env).await;
+        let passed = outcome.is_ok();
+        let output = match outcome {
+            Ok(output) | Err(output) => output,
+        };
+
+        let last = passed || attempt == max_attempts;
+        update_result(db, release.id, &check.name, |result| {
+            result.output = Some(output.clone());
+            result.checked_at = Some(Utc::now());
+            if passed {
+                result.status = HealthCheckStatus::Passed;
+            } else if last {
+                result.status = HealthCheckStatus::Failed;
+            }
+        });
+
+        if passed {
+            log(release, &check.name, &format!("passed on attempt {} of {}", attempt, max_attempts));
+            return Ok(());
+        }
+        log(release, &check.name, &format!("attempt {} of {} failed: {}", attempt, max_attempts, output));
+        last_output = output;
+        if !last {
+            tokio::time::sleep(Duration::from_secs(check.interval_secs)).await;
+        }
+    }
+
+    Err(format!("Health check {} failed after {} attempts: {}", check.name, max_attempts, last_output))
+}
+
+// Run the health checks configured for a release's client and the environment it was
+// deployed to, all at once. Returns the failures if any check didn't pass.
+pub async fn run_checks(db: &SledStorage, release: &Release, environment: &Environment) -> Result<(), String> {
+    let env_name = environment.script_name();
+    let checks = config::deployment().health_checks_for(&release.client_id, env_name);
+    if checks.is_empty() {
+        return Ok(());
+    }
+
+    info!("Running {} health checks for release {} in {}", checks.len(), release.id, env_name);
+    let probes: Vec<HealthProbe> = checks.iter().map(|check| check.probe.for_environment(env_name)).collect();
+    let results: Vec<HealthCheckResult> = checks.iter().zip(&probes).map(|(check, probe)| HealthCheckResult {
+        name: check.name.clone(),
+        probe: probe.describe(),
+        environment: environment.clone(),
+        status: HealthCheckStatus::Pending,
+        attempts: 0,
+        max_attempts: check.retries + 1,
+        output: None,
+        checked_at: None,
+    }).collect();
+    match db.update_release(&release.id, |release| release.health_checks = results.clone()) {
+        Ok(Some(release)) => broadcast_health_check_update(release.id.to_string(), &release.health_checks),
+        Ok(None) => return Err("Release was removed before its health checks".to_string()),
+        Err(e) => return Err(format!("Failed to store the health checks: {}", e)),
+    }
+
+    let client_name = client_name(db, &release.client_id);
+    let mut running = Vec::new();
+    for (check, probe_config) in checks.iter().zip(&probes) {
+        let item_env = match probe_config {
+            HealthProbe::Command { env, .. } => env.clone(),
+            _ => HashMap::new(),
+        };
+        let context = RunContext {
+            release,
+            client_name: &client_name,
+            item_name: &check.name,
+            env_name,
+            run: release.deployment_items.iter().map(|item| item.runs).max().unwrap_or(0),
+            attempt: 1,
+            rollback: false,
+            dry_run: false,
+            target: None,
+        };
+        let script_env = ScriptEnvironment::new(&context, &item_env);
+        running.push(async move { run_check(db, release, check, probe_config, &script_env).await });
+    }
+
+    let failures: Vec<String> = futures::future::join_all(running).await
+        .into_iter()
+        .filter_map(Result::err)
+        .collect();
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures.join("\n"))
+    }
+}
@@ -0,0 +1,57 @@
+use chrono::Utc;
+use log::{info, error};
+use crate::models::{InventoryEntry, Release};
+use crate::models::inventory::VERSION_OUTPUT;
+use crate::storage::SledStorage;
+use crate::websocket::server::broadcast_inventory_update;
+
+// Record the items a finished deployment put in place, with what their scripts reported
+pub fn record_deployment(db: &SledStorage, release: &Release) {
+    let environment = release.last_deployment_environment();
+    let deployed_status = environment.deployed_status();
+    let now = Utc::now();
+
+    for item in release.deployment_items.iter().filter(|item| item.status == deployed_status) {
+        let deployed_at = item.finished_at.unwrap_or(now);
+        let entry = InventoryEntry {
+            client_id: release.client_id.clone(),
+            environment: environment.clone(),
+            item: item.name.clone(),
+            version: item.outputs.get(VERSION_OUTPUT).cloned(),
+            outputs: item.outputs.clone(),
+            release_id: Some(release.id),
+            release_title: Some(release.title.clone()),
+            reported_by: None,
+            deployed_at,
+            since: deployed_at,
+            rolled_back_at: None,
+        };
+
+        match db.record_inventory(&entry) {
+            Ok(entry) => {
+                info!("Inventory: {} of client {} in {:?} is at {}", entry.item, entry.client_id, entry.environment,
+                    entry.version.as_deref().unwrap_or("an unknown version"));
+                broadcast_inventory_update(&entry);
+            }
+            Err(e) => error!("Failed to record {} of release {} in the inventory: {}", item.name, release.id, e),
+        }
+    }
+}
+
+// Mark an item's inventory entry as undone when the release that deployed it rolled it back
+pub fn record_rollback(db: &SledStorage, release: &Release, item_name: &str) {
+    let environment = release.last_deployment_environment();
+    let mut rolled_back = false;
+    let updated = db.update_inventory(&release.client_id, &environment, item_name, |entry| {
+        rolled_back = entry.release_id == Some(release.id);
+        if rolled_back && entry.rolled_back_at.is_none() {
+            entry.rolled_back_at = Some(Utc::now());
+        }
+    });
+
+    match updated {
+        Ok(Some(entry)) if rolled_back => broadcast_inventory_update(&entry),
+        Ok(_) => {}
+        Err(e) => error!("Failed to record the rollback of {} of release {} in the inventory: {}", item_name, release.id, e),
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::{HashMap, VecDeque};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+// Someone waiting for a slot under every one of its keys
+struct Waiter {
+    id: Uuid,
+    keys: Vec<String>,
+    grant: oneshot::Sender<()>,
+}
+
+// Counts running work per key (e.g. "releases", "environment:production") and hands
+// out slots in arrival order. Waiters that can't start don't block later waiters
+// whose keys still have room.
+pub struct Limiter {
+    limits: HashMap<String, usize>,
+    running: HashMap<String, usize>,
+    waiting: VecDeque<Waiter>,
+}
+
+impl Limiter {
+    pub fn new(limits: HashMap<String, usize>) -> Self {
+        Self {
+            limits,
+            running: HashMap::new(),
+            waiting: VecDeque::new(),
+        }
+    }
+
+    // Keys without a configured limit never block
+    fn fits(&self, keys: &[String]) -> bool {
+        keys.iter().all(|key| match self.limits.get(key) {
+            Some(limit) => self.running.get(key).copied().unwrap_or(0) < *limit,
+            None => true,
+        })
+    }
+
+    // Queue up for a slot, the receiver resolves once the slot is granted
+    pub fn enqueue(&mut self, id: Uuid, keys: Vec<String>) -> oneshot::Receiver<()> {
+        let (grant, granted) = oneshot::channel();
+        self.waiting.push_back(Waiter { id, keys, grant });
+        self.dispatch();
+        granted
+    }
+
+    // Stop waiting, returns false if the waiter already got its slot
+    pub fn withdraw(&mut self, id: Uuid) -> bool {
+        let before = self.waiting.len();
+        self.waiting.retain(|waiter| waiter.id != id);
+        self.waiting.len() != before
+    }
+
+    // Return a granted slot and let the next waiters in
+    pub fn release(&mut self, keys: &[String]) {
+        for key in keys {
+            if let Some(count) = self.running.get_mut(key) {
+                *count = count.saturating_sub(1);
+            }
+        }
+        self.dispatch();
+    }
+
+    fn dispatch(&mut self) {
+        let mut index = 0;
+        while index < self.waiting.len() {
+            if !self.fits(&self.waiting[index].keys) {
+                index += 1;
+                continue;
+            }
+
+            if let Some(waiter) = self.waiting.remove(index) {
+                for key in &waiter.keys {
+                    *self.running.entry(key.clone()).or_insert(0) += 1;
+                }
+                // Nobody is listening anymore, so the slot is free again right away
+                if waiter.grant.send(()).is_err() {
+                    for key in &waiter.keys {
+                        if let Some(count) = self.running.get_mut(key) {
+                            *count = count.saturating_sub(1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Waiting ids with their 1-based position in the queue
+    pub fn positions(&self) -> Vec<(Uuid, usize)> {
+        self.waiting.iter()
+            .enumerate()
+            .map(|(index, waiter)| (waiter.id, index + 1))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    fn limiter(limits: &[(&str, usize)]) -> Limiter {
+        Limiter::new(limits.iter().map(|(key, limit)| (key.to_string(), *limit)).collect())
+    }
+
+    #[test]
+    fn slots_are_granted_up_to_the_limit() {
+        let mut limiter = limiter(&[("releases", 2)]);
+        let mut first = limiter.enqueue(Uuid::new_v4(), keys(&["releases"]));
+        let mut second = limiter.enqueue(Uuid::new_v4(), keys(&["releases"]));
+        let third_id = Uuid::new_v4();
+        let mut third = limiter.enqueue(third_id, keys(&["releases"]));
+
+        assert!(first.try_recv().is_ok());
+        assert!(second.try_recv().is_ok());
+        assert!(third.try_recv().is_err());
+        assert_eq!(limiter.positions(), vec![(third_id, 1)]);
+
+        limiter.release(&keys(&["releases"]));
+        assert!(third.try_recv().is_ok());
+        assert!(limiter.positions().is_empty());
+    }
+
+    #[test]
+    fn keys_without_a_limit_never_block() {
+        let mut limiter = limiter(&[]);
+        let mut receivers: Vec<_> = (0..5)
+            .map(|_| limiter.enqueue(Uuid::new_v4(), keys(&["releases", "environment:staging"])))
+            .collect();
+
+        assert!(receivers.iter_mut().all(|granted| granted.try_recv().is_ok()));
+    }
+
+    #[test]
+    fn a_full_key_does_not_hold_up_other_waiters() {
+        let mut limiter = limiter(&[("releases", 3), ("environment:production", 1)]);
+        let mut production = limiter.enqueue(Uuid::new_v4(), keys(&["releases", "environment:production"]));
+        let waiting_id = Uuid::new_v4();
+        let mut waiting = limiter.enqueue(waiting_id, keys(&["releases", "environment:production"]));
+        let mut staging = limiter.enqueue(Uuid::new_v4(), keys(&["releases", "environment:staging"]));
+
+        assert!(production.try_recv().is_ok());
+        assert!(waiting.try_recv().is_err());
+        assert!(staging.try_recv().is_ok());
+        assert_eq!(limiter.positions(), vec![(waiting_id, 1)]);
+
+        // Only a production slot lets it in, a staging one isn't enough
+        limiter.release(&keys(&["releases", "environment:staging"]));
+        assert!(waiting.try_recv().is_err());
+        limiter.release(&keys(&["releases", "environment:production"]));
+        assert!(waiting.try_recv().is_ok());
+    }
+
+    #[test]
+    fn withdrawn_waiters_give_up_their_place() {
+        let mut limiter = limiter(&[("releases", 1)]);
+        let granted_id = Uuid::new_v4();
+        let _granted = limiter.enqueue(granted_id, keys(&["releases"]));
+        let (withdrawn_id, next_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let _withdrawn = limiter.enqueue(withdrawn_id, keys(&["releases"]));
+        let mut next = limiter.enqueue(next_id, keys(&["releases"]));
+
+        assert!(!limiter.withdraw(granted_id));
+        assert!(limiter.withdraw(withdrawn_id));
+        assert_eq!(limiter.positions(), vec![(next_id, 1)]);
+
+        limiter.release(&keys(&["releases"]));
+        assert!(next.try_recv().is_ok());
+    }
+
+    #[test]
+    fn slots_nobody_listens_for_are_freed() {
+        let mut limiter = limiter(&[("releases", 1)]);
+        let mut running = limiter.enqueue(Uuid::new_v4(), keys(&["releases"]));
+        let gone = limiter.enqueue(Uuid::new_v4(), keys(&["releases"]));
+        let mut next = limiter.enqueue(Uuid::new_v4(), keys(&["releases"]));
+        assert!(running.try_recv().is_ok());
+        drop(gone);
+
+        limiter.release(&keys(&["releases"]));
+        assert!(next.try_recv().is_ok());
+    }
+}
@@ -1,49 +1,727 @@
 use actix_web::web;
 use tokio::time::{interval, Duration};
+
+pub mod dry_run;
+mod health;
+mod inventory;
+mod limits;
+mod protocol;
+mod queue;
+pub mod recurring;
+mod rollout;
+pub mod runners;
+mod sandbox;
+mod script_env;
+pub mod train;
+
+use limits::Limiter;
+use protocol::ScriptMarker;
+use queue::ScheduleQueue;
+use runners::RunnerEvent;
+use sandbox::Limit;
+use script_env::{RunContext, ScriptEnvironment};
 use std::process::{Stdio};
-use tokio::process::Command;
-use tokio::io::{BufReader, AsyncBufReadExt};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::io::{BufReader, AsyncBufReadExt, AsyncWriteExt};
 use log::{info, error, warn};
-use std::path::Path;
-use regex::Regex;
-use crate::models::{Release, ReleaseStatus, DeploymentItem};
+use std::path::PathBuf;
+use crate::models::{Release, ReleaseStatus, DeploymentItem, Environment, ItemEvent, ItemEventKind, LogLine, LogStream, RunnerJob, JobResult};
 use crate::storage::SledStorage;
-use chrono::Utc;
+use crate::config::{self, RecoveryPolicy};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{oneshot, watch, Notify};
+use uuid::Uuid;
 
 // Import WebSocket broadcast functionality
 use crate::websocket::server::broadcast_release_update;
+use crate::websocket::server::broadcast_item_update;
+use crate::websocket::server::broadcast_item_event;
 use crate::websocket::server::broadcast_app_log;
+use crate::websocket::server::broadcast_queue_update;
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60); // Prune stale WebSockets every minute
+const IDLE_WAIT: Duration = Duration::from_secs(3600); // Upper bound on sleeping with nothing queued
+
+// Releases waiting for their scheduled time, and the signal that wakes the scheduler up
+// when that queue changes
+lazy_static::lazy_static! {
+    static ref QUEUE: Mutex<ScheduleQueue> = Mutex::new(ScheduleQueue::default());
+    static ref WAKEUP: Notify = Notify::new();
+}
+
+// Queue a release that was created or changed, or drop it from the queue if it no
+// longer needs processing. The scheduler picks the change up right away.
+pub fn schedule_release(release: &Release) {
+    if let Ok(mut queue) = QUEUE.lock() {
+        if release.should_process() {
+            queue.upsert(release.id, release.scheduled_at);
+        } else {
+            queue.remove(release.id);
+        }
+    }
+    // A release waiting for a deployment slot that is no longer due gives it up
+    if !release.should_process() || release.scheduled_at > Utc::now() {
+        withdraw_release(release.id);
+    }
+    WAKEUP.notify_one();
+}
+
+// Drop a deleted release from the queue
+pub fn unschedule_release(release_id: Uuid) {
+    if let Ok(mut queue) = QUEUE.lock() {
+        queue.remove(release_id);
+    }
+    withdraw_release(release_id);
+    WAKEUP.notify_one();
+}
+
+// Queue every release that needs processing again, e.g. after a freeze that held
+// some of them was lifted
+pub fn reschedule_releases(db: &SledStorage) {
+    match db.get_releases_to_process() {
+        Ok(releases) => {
+            for release in releases.iter() {
+                schedule_release(release);
+            }
+        }
+        Err(e) => error!("Error loading releases to reschedule: {}", e),
+    }
+}
+
+// Keep a due release in the queue until a freeze is over
+fn hold_release(release_id: Uuid, until: DateTime<Utc>) {
+    if let Ok(mut queue) = QUEUE.lock() {
+        queue.upsert(release_id, until);
+    }
+    WAKEUP.notify_one();
+}
+
+// Maximum number of log lines kept per deployment item
+const DEFAULT_MAX_LOG_LINES: usize = 5000;
+
+lazy_static::lazy_static! {
+    static ref MAX_LOG_LINES: usize = std::env::var("MAX_ITEM_LOG_LINES")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_MAX_LOG_LINES);
+}
+
+// How long a cancelled script gets to exit after SIGTERM before it is killed, and how
+// long the processes a finished script left behind may keep its output open
+const DEFAULT_CANCEL_GRACE_SECS: u64 = 10;
+
+lazy_static::lazy_static! {
+    static ref CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(
+        std::env::var("CANCEL_GRACE_PERIOD_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_CANCEL_GRACE_SECS)
+    );
+}
+
+// Why a deployment item did not finish successfully
+#[derive(Debug)]
+pub enum ItemError {
+    Cancelled,
+    TimedOut(Duration),
+    ExitCode { code: i32, message: String },
+    Failed(String),
+}
+
+impl std::fmt::Display for ItemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ItemError::Cancelled => write!(f, "Cancelled by user"),
+            ItemError::TimedOut(timeout) => write!(f, "timed out after {}s", timeout.as_secs()),
+            ItemError::ExitCode { message, .. } |
+            ItemError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl ItemError {
+    // Exit code of the script, if it got to exit on its own
+    fn exit_code(&self) -> Option<i32> {
+        match self {
+            ItemError::ExitCode { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for ItemError {}
+
+impl From<String> for ItemError {
+    fn from(message: String) -> Self {
+        ItemError::Failed(message)
+    }
+}
+
+// How a manual step was marked: Ok when done, Err with the reason when it failed
+type ManualAnswer = Result<(), String>;
+
+// Shared handle used to cancel a running deployment item and to answer its script
+#[derive(Clone, Default)]
+pub struct ItemControl {
+    cancelled: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+    stdin: Arc<tokio::sync::Mutex<Option<ChildStdin>>>,
+    remote_job: Arc<Mutex<Option<Uuid>>>, // Runner job of an item running remotely
+    asking: Arc<Mutex<Vec<(ItemControl, String)>>>, // Rollout targets waiting for an answer and their prompts, oldest first
+    manual: Arc<Mutex<Option<oneshot::Sender<ManualAnswer>>>>, // Manual step waiting to be marked done or failed
+    answer_pending: Arc<watch::Sender<bool>>, // Set while the script waits for a confirmation
+}
+
+impl ItemControl {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        // The targets of a rollout all wait on the same notify
+        self.notify.notify_waiters();
+    }
+    
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+    
+    // Resolves once the item has been cancelled
+    async fn cancelled(&self) {
+        // Created before checking so a cancel in between isn't missed
+        let notified = self.notify.notified();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+    
+    // Control for the script running on one rollout target. It's cancelled with the
+    // item but has a stdin and runner job of its own.
+    fn for_target(&self) -> ItemControl {
+        ItemControl {
+            cancelled: self.cancelled.clone(),
+            notify: self.notify.clone(),
+            stdin: Default::default(),
+            remote_job: Default::default(),
+            asking: self.asking.clone(),
+            manual: Default::default(),
+            answer_pending: Default::default(),
+        }
+    }
+    
+    // A target's script asked for a confirmation, the item's next answer goes to it.
+    // Returns whether it's the first target waiting, later ones are asked in turn.
+    fn ask(&self, prompt: &str) -> bool {
+        match self.asking.lock() {
+            Ok(mut asking) => {
+                asking.push((self.clone(), prompt.to_string()));
+                asking.len() == 1
+            }
+            Err(_) => true,
+        }
+    }
+    
+    // A target's script finished, answers no longer go to it
+    fn forget(&self) {
+        if let Ok(mut asking) = self.asking.lock() {
+            asking.retain(|(control, _)| !Arc::ptr_eq(&control.stdin, &self.stdin));
+        }
+    }
+    
+    // The script asked for a confirmation and waits for the answer
+    fn await_answer(&self) {
+        self.answer_pending.send_replace(true);
+    }
+    
+    // Resolves once the script ran for `timeout`. Time spent waiting for a confirmation
+    // doesn't count, a human may take a while to answer.
+    async fn timed_out(&self, timeout: Duration) {
+        let mut pending = self.answer_pending.subscribe();
+        let mut remaining = timeout;
+        loop {
+            if *pending.borrow_and_update() {
+                let _ = pending.changed().await;
+                continue;
+            }
+            
+            let started = tokio::time::Instant::now();
+            tokio::select! {
+                _ = tokio::time::sleep(remaining) => return,
+                _ = pending.changed() => remaining = remaining.saturating_sub(started.elapsed()),
+            }
+        }
+    }
+    
+    // Prompt of the next rollout target waiting for an answer
+    fn next_prompt(&self) -> Option<String> {
+        self.asking.lock().ok()
+            .and_then(|asking| asking.first().map(|(_, prompt)| prompt.clone()))
+    }
+    
+    // Keep the running script's stdin for answering confirmations, None closes it
+    async fn set_stdin(&self, stdin: Option<ChildStdin>) {
+        *self.stdin.lock().await = stdin;
+    }
+    
+    // Wait for the item's manual step to be marked done or failed
+    fn wait_for_manual_step(&self) -> oneshot::Receiver<ManualAnswer> {
+        let (sender, receiver) = oneshot::channel();
+        if let Ok(mut manual) = self.manual.lock() {
+            *manual = Some(sender);
+        }
+        receiver
+    }
+    
+    // Take the sender for marking the manual step done or failed, None if nothing waits for it
+    fn take_manual_step(&self) -> Option<oneshot::Sender<ManualAnswer>> {
+        self.manual.lock().ok().and_then(|mut manual| manual.take())
+    }
+    
+    fn set_remote_job(&self, job_id: Option<Uuid>) {
+        if let Ok(mut remote_job) = self.remote_job.lock() {
+            *remote_job = job_id;
+        }
+    }
+    
+    // Answer a confirmation request with "yes" or "no" on the script's stdin. During a
+    // rollout the target that asked first gets the answer.
+    async fn answer(&self, approved: bool) -> std::io::Result<()> {
+        let asking = self.asking.lock().ok()
+            .and_then(|mut asking| (!asking.is_empty()).then(|| asking.remove(0)));
+        match asking {
+            Some((target, _)) => target.answer_script(approved).await,
+            None => self.answer_script(approved).await,
+        }
+    }
+    
+    async fn answer_script(&self, approved: bool) -> std::io::Result<()> {
+        // A runner passes the answer on with its next output reply
+        let remote_job = self.remote_job.lock().ok().and_then(|remote_job| *remote_job);
+        if let Some(job_id) = remote_job {
+            return if runners::answer(job_id, if approved { "yes" } else { "no" }) {
+                self.answer_pending.send_replace(false);
+                Ok(())
+            } else {
+                Err(std::io::Error::new(std::io::ErrorKind::NotConnected, "no runner is working on the item"))
+            };
+        }
+        
+        let mut stdin = self.stdin.lock().await;
+        let stdin = stdin.as_mut().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotConnected, "the script is not running")
+        })?;
+        stdin.write_all(if approved { b"yes\n" } else { b"no\n" }).await?;
+        stdin.flush().await?;
+        self.answer_pending.send_replace(false);
+        Ok(())
+    }
+}
+
+// Deployment items that currently have a script running, keyed by release and item name
+lazy_static::lazy_static! {
+    static ref RUNNING_ITEMS: Mutex<HashMap<(Uuid, String), ItemControl>> = Mutex::new(HashMap::new());
+}
+
+// Marks a deployment item as running until dropped
+struct RunningItemGuard {
+    release_id: Uuid,
+    item_name: String,
+    control: ItemControl,
+}
+
+impl Drop for RunningItemGuard {
+    fn drop(&mut self) {
+        if let Ok(mut running) = RUNNING_ITEMS.lock() {
+            running.remove(&(self.release_id, self.item_name.clone()));
+        }
+    }
+}
+
+// Releases that have a process_release run in progress
+lazy_static::lazy_static! {
+    static ref ACTIVE_RELEASES: Mutex<HashSet<Uuid>> = Mutex::new(HashSet::new());
+}
 
-const CHECK_INTERVAL: Duration = Duration::from_secs(60); // Check every minute
+// Marks a release as being processed until dropped
+pub struct ActiveReleaseGuard {
+    release_id: Uuid,
+}
 
-// Define script paths
-const SCRIPTS_DIR: &str = "scripts";
-const DATA_SCRIPT: &str = "deploy_data.sh";
-const SOLR_SCRIPT: &str = "deploy_solr.sh";
-const APP_SCRIPT: &str = "deploy_app.sh";
+impl Drop for ActiveReleaseGuard {
+    fn drop(&mut self) {
+        if let Ok(mut active) = ACTIVE_RELEASES.lock() {
+            active.remove(&self.release_id);
+        }
+    }
+}
 
-// Define progress pattern for parsing script output
+// Deployment slots for releases (overall and per environment) and for item types
+// with a concurrency limit, plus the queue positions last stored on waiting releases
 lazy_static::lazy_static! {
-    static ref PROGRESS_PATTERN: Regex = Regex::new(r"\[PROGRESS:([a-z]+):(\d+)\]").unwrap();
+    static ref RELEASE_SLOTS: Mutex<Limiter> = Mutex::new(Limiter::new(config::deployment().release_limits()));
+    static ref ITEM_SLOTS: Mutex<Limiter> = Mutex::new(Limiter::new(config::deployment().item_limits()));
+    static ref QUEUE_POSITIONS: Mutex<HashMap<Uuid, usize>> = Mutex::new(HashMap::new());
+}
+
+// Holds a release's deployment slot until dropped
+struct ReleaseSlot {
+    keys: Vec<String>,
+    db: web::Data<SledStorage>,
+}
+
+impl Drop for ReleaseSlot {
+    fn drop(&mut self) {
+        if let Ok(mut slots) = RELEASE_SLOTS.lock() {
+            slots.release(&self.keys);
+        }
+        // Waiting releases move up
+        publish_queue_positions(&self.db);
+    }
+}
+
+// Holds a slot of an item type until dropped
+struct ItemSlot {
+    keys: Vec<String>,
+}
+
+impl Drop for ItemSlot {
+    fn drop(&mut self) {
+        if let Ok(mut slots) = ITEM_SLOTS.lock() {
+            slots.release(&self.keys);
+        }
+    }
+}
+
+// Wait until the release may deploy without going over the concurrency limits.
+// Returns None if the release stopped waiting (e.g. because it was cancelled).
+async fn acquire_release_slot(db: &web::Data<SledStorage>, release_id: Uuid, env_name: &str) -> Option<ReleaseSlot> {
+    let keys = vec!["releases".to_string(), format!("environment:{}", env_name)];
+    let mut granted = RELEASE_SLOTS.lock().ok()?.enqueue(release_id, keys.clone());
+    
+    if granted.try_recv().is_err() {
+        publish_queue_positions(db);
+        info!("Release {} is waiting for a free deployment slot in {}", release_id, env_name);
+        broadcast_app_log("info", &format!("Release {} is queued for deployment to {}", release_id, env_name));
+        
+        if granted.await.is_err() {
+            publish_queue_positions(db);
+            return None;
+        }
+    }
+    
+    let slot = ReleaseSlot { keys, db: db.clone() };
+    publish_queue_positions(db);
+    Some(slot)
+}
+
+// Give up a release's place in the deployment queue, returns false if it wasn't waiting
+fn withdraw_release(release_id: Uuid) -> bool {
+    RELEASE_SLOTS.lock()
+        .map(|mut slots| slots.withdraw(release_id))
+        .unwrap_or(false)
+}
+
+// Wait for a free slot of the item's type. Returns None if the item was cancelled
+// while waiting, or right away for item types without a limit.
+async fn acquire_item_slot(item_name: &str, control: &ItemControl) -> Option<ItemSlot> {
+    let keys = vec![format!("item:{}", item_name)];
+    let waiter_id = Uuid::new_v4();
+    let mut granted = ITEM_SLOTS.lock().ok()?.enqueue(waiter_id, keys.clone());
+    if granted.try_recv().is_ok() {
+        return Some(ItemSlot { keys });
+    }
+    
+    info!("Deployment item {} is waiting for a free slot", item_name);
+    tokio::select! {
+        result = &mut granted => result.ok().map(|_| ItemSlot { keys }),
+        _ = control.cancelled() => {
+            if let Ok(mut slots) = ITEM_SLOTS.lock() {
+                // The slot may have been granted just now, give it back then
+                if !slots.withdraw(waiter_id) {
+                    slots.release(&keys);
+                }
+            }
+            None
+        }
+    }
+}
+
+// Store the queue position of every waiting release that moved, and clear it on
+// releases that left the queue
+fn publish_queue_positions(db: &SledStorage) {
+    let mut published = match QUEUE_POSITIONS.lock() {
+        Ok(published) => published,
+        Err(_) => return,
+    };
+    let current: HashMap<Uuid, usize> = match RELEASE_SLOTS.lock() {
+        Ok(slots) => slots.positions().into_iter().collect(),
+        Err(_) => return,
+    };
+    
+    let left: Vec<Uuid> = published.keys()
+        .filter(|release_id| !current.contains_key(release_id))
+        .copied()
+        .collect();
+    for release_id in left {
+        published.remove(&release_id);
+        store_queue_position(db, release_id, None);
+    }
+    
+    for (release_id, position) in current {
+        if published.get(&release_id) != Some(&position) {
+            published.insert(release_id, position);
+            store_queue_position(db, release_id, Some(position));
+        }
+    }
+}
+
+fn store_queue_position(db: &SledStorage, release_id: Uuid, position: Option<usize>) {
+    match db.update_release(&release_id, |release| release.queue_position = position) {
+        Ok(Some(_)) => broadcast_queue_update(release_id.to_string(), position),
+        Ok(None) => {}
+        Err(e) => error!("Failed to store queue position of release {}: {}", release_id, e),
+    }
+}
+
+// Claim a release for processing, returns None if it is already being processed
+pub fn claim_release(release_id: Uuid) -> Option<ActiveReleaseGuard> {
+    let mut active = ACTIVE_RELEASES.lock().ok()?;
+    if !active.insert(release_id) {
+        return None;
+    }
+    
+    Some(ActiveReleaseGuard { release_id })
+}
+
+// Claim a deployment item for execution, returns None if it is already running
+fn claim_item(release_id: Uuid, item_name: &str) -> Option<RunningItemGuard> {
+    let mut running = RUNNING_ITEMS.lock().ok()?;
+    let key = (release_id, item_name.to_string());
+    if running.contains_key(&key) {
+        return None;
+    }
+    
+    let control = ItemControl::default();
+    running.insert(key, control.clone());
+    
+    Some(RunningItemGuard {
+        release_id,
+        item_name: item_name.to_string(),
+        control,
+    })
+}
+
+// Check whether a deployment item currently has a script running
+pub fn is_item_running(release_id: Uuid, item_name: &str) -> bool {
+    RUNNING_ITEMS.lock()
+        .map(|running| running.contains_key(&(release_id, item_name.to_string())))
+        .unwrap_or(false)
+}
+
+// Cancel a running deployment item, returns false if it isn't running
+pub fn cancel_item(release_id: Uuid, item_name: &str) -> bool {
+    let running = match RUNNING_ITEMS.lock() {
+        Ok(running) => running,
+        Err(_) => return false,
+    };
+    
+    match running.get(&(release_id, item_name.to_string())) {
+        Some(control) => {
+            info!("Cancelling deployment item {} of release {}", item_name, release_id);
+            control.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+// Answer the confirmation a running item's script asked for, returns an error
+// message if the item isn't waiting for one
+pub async fn answer_confirmation(
+    db: &SledStorage,
+    release_id: Uuid,
+    item_name: &str,
+    approved: bool,
+    answered_by: String,
+) -> Result<(), String> {
+    let control = RUNNING_ITEMS.lock().ok()
+        .and_then(|running| running.get(&(release_id, item_name.to_string())).cloned())
+        .ok_or_else(|| format!("Deployment item {} is not running", item_name))?;
+    
+    let release = db.get_release(&release_id)
+        .map_err(|e| format!("Failed to get release: {}", e))?
+        .ok_or_else(|| format!("Release with ID {} not found", release_id))?;
+    let item = release.deployment_items.iter()
+        .find(|item| item.name == item_name)
+        .ok_or_else(|| format!("Deployment item {} not found", item_name))?;
+    if item.pending_confirmation.is_none() {
+        return Err(format!("Deployment item {} is not waiting for a confirmation", item_name));
+    }
+    
+    control.answer(approved).await
+        .map_err(|e| format!("Failed to answer {}: {}", item_name, e))?;
+    
+    info!("Confirmation of item {} of release {} answered with {} by {}", item_name, release_id,
+        if approved { "yes" } else { "no" }, answered_by);
+    record_item_event(db, &release_id, item_name, ItemEventKind::ConfirmationAnswered { approved, answered_by }, item.runs);
+    
+    // Another rollout target may be waiting for an answer as well
+    if let Some(prompt) = control.next_prompt() {
+        record_item_event(db, &release_id, item_name, ItemEventKind::ConfirmationRequested { prompt }, item.runs);
+    }
+    Ok(())
+}
+
+// Mark the manual step a running item is waiting on as done or failed
+pub fn complete_manual_step(
+    db: &SledStorage,
+    release_id: Uuid,
+    item_name: &str,
+    done: bool,
+    completed_by: String,
+    note: Option<String>,
+) -> Result<(), String> {
+    let control = RUNNING_ITEMS.lock().ok()
+        .and_then(|running| running.get(&(release_id, item_name.to_string())).cloned())
+        .ok_or_else(|| format!("Deployment item {} is not running", item_name))?;
+    
+    let release = db.get_release(&release_id)
+        .map_err(|e| format!("Failed to get release: {}", e))?
+        .ok_or_else(|| format!("Release with ID {} not found", release_id))?;
+    let item = release.deployment_items.iter()
+        .find(|item| item.name == item_name)
+        .ok_or_else(|| format!("Deployment item {} not found", item_name))?;
+    let sender = control.take_manual_step()
+        .filter(|_| item.pending_manual_step.is_some())
+        .ok_or_else(|| format!("Deployment item {} is not waiting for a manual step", item_name))?;
+    
+    info!("Manual step {} of release {} marked {} by {}", item_name, release_id,
+        if done { "done" } else { "failed" }, completed_by);
+    let answer = if done {
+        Ok(())
+    } else {
+        Err(match &note {
+            Some(note) => format!("Manual step {} marked as failed by {}: {}", item_name, completed_by, note),
+            None => format!("Manual step {} marked as failed by {}", item_name, completed_by),
+        })
+    };
+    // Recorded before the run goes on so the outcome is stored when the item finishes
+    record_item_event(db, &release_id, item_name, ItemEventKind::ManualStepCompleted { done, completed_by, note }, item.runs);
+    
+    sender.send(answer)
+        .map_err(|_| format!("Deployment item {} stopped waiting for its manual step", item_name))
+}
+
+// Cancel every running item of a release, returns how many were signalled
+pub fn cancel_release_items(release_id: Uuid) -> usize {
+    let running = match RUNNING_ITEMS.lock() {
+        Ok(running) => running,
+        Err(_) => return 0,
+    };
+    
+    let mut count = 0;
+    for ((id, item_name), control) in running.iter() {
+        if *id == release_id {
+            info!("Cancelling deployment item {} of release {}", item_name, release_id);
+            control.cancel();
+            count += 1;
+        }
+    }
+    
+    count
+}
+
+// Cancel a release: running items are signalled and the scheduler records their final
+// state, the others are cancelled right away. If nothing was running the release itself
+// is done too, otherwise the scheduler finalizes it. Returns the release and how many
+// running items were signalled, None if the release doesn't exist.
+pub fn cancel_release(
+    db: &SledStorage,
+    release_id: Uuid,
+) -> Result<Option<(Release, usize)>, Box<dyn std::error::Error + Send + Sync>> {
+    let signalled = cancel_release_items(release_id);
+    
+    let updated = db.update_release(&release_id, |release| {
+        for item in release.deployment_items.iter_mut() {
+            let finished = matches!(item.status,
+                ReleaseStatus::ReadyToTestInStaging |
+                ReleaseStatus::ReadyToTestInProduction |
+                ReleaseStatus::Error |
+                ReleaseStatus::Skipped);
+            if !finished && !is_item_running(release_id, &item.name) {
+                item.status = ReleaseStatus::Cancelled;
+            }
+        }
+        
+        if signalled == 0 {
+            release.status = ReleaseStatus::Cancelled;
+        }
+    })?;
+    
+    let release = match updated {
+        Some(release) => release,
+        None => return Ok(None),
+    };
+    info!("Cancelled release {} ({} running items signalled)", release_id, signalled);
+    schedule_release(&release);
+    
+    if signalled == 0 {
+        broadcast_release_update(
+            release.id.to_string(),
+            format!("{:?}", release.status),
+            release.progress,
+            Some(format!("Deployment cancelled for {}", release.title))
+        );
+    }
+    Ok(Some((release, signalled)))
 }
 
 // Start scheduler to check for pending releases
 pub fn start_scheduler(db: web::Data<SledStorage>) -> tokio::task::JoinHandle<()> {
     let db = db.clone();
     
+    // Runners that stop polling fail the items they were running
+    runners::start_reaper();
+    
+    // Recurring releases create their releases as their occurrences come due
+    recurring::start(db.clone());
+    
     tokio::spawn(async move {
-        let mut interval = interval(CHECK_INTERVAL);
+        // Deal with deployments a previous run of blend didn't get to finish
+        match recover_interrupted_releases(&db).await {
+            Ok(count) => {
+                if count > 0 {
+                    info!("Recovered {} interrupted releases", count);
+                }
+            }
+            Err(e) => {
+                error!("Error recovering interrupted releases: {}", e);
+                broadcast_app_log("error", &format!("Error recovering interrupted releases: {}", e));
+            }
+        }
+        
+        // Fill the queue once, later changes come in through schedule_release
+        match db.get_releases_to_process() {
+            Ok(releases) => {
+                for release in releases.iter() {
+                    schedule_release(release);
+                }
+                info!("Scheduler started with {} queued releases", releases.len());
+            }
+            Err(e) => {
+                error!("Error loading releases to schedule: {}", e);
+                broadcast_app_log("error", &format!("Scheduler error: {}", e));
+            }
+        }
+        
+        let mut prune_interval = interval(PRUNE_INTERVAL);
         
         loop {
-            interval.tick().await;
-            
-            info!("Checking for releases to process...");
-            broadcast_app_log("info", "Scheduler checking for releases to process");
-            
-            match check_releases_to_process(db.clone()).await {
+            match start_due_releases(db.clone()) {
                 Ok(count) => {
                     if count > 0 {
-                        info!("Processed {} releases", count);
+                        info!("Processing {} releases", count);
                         broadcast_app_log("info", &format!("Scheduler processing {} releases", count));
                     }
                 }
@@ -53,57 +731,168 @@ pub fn start_scheduler(db: web::Data<SledStorage>) -> tokio::task::JoinHandle<()
                 }
             }
             
-            // Also periodically prune stale WebSocket connections
-            match db.prune_stale_websockets() {
-                Ok(count) => {
-                    if count > 0 {
-                        info!("Pruned {} stale WebSocket connections", count);
-                        broadcast_app_log("info", &format!("Pruned {} stale WebSocket connections", count));
+            // Sleep until the next release is due, or until the queue changes
+            let next_due_at = QUEUE.lock().ok().and_then(|queue| queue.next_due_at());
+            let wait = match next_due_at {
+                Some(due_at) => (due_at - Utc::now()).to_std().unwrap_or(Duration::ZERO).min(IDLE_WAIT),
+                None => IDLE_WAIT,
+            };
+            
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = WAKEUP.notified() => {}
+                _ = prune_interval.tick() => {
+                    // Also periodically prune stale WebSocket connections
+                    match db.prune_stale_websockets() {
+                        Ok(count) => {
+                            if count > 0 {
+                                info!("Pruned {} stale WebSocket connections", count);
+                                broadcast_app_log("info", &format!("Pruned {} stale WebSocket connections", count));
+                            }
+                        }
+                        Err(e) => {
+                            error!("Error pruning stale WebSocket connections: {}", e);
+                            broadcast_app_log("error", &format!("Error pruning WebSocket connections: {}", e));
+                        }
                     }
                 }
-                Err(e) => {
-                    error!("Error pruning stale WebSocket connections: {}", e);
-                    broadcast_app_log("error", &format!("Error pruning WebSocket connections: {}", e));
-                }
             }
         }
     })
 }
 
-// Check for releases that need to be processed
-async fn check_releases_to_process(db: web::Data<SledStorage>) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
-    // Get releases that need processing (those in a "Deploying" state)
-    let releases_to_process = db.get_releases_to_process()?;
-    let count = releases_to_process.len();
-    
-    if count == 0 {
-        return Ok(0);
-    }
+// Find releases that were left deploying when blend stopped. Their scripts are gone,
+// so every unfinished item gets a note in its log and is then either left for the
+// scheduler to resume or marked as failed, depending on the recovery policy.
+async fn recover_interrupted_releases(db: &web::Data<SledStorage>) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let policy = config::deployment().recovery_policy;
+    let mut count = 0;
     
-    info!("Found {} releases to process", count);
-    
-    // Process each release
-    for release in releases_to_process {
-        info!("Processing release: {}", release.id);
+    for release in db.get_all_releases()? {
+        // Rollbacks are never resumed, the items still being rolled back are marked as failed
+        if release.status == ReleaseStatus::RollingBack {
+            count += 1;
+            warn!("Release {} was interrupted while rolling back", release.id);
+            for item in release.deployment_items.iter().filter(|item| item.status == ReleaseStatus::RollingBack) {
+                append_log_line(db, &release.id, &item.name, LogStream::System,
+                    "Rollback was interrupted by a restart, marking the item as failed", item.runs);
+            }
+            
+            db.update_release(&release.id, |release| {
+                release.status = ReleaseStatus::Error;
+                for item in release.deployment_items.iter_mut() {
+                    if item.status == ReleaseStatus::RollingBack {
+                        item.status = ReleaseStatus::Error;
+                        item.error = Some("Rollback interrupted by a restart".to_string());
+                    }
+                }
+            })?;
+            
+            broadcast_app_log("warn", &format!("Rollback interrupted by a restart: {}", release.title));
+            continue;
+        }
         
-        // Clone release for async processing
-        let release_id = release.id;
-        let db_clone = db.clone();
+        let deploying_status = release.status.clone();
+        if !matches!(deploying_status, ReleaseStatus::DeployingToStaging | ReleaseStatus::DeployingToProduction) {
+            continue;
+        }
         
-        // Process release in a separate task
-        tokio::spawn(async move {
-            if let Err(e) = process_release(release_id, db_clone).await {
-                error!("Error processing release {}: {}", release_id, e);
-                broadcast_app_log("error", &format!("Error processing release {}: {}", release_id, e));
+        count += 1;
+        let interrupted: Vec<(String, u32)> = release.deployment_items.iter()
+            .filter(|item| !item_finished(&item.status))
+            .map(|item| (item.name.clone(), item.runs))
+            .collect();
+        
+        warn!("Release {} was interrupted while deploying, {} unfinished items ({:?} policy)",
+              release.id, interrupted.len(), policy);
+        
+        for (item_name, run) in &interrupted {
+            let message = match policy {
+                RecoveryPolicy::Resume => "Deployment was interrupted by a restart, the item will be run again",
+                RecoveryPolicy::Fail => "Deployment was interrupted by a restart, marking the item as failed",
+            };
+            append_log_line(db, &release.id, item_name, LogStream::System, message, *run);
+        }
+        
+        match policy {
+            RecoveryPolicy::Resume => {
+                broadcast_app_log("warn", &format!("Resuming interrupted deployment: {}", release.title));
+                // The scheduler picks the release up again and only runs the unfinished items
             }
-        });
-    }
-    
-    Ok(count)
-}
+            RecoveryPolicy::Fail => {
+                db.update_release(&release.id, |release| {
+                    for item in release.deployment_items.iter_mut() {
+                        if !item_finished(&item.status) {
+                            item.status = ReleaseStatus::Error;
+                            item.error = Some("Interrupted by a restart".to_string());
+                        }
+                    }
+                })?;
+                
+                broadcast_app_log("warn", &format!("Deployment interrupted by a restart: {}", release.title));
+                finalize_release(release.id, db.clone()).await?;
+            }
+        }
+    }
+    
+    Ok(count)
+}
+
+// Start processing every queued release that is due
+fn start_due_releases(db: web::Data<SledStorage>) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let now = Utc::now();
+    let due = match QUEUE.lock() {
+        Ok(mut queue) => queue.pop_due(now),
+        Err(_) => return Err("Schedule queue lock poisoned".into()),
+    };
+    
+    let mut count = 0;
+    for release_id in due {
+        // The queue only holds ids, so check the stored release is still due
+        let release = match db.get_release(&release_id)? {
+            Some(release) => release,
+            None => continue,
+        };
+        if !release.should_process() {
+            continue;
+        }
+        if release.scheduled_at > now {
+            schedule_release(&release);
+            continue;
+        }
+        
+        info!("Processing release: {}", release.id);
+        count += 1;
+        
+        // Process release in a separate task
+        let db_clone = db.clone();
+        tokio::spawn(async move {
+            if let Err(e) = process_release(release_id, db_clone).await {
+                error!("Error processing release {}: {}", release_id, e);
+                broadcast_app_log("error", &format!("Error processing release {}: {}", release_id, e));
+            }
+        });
+    }
+    
+    Ok(count)
+}
 
 // Process a single release
-async fn process_release(release_id: uuid::Uuid, db: web::Data<SledStorage>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+async fn process_release(release_id: Uuid, db: web::Data<SledStorage>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // A release can be queued again (e.g. by an update) while it is still deploying
+    let _active = match claim_release(release_id) {
+        Some(guard) => guard,
+        None => {
+            info!("Release {} is already being processed", release_id);
+            return Ok(());
+        }
+    };
+    
+    // The real deployment wins over a rehearsal of it
+    if dry_run::cancel(release_id) {
+        info!("Cancelled the dry run of release {} because it is being deployed", release_id);
+    }
+    
     // Get release
     let mut release = match db.get_release(&release_id)? {
         Some(release) => release,
@@ -114,6 +903,7 @@ async fn process_release(release_id: uuid::Uuid, db: web::Data<SledStorage>) ->
     };
     
     info!("Starting release process for {}: {}", release.id, release.title);
+    let release_status_before = release.status.clone();
     
     // Determine current environment from the release status
     let env_name = match release.status {
@@ -130,8 +920,68 @@ async fn process_release(release_id: uuid::Uuid, db: web::Data<SledStorage>) ->
         }
     };
     
+    // Wait for a free deployment slot, releases over the concurrency limits queue up here
+    let _slot = match acquire_release_slot(&db, release_id, env_name).await {
+        Some(slot) => slot,
+        None => {
+            info!("Release {} left the deployment queue before it started", release_id);
+            return Ok(());
+        }
+    };
+    
+    // The release may have been edited while it was waiting
+    release = match db.get_release(&release_id)? {
+        Some(release) if release.status == release_status_before => release,
+        _ => {
+            info!("Release {} changed while waiting for a deployment slot, not processing", release_id);
+            return Ok(());
+        }
+    };
+    release.queue_position = None;
+    
+    let environment = if env_name == "staging" {
+        Environment::Staging
+    } else {
+        Environment::Production
+    };
+    
+    // Releases that are due during a freeze wait for its end, unless an Admin overrode it.
+    // This covers deployments resumed after a restart as well as new ones.
+    if release.freeze_override_by.is_none() {
+        if let Some(freeze) = db.find_freeze(&environment, &release.client_id, Utc::now())? {
+            let message = format!("{:?} is frozen until {} ({}), holding the deployment",
+                environment, freeze.ends_at.format("%Y-%m-%d %H:%M UTC"), freeze.reason);
+            info!("Release {}: {}", release_id, message);
+            broadcast_app_log("info", &format!("{}: {}", release.title, message));
+            broadcast_release_update(
+                release.id.to_string(),
+                format!("{:?}", release.status),
+                release.progress,
+                Some(message)
+            );
+            hold_release(release_id, freeze.ends_at);
+            return Ok(());
+        }
+    }
+    
+    // Releases of a stopped train don't deploy anymore
+    if release.pending_deployment_environment().is_some() {
+        if let Some(stopped) = train::stopped_train(&db, &release)? {
+            return train::cancel_held_release(&db, &release, &stopped);
+        }
+    }
+    
+    // A release that is already deploying without being processed was interrupted
+    // (e.g. by a restart), only its unfinished items are run again
+    let resuming = matches!(release.status,
+        ReleaseStatus::DeployingToStaging |
+        ReleaseStatus::DeployingToProduction);
+    
     // Reset progress to 0 when starting the deployment
-    release.progress = 0.0;
+    if !resuming {
+        release.progress = 0.0;
+    }
+    release.deployment_environment = Some(environment);
     
     // Update the status to the appropriate "Deploying" status if it's in a waiting state
     release.status = match release.status {
@@ -141,362 +991,1559 @@ async fn process_release(release_id: uuid::Uuid, db: web::Data<SledStorage>) ->
         _ => release.status, // Keep current status if it's already deploying
     };
     
-    // Update all deployment items to the same status as the release
-    for item in release.deployment_items.iter_mut() {
-        item.status = release.status.clone();
+    // Update all deployment items to the same status as the release, except for
+    // items that already finished when resuming
+    for item in release.deployment_items.iter_mut() {
+        if !resuming || !item_finished(&item.status) {
+            item.status = release.status.clone();
+            item.progress = 0.0;
+        }
+    }
+    
+    // Save the updated release, unless it was changed (e.g. cancelled) since we read it
+    let original_status = release_status_before;
+    let mut changed = false;
+    db.update_release(&release_id, |stored| {
+        if stored.status == original_status {
+            *stored = release.clone();
+        } else {
+            changed = true;
+        }
+    })?;
+    
+    if changed {
+        info!("Release {} changed before its deployment started, not processing", release_id);
+        return Ok(());
+    }
+    
+    // Add more detailed logging
+    info!("STARTING DEPLOYMENT: Release {} - {} is being deployed to {}", 
+          release_id, release.title, env_name);
+    broadcast_app_log("info", &format!("Starting deployment: {} to {}", release.title, env_name));
+    
+    // Broadcast status update
+    broadcast_release_update(
+        release.id.to_string(),
+        format!("{:?}", release.status), 
+        0.0, 
+        Some(format!("Starting deployment process for {}", release.title))
+    );
+    
+    // Run the items as a DAG: independent items run in parallel and an item only
+    // starts once every prerequisite in this release has succeeded
+    let item_names: Vec<String> = release.deployment_items.iter().map(|item| item.name.clone()).collect();
+    let dependencies: HashMap<String, Vec<String>> = item_names.iter()
+        .map(|name| (name.clone(), config::deployment().dependencies_within(name, &item_names)))
+        .collect();
+    
+    // Items that already finished count as done for their dependents
+    let mut pending = Vec::new();
+    let mut succeeded: HashMap<String, bool> = HashMap::new();
+    for item in release.deployment_items.iter() {
+        if item.status == release.status {
+            pending.push(item.name.clone());
+        } else {
+            succeeded.insert(item.name.clone(), item_succeeded(&item.status));
+        }
+    }
+    let mut running = tokio::task::JoinSet::new();
+    
+    loop {
+        // Start or skip every pending item whose prerequisites are all done
+        while let Some((item_name, failed)) = next_ready_item(&mut pending, &dependencies, &succeeded) {
+            if let Some(failed) = failed {
+                skip_item(&db, release_id, &item_name, &failed, &release.status);
+                succeeded.insert(item_name, false);
+                continue;
+            }
+            
+            // Items cancelled while waiting for their prerequisites are left alone
+            if !item_still_pending(&db, release_id, &item_name, &release.status) {
+                info!("Deployment item {} of release {} is no longer pending, not starting it", item_name, release_id);
+                succeeded.insert(item_name, false);
+                continue;
+            }
+            
+            // Items that are already running are left alone
+            let guard = match claim_item(release_id, &item_name) {
+                Some(guard) => guard,
+                None => {
+                    warn!("Deployment item {} of release {} is already running, skipping", item_name, release_id);
+                    succeeded.insert(item_name, false);
+                    continue;
+                }
+            };
+            
+            let db_clone = db.clone();
+            let env_name = env_name.to_string();
+            
+            // Run in a separate task
+            running.spawn(async move {
+                let control = guard.control.clone();
+                let result = run_and_record_item(db_clone, release_id, item_name.clone(), env_name, control).await;
+                drop(guard);
+                (item_name, result.is_ok())
+            });
+        }
+        
+        // Wait for the next deployment item to complete
+        match running.join_next().await {
+            Some(Ok((item_name, ok))) => {
+                succeeded.insert(item_name, ok);
+            }
+            Some(Err(e)) => error!("Error joining task: {}", e),
+            None => break,
+        }
+    }
+    
+    // Only items whose prerequisite task died without reporting back are left
+    for item_name in pending {
+        let unfinished = dependencies[&item_name].iter()
+            .find(|dep| !succeeded.get(*dep).copied().unwrap_or(false))
+            .cloned()
+            .unwrap_or_default();
+        skip_item(&db, release_id, &item_name, &unfinished, &release.status);
+    }
+    
+    finalize_release(release_id, db.clone()).await?;
+    
+    // A failed client counts against its release train
+    if let Err(e) = train::record_outcome(&db, release_id) {
+        error!("Error recording the outcome of release {} on its train: {}", release_id, e);
+    }
+    
+    // Don't leave production half deployed, undo the items that did succeed
+    if env_name == "production" && config::deployment().auto_rollback {
+        let failed = db.get_release(&release_id)?
+            .is_some_and(|release| release.status == ReleaseStatus::Error);
+        if failed {
+            info!("Deployment of release {} to production failed, rolling back", release_id);
+            rollback_release(release_id, db, "auto_rollback").await?;
+        }
+    }
+    
+    Ok(())
+}
+
+// Take the next pending item whose prerequisites are all done, along with the first
+// prerequisite that didn't succeed, in which case the item has to be skipped
+fn next_ready_item(
+    pending: &mut Vec<String>,
+    dependencies: &HashMap<String, Vec<String>>,
+    succeeded: &HashMap<String, bool>,
+) -> Option<(String, Option<String>)> {
+    let index = pending.iter().position(|name| {
+        dependencies[name].iter().all(|dep| succeeded.contains_key(dep))
+    })?;
+    let item_name = pending.remove(index);
+    let failed = dependencies[&item_name].iter().find(|dep| !succeeded[*dep]).cloned();
+    Some((item_name, failed))
+}
+
+// Whether an item is done with the current deployment, successfully or not
+fn item_finished(status: &ReleaseStatus) -> bool {
+    matches!(status,
+        ReleaseStatus::ReadyToTestInStaging |
+        ReleaseStatus::ReadyToTestInProduction |
+        ReleaseStatus::Error |
+        ReleaseStatus::Cancelled |
+        ReleaseStatus::Skipped)
+}
+
+fn item_succeeded(status: &ReleaseStatus) -> bool {
+    matches!(status,
+        ReleaseStatus::ReadyToTestInStaging |
+        ReleaseStatus::ReadyToTestInProduction)
+}
+
+// Check that an item still has the deploying status it was given when the release started
+fn item_still_pending(db: &SledStorage, release_id: Uuid, item_name: &str, deploying_status: &ReleaseStatus) -> bool {
+    match db.get_release(&release_id) {
+        Ok(Some(release)) => release.deployment_items.iter()
+            .any(|item| item.name == item_name && item.status == *deploying_status),
+        _ => false,
+    }
+}
+
+// Mark an item as skipped because one of its prerequisites did not succeed
+fn skip_item(db: &SledStorage, release_id: Uuid, item_name: &str, failed_dependency: &str, deploying_status: &ReleaseStatus) {
+    let message = format!("Skipped because prerequisite {} did not succeed", failed_dependency);
+    let mut skipped = false;
+    let mut run = 0;
+    
+    let result = db.update_release(&release_id, |release| {
+        if let Some(item) = release.deployment_items.iter_mut().find(|it| it.name == item_name) {
+            // A cancelled item stays cancelled
+            if item.status == *deploying_status {
+                item.status = ReleaseStatus::Skipped;
+                item.error = Some(message.clone());
+                run = item.runs;
+                skipped = true;
+            }
+        }
+    });
+    
+    match result {
+        Ok(Some(release)) if skipped => {
+            warn!("Deployment item {} of release {}: {}", item_name, release_id, message);
+            if let Some(item) = release.deployment_items.iter().find(|it| it.name == item_name) {
+                broadcast_item_update(release.id.to_string(), item);
+            }
+            append_log_line(db, &release_id, item_name, LogStream::System, &message, run);
+            broadcast_release_update(
+                release.id.to_string(),
+                "InProgress".to_string(),
+                release.progress,
+                Some(format!("[{}] {}", item_name, message))
+            );
+        }
+        Ok(_) => {}
+        Err(e) => error!("Failed to mark item {} of release {} as skipped: {}", item_name, release_id, e),
+    }
+}
+
+// Roll back a release on demand, returns false if the release is busy
+pub fn spawn_rollback(db: web::Data<SledStorage>, release_id: Uuid, requested_by: String) -> bool {
+    let active = match claim_release(release_id) {
+        Some(guard) => guard,
+        None => return false,
+    };
+    dry_run::cancel(release_id);
+    
+    tokio::spawn(async move {
+        let _active = active;
+        if let Err(e) = rollback_release(release_id, db, &requested_by).await {
+            error!("Error rolling back release {}: {}", release_id, e);
+            broadcast_app_log("error", &format!("Error rolling back release {}: {}", release_id, e));
+        }
+    });
+    
+    true
+}
+
+// Order in which items are rolled back: dependents before their prerequisites
+fn rollback_order(items: &[String]) -> Vec<String> {
+    let mut order = Vec::new();
+    let mut remaining = items.to_vec();
+    while !remaining.is_empty() {
+        let index = remaining.iter()
+            .position(|name| config::deployment().dependencies_within(name, &remaining).is_empty())
+            .unwrap_or(0);
+        order.push(remaining.remove(index));
+    }
+    
+    order.reverse();
+    order
+}
+
+// Undo the items the latest run deployed successfully, one at a time. Items without a
+// rollback command stay deployed. Stops at the first rollback that fails, the release
+// ends up RolledBack if none did and in Error otherwise.
+async fn rollback_release(release_id: Uuid, db: web::Data<SledStorage>, requested_by: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let release = match db.get_release(&release_id)? {
+        Some(release) => release,
+        None => return Err("Release not found".into()),
+    };
+    
+    let environment = release.last_deployment_environment();
+    let env_name = environment.script_name();
+    let deployed_status = environment.deployed_status();
+    
+    let (to_roll_back, without_rollback): (Vec<&DeploymentItem>, Vec<&DeploymentItem>) = release.deployment_items.iter()
+        .filter(|item| item.status == deployed_status)
+        .partition(|item| config::deployment().item(&item.name).is_some_and(|config| config.rollback.is_some()));
+    
+    for item in &without_rollback {
+        append_log_line(&db, &release_id, &item.name, LogStream::System,
+            "No rollback command configured, the item stays deployed", item.runs);
+    }
+    
+    if to_roll_back.is_empty() {
+        warn!("Release {} has no deployed items that can be rolled back", release_id);
+        broadcast_app_log("warn", &format!("Nothing to roll back for {}", release.title));
+        return Ok(());
+    }
+    
+    let item_names: Vec<String> = to_roll_back.iter().map(|item| item.name.clone()).collect();
+    let updated = db.update_release(&release_id, |release| {
+        release.status = ReleaseStatus::RollingBack;
+        for item in release.deployment_items.iter_mut() {
+            if item_names.contains(&item.name) {
+                item.status = ReleaseStatus::RollingBack;
+            }
+        }
+    })?;
+    
+    if let Some(release) = &updated {
+        for item in release.deployment_items.iter().filter(|item| item_names.contains(&item.name)) {
+            append_log_line(&db, &release_id, &item.name, LogStream::System,
+                &format!("Rollback in {} requested by {}", env_name, requested_by), item.runs);
+            broadcast_item_update(release_id.to_string(), item);
+        }
+    }
+    
+    info!("Rolling back {} items of release {} in {} for {}", item_names.len(), release_id, env_name, requested_by);
+    broadcast_app_log("warn", &format!("Rolling back {} in {}", release.title, env_name));
+    broadcast_release_update(
+        release_id.to_string(),
+        format!("{:?}", ReleaseStatus::RollingBack),
+        release.progress,
+        Some(format!("Rolling back {}", item_names.join(", ")))
+    );
+    
+    let mut failed: Option<String> = None;
+    for item_name in rollback_order(&item_names) {
+        // Once a rollback failed the remaining items are left deployed
+        if let Some(failed_item) = &failed {
+            let message = format!("Not rolled back because the rollback of {} failed", failed_item);
+            let restored = db.update_release(&release_id, |release| {
+                if let Some(item) = release.deployment_items.iter_mut().find(|it| it.name == item_name) {
+                    item.status = deployed_status.clone();
+                }
+            })?;
+            if let Some(item) = restored.as_ref().and_then(|release| release.deployment_items.iter().find(|it| it.name == item_name)) {
+                append_log_line(&db, &release_id, &item_name, LogStream::System, &message, item.runs);
+                broadcast_item_update(release_id.to_string(), item);
+            }
+            continue;
+        }
+        
+        let guard = match claim_item(release_id, &item_name) {
+            Some(guard) => guard,
+            None => {
+                warn!("Deployment item {} of release {} is already running, not rolling it back", item_name, release_id);
+                failed = Some(item_name);
+                continue;
+            }
+        };
+        
+        let control = guard.control.clone();
+        let result = run_and_record_rollback(db.clone(), release_id, item_name.clone(), env_name.to_string(), control).await;
+        drop(guard);
+        
+        if result.is_err() {
+            failed = Some(item_name);
+        }
+    }
+    
+    let final_status = if failed.is_none() { ReleaseStatus::RolledBack } else { ReleaseStatus::Error };
+    let release = match db.update_release(&release_id, |release| {
+        release.status = final_status.clone();
+        release.progress = 0.0;
+    })? {
+        Some(release) => release,
+        None => return Err("Release not found after rollback".into()),
+    };
+    
+    let (message, level) = match &failed {
+        None => (format!("Rollback complete for {}", release.title), "warn"),
+        Some(item_name) => (format!("Rollback of {} failed at {}", release.title, item_name), "error"),
+    };
+    info!("{}", message);
+    broadcast_release_update(
+        release.id.to_string(),
+        format!("{:?}", release.status),
+        release.progress,
+        Some(message.clone())
+    );
+    broadcast_app_log(level, &message);
+    
+    Ok(())
+}
+
+// Run one item's rollback command and store the outcome on the release
+async fn run_and_record_rollback(
+    db: web::Data<SledStorage>,
+    release_id: Uuid,
+    item_name: String,
+    env_name: String,
+    control: ItemControl,
+) -> Result<(), ItemError> {
+    // A rollback is a run of its own, but never retried
+    let mut run = 0;
+    let started = db.update_release(&release_id, |release| {
+        if let Some(item) = release.deployment_items.iter_mut().find(|it| it.name == item_name) {
+            item.start_run();
+            item.attempt = 1;
+            item.max_attempts = 1;
+            run = item.runs;
+        }
+    }).map_err(|e| format!("Failed to start rollback of {}: {}", item_name, e))?;
+    
+    if let Some(item) = started.as_ref().and_then(|release| release.deployment_items.iter().find(|it| it.name == item_name)) {
+        broadcast_item_update(release_id.to_string(), item);
+    }
+    
+    let item_run = ItemRun {
+        db: db.clone(),
+        release_id,
+        item_name: item_name.clone(),
+        env_name: env_name.clone(),
+        run,
+        attempt: 1,
+        max_attempts: 1,
+        timeout: config::deployment().item_timeout(&item_name, &env_name),
+        control,
+        rollback: true,
+        dry_run: false,
+        target: None,
+    };
+    
+    let result = rollout::run(&item_run).await;
+    
+    let updated = db.update_release(&release_id, |release| {
+        if let Some(item) = release.deployment_items.iter_mut().find(|it| it.name == item_name) {
+            item.finish_run(exit_code(&result));
+            match &result {
+                Ok(()) => item.status = ReleaseStatus::RolledBack,
+                Err(e) => {
+                    item.status = match e {
+                        ItemError::Cancelled => ReleaseStatus::Cancelled,
+                        _ => ReleaseStatus::Error,
+                    };
+                    item.error = Some(format!("Rollback failed: {}", e));
+                }
+            }
+        }
+    });
+    
+    match updated {
+        Ok(Some(release)) => {
+            if let Some(item) = release.deployment_items.iter().find(|it| it.name == item_name) {
+                broadcast_item_update(release.id.to_string(), item);
+            }
+            if result.is_ok() {
+                inventory::record_rollback(&db, &release, &item_name);
+            }
+        }
+        Ok(None) => warn!("Release {} was removed while item {} was rolled back", release_id, item_name),
+        Err(e) => error!("Failed to save release after rolling back item {}: {}", item_name, e),
+    }
+    
+    result
+}
+
+// A single run of a deployment item
+struct ItemRun {
+    db: web::Data<SledStorage>,
+    release_id: Uuid,
+    item_name: String,
+    env_name: String,
+    run: u32,
+    attempt: u32,
+    max_attempts: u32,
+    timeout: Duration,
+    control: ItemControl,
+    rollback: bool, // Run the item's rollback command instead of deploying it
+    dry_run: bool, // Rehearse the deployment, the script gets BLEND_DRY_RUN=1
+    target: Option<String>, // Rollout target of the run, None when the item runs once
+}
+
+impl ItemRun {
+    // Store a line in the item's log history, lines of a target's run start with the target
+    fn log(&self, stream: LogStream, line: &str) {
+        let line = with_target(self.target.as_deref(), line);
+        store_log_line(&self.db, &self.release_id, log_line(&self.item_name, stream, &line, self.run, self.dry_run));
+    }
+}
+
+// Prefix a message about a run with its rollout target, if it has one
+fn with_target<'l>(target: Option<&str>, line: &'l str) -> std::borrow::Cow<'l, str> {
+    match target {
+        Some(target) => format!("[{}] {}", target, line).into(),
+        None => line.into(),
+    }
+}
+
+// Run one deployment item script and store its outcome on the release
+async fn run_and_record_item(
+    db: web::Data<SledStorage>,
+    release_id: Uuid,
+    item_name: String,
+    env_name: String,
+    control: ItemControl,
+) -> Result<(), ItemError> {
+    // Item types with a concurrency limit wait for a free slot first. When cancelled
+    // while waiting, the first attempt below ends right away.
+    let _slot = acquire_item_slot(&item_name, &control).await;
+    
+    let policy = config::deployment().retry_policy(&item_name);
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempt = 1;
+    
+    let result = loop {
+        // Every attempt is a new run of the item so its log lines can be told apart
+        let mut run = 0;
+        let mut timeout_override = None;
+        let started = db.update_release(&release_id, |release| {
+            timeout_override = release.item_timeouts.get(&item_name).copied();
+            if let Some(item) = release.deployment_items.iter_mut().find(|it| it.name == item_name) {
+                item.start_run();
+                item.attempt = attempt;
+                item.max_attempts = max_attempts;
+                run = item.runs;
+            }
+        }).map_err(|e| format!("Failed to start run of {}: {}", item_name, e))?;
+        
+        if let Some(item) = started.as_ref().and_then(|release| release.deployment_items.iter().find(|it| it.name == item_name)) {
+            broadcast_item_update(release_id.to_string(), item);
+        }
+        
+        // A timeout set on the release wins over the configured one
+        let timeout = timeout_override
+            .map(Duration::from_secs)
+            .unwrap_or_else(|| config::deployment().item_timeout(&item_name, &env_name));
+        
+        let item_run = ItemRun {
+            db: db.clone(),
+            release_id,
+            item_name: item_name.clone(),
+            env_name: env_name.clone(),
+            run,
+            attempt,
+            max_attempts,
+            timeout,
+            control: control.clone(),
+            rollback: false,
+            dry_run: false,
+            target: None,
+        };
+        
+        let result = rollout::run(&item_run).await;
+        record_run_end(&db, &release_id, &item_name, &result);
+        
+        let retryable = match &result {
+            Ok(()) => false,
+            Err(ItemError::ExitCode { code, .. }) => policy.is_retryable_exit_code(*code),
+            Err(ItemError::TimedOut(_)) => policy.retries_timeouts(),
+            Err(_) => false,
+        };
+        if !retryable || attempt >= max_attempts {
+            break result;
+        }
+        
+        attempt += 1;
+        let delay = policy.backoff(attempt);
+        let message = format!("Attempt {}/{} failed: {}. Retrying in {}s",
+            attempt - 1, max_attempts, result.unwrap_err(), delay.as_secs());
+        warn!("Deployment item {} of release {}: {}", item_name, release_id, message);
+        item_run.log(LogStream::System, &message);
+        broadcast_release_update(
+            release_id.to_string(),
+            "InProgress".to_string(),
+            0.0,
+            Some(format!("[{}] {}", item_name, message))
+        );
+        
+        // Cancelling while waiting for the next attempt ends the item right away
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = control.cancelled() => {
+                item_run.log(LogStream::System, "Cancelled while waiting to retry");
+                break Err(ItemError::Cancelled);
+            }
+        }
+    };
+    
+    // Update the item in place so concurrent items don't overwrite each other
+    let updated = db.update_release(&release_id, |updated_release| {
+        let release_status = updated_release.status.clone();
+        
+        // Find the right deployment item
+        if let Some(deployment_item) = updated_release.deployment_items.iter_mut().find(|it| it.name == item_name) {
+            if let Err(e) = &result {
+                deployment_item.status = match e {
+                    ItemError::Cancelled => ReleaseStatus::Cancelled,
+                    ItemError::TimedOut(_) |
+                    ItemError::ExitCode { .. } |
+                    ItemError::Failed(_) => ReleaseStatus::Error,
+                };
+                deployment_item.error = Some(e.to_string());
+            } else {
+                // Set next status based on current status
+                deployment_item.status = match release_status {
+                    ReleaseStatus::DeployingToStaging => ReleaseStatus::ReadyToTestInStaging,
+                    ReleaseStatus::DeployingToProduction => ReleaseStatus::ReadyToTestInProduction,
+                    _ => deployment_item.status.clone(),
+                };
+            }
+        }
+        
+        // Update the overall release progress but NOT the status yet
+        // We'll only update the overall status when all items are done
+        updated_release.progress = updated_release.calculate_progress();
+    });
+    
+    match updated {
+        Ok(Some(updated_release)) => {
+            if let Some(item) = updated_release.deployment_items.iter().find(|it| it.name == item_name) {
+                broadcast_item_update(updated_release.id.to_string(), item);
+            }
+            
+            // Broadcast progress update (but with InProgress status)
+            broadcast_release_update(
+                updated_release.id.to_string(),
+                "InProgress".to_string(), 
+                updated_release.progress, 
+                Some(format!("Item [{}] completed. Updated overall progress to {:.1}%", item_name, updated_release.progress))
+            );
+        }
+        Ok(None) => warn!("Release {} was removed while item {} was running", release_id, item_name),
+        Err(e) => error!("Failed to save release after deployment item completion: {}", e),
+    }
+    
+    result
+}
+
+// Store a line of item output in the release history
+fn append_log_line(
+    db: &SledStorage,
+    release_id: &Uuid,
+    item_name: &str,
+    stream: LogStream,
+    line: &str,
+    run: u32,
+) {
+    store_log_line(db, release_id, log_line(item_name, stream, line, run, false));
+}
+
+fn log_line(item_name: &str, stream: LogStream, line: &str, run: u32, dry_run: bool) -> LogLine {
+    LogLine {
+        timestamp: Utc::now(),
+        stream,
+        item_name: item_name.to_string(),
+        line: line.to_string(),
+        run,
+        dry_run,
+    }
+}
+
+fn store_log_line(db: &SledStorage, release_id: &Uuid, log_line: LogLine) {
+    if let Err(e) = db.append_log_line(release_id, &log_line, *MAX_LOG_LINES) {
+        error!("Failed to store log line for item {} of release {}: {}", log_line.item_name, release_id, e);
+    }
+}
+
+// Recompute the overall release status once every deployment item has finished
+async fn finalize_release(release_id: Uuid, db: web::Data<SledStorage>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Scripts exiting with 0 doesn't mean what they deployed works, so the health
+    // checks have to pass before the release is ready to test
+    if let Some(release) = db.get_release(&release_id)? {
+        let deployed = matches!(release.status, ReleaseStatus::DeployingToStaging | ReleaseStatus::DeployingToProduction)
+            && !release.deployment_items.is_empty()
+            && release.deployment_items.iter().all(|item| item_succeeded(&item.status));
+        if deployed {
+            if let Err(failure) = health::run_checks(&db, &release, &release.last_deployment_environment()).await {
+                return fail_health_checks(&db, release_id, &failure);
+            }
+        }
+    }
+    
+    // After all tasks complete, update the most up-to-date release state in place
+    let mut outcome = None;
+    let release = match db.update_release(&release_id, |release| {
+        outcome = release.apply_deployment_outcome();
+    })? {
+        Some(release) => release,
+        None => {
+            error!("Release {} not found after deployment", release_id);
+            return Err("Release not found after deployment".into());
+        }
+    };
+
+    let final_status = match outcome {
+        Some(status) => status,
+        None => {
+            info!("Not all deployment items for release {} have completed yet, waiting...", release_id);
+            return Ok(());
+        }
+    };
+
+    // Whatever deployed successfully is what the environment runs now
+    inventory::record_deployment(&db, &release);
+
+    // Broadcast final status
+    let (completion_message, app_log) = match final_status {
+        ReleaseStatus::Error => {
+            info!("Release {} has deployment errors, setting status to Error", release_id);
+            (format!("Deployment failed for {}. Check details for more information.", release.title),
+             ("error", format!("Deployment failed: {}", release.title)))
+        }
+        ReleaseStatus::Cancelled => {
+            info!("Release {} was cancelled", release_id);
+            (format!("Deployment cancelled for {}", release.title),
+             ("warn", format!("Deployment cancelled: {}", release.title)))
+        }
+        _ => {
+            info!("All deployment items for release {} completed successfully, status set to {:?}", 
+                   release_id, release.status);
+            (format!("Deployment process complete for {}", release.title),
+             ("info", format!("Deployment completed successfully: {}", release.title)))
+        }
+    };
+
+    broadcast_release_update(
+        release.id.to_string(),
+        format!("{:?}", release.status), 
+        release.progress, 
+        Some(completion_message)
+    );
+    
+    // Add app log for deployment completion
+    broadcast_app_log(app_log.0, &app_log.1);
+    
+    info!("Release process completed for {}: {}", release.id, release.title);
+    Ok(())
+}
+
+// Put a release whose items deployed but whose health checks failed in Error
+fn fail_health_checks(db: &SledStorage, release_id: Uuid, failure: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut failed = false;
+    let release = db.update_release(&release_id, |release| {
+        // Cancelled while the checks ran
+        failed = matches!(release.status, ReleaseStatus::DeployingToStaging | ReleaseStatus::DeployingToProduction);
+        if failed {
+            release.status = ReleaseStatus::Error;
+            release.progress = release.calculate_progress();
+        }
+    })?;
+    
+    if let Some(release) = release.filter(|_| failed) {
+        error!("Health checks of release {} failed: {}", release_id, failure);
+        broadcast_release_update(
+            release.id.to_string(),
+            format!("{:?}", release.status),
+            release.progress,
+            Some(format!("Health checks FAILED for {}: {}", release.title, failure))
+        );
+        broadcast_app_log("error", &format!("Health checks failed: {}", release.title));
+    }
+    Ok(())
+}
+
+// Exit code of a finished run, None if the script was stopped or never started
+fn exit_code(result: &Result<(), ItemError>) -> Option<i32> {
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => e.exit_code(),
+    }
+}
+
+// Store the end of an item's run so its timing survives retries
+fn record_run_end(db: &SledStorage, release_id: &Uuid, item_name: &str, result: &Result<(), ItemError>) {
+    let updated = db.update_release(release_id, |release| {
+        if let Some(item) = release.deployment_items.iter_mut().find(|it| it.name == item_name) {
+            item.finish_run(exit_code(result));
+        }
+    });
+    
+    match updated {
+        Ok(Some(release)) => {
+            if let Some(item) = release.deployment_items.iter().find(|it| it.name == item_name) {
+                broadcast_item_update(release.id.to_string(), item);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => error!("Failed to record the end of the run of {} for release {}: {}", item_name, release_id, e),
+    }
+}
+
+// Store an event reported by an item's script and broadcast it
+fn record_item_event(db: &SledStorage, release_id: &Uuid, item_name: &str, kind: ItemEventKind, run: u32) {
+    let event = ItemEvent {
+        timestamp: Utc::now(),
+        run,
+        kind,
+    };
+    
+    let updated = db.update_release(release_id, |release| {
+        if let Some(item) = release.deployment_items.iter_mut().find(|it| it.name == item_name) {
+            item.record_event(event.clone());
+        }
+    });
+    
+    let release = match updated {
+        Ok(Some(release)) => release,
+        Ok(None) => return,
+        Err(e) => {
+            error!("Failed to store event of {} for release {}: {}", item_name, release_id, e);
+            return;
+        }
+    };
+    
+    // Warnings and confirmation requests need someone to look at them
+    match &event.kind {
+        ItemEventKind::Warning { message } => {
+            warn!("Deployment item {} of release {} warned: {}", item_name, release_id, message);
+            broadcast_app_log("warn", &format!("{} [{}]: {}", release.title, item_name, message));
+        }
+        ItemEventKind::ConfirmationRequested { prompt } => {
+            info!("Deployment item {} of release {} is waiting for confirmation: {}", item_name, release_id, prompt);
+            broadcast_app_log("warn", &format!("{} [{}] is waiting for confirmation: {}", release.title, item_name, prompt));
+        }
+        ItemEventKind::ManualStepRequested { instructions } => {
+            info!("Deployment item {} of release {} is waiting for a manual step: {}", item_name, release_id, instructions);
+            broadcast_app_log("warn", &format!("{} [{}] is waiting for a manual step: {}", release.title, item_name, instructions));
+        }
+        _ => {}
+    }
+    
+    broadcast_item_event(release.id.to_string(), item_name, &event);
+}
+
+// Store the progress reported by an item's script and return the release's overall progress.
+// An item rolled out to targets progresses by the average over its targets.
+fn record_item_progress(db: &SledStorage, release_id: &Uuid, item_name: &str, target: Option<&str>, progress: f32) -> Option<f32> {
+    let updated = db.update_release(release_id, |release| {
+        if let Some(item) = release.deployment_items.iter_mut().find(|it| it.name == item_name) {
+            match target.and_then(|target| item.target_mut(target)) {
+                Some(target) => {
+                    target.progress = progress.clamp(0.0, 100.0);
+                    item.progress = item.target_progress();
+                }
+                None => item.progress = progress.clamp(0.0, 100.0),
+            }
+        }
+        release.progress = release.calculate_progress();
+    });
+    
+    match updated {
+        Ok(Some(release)) => {
+            if let Some(item) = release.deployment_items.iter().find(|it| it.name == item_name) {
+                broadcast_item_update(release.id.to_string(), item);
+            }
+            Some(release.progress)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            error!("Failed to store progress of {} for release {}: {}", item_name, release_id, e);
+            None
+        }
+    }
+}
+
+// Where a deployment item's script output goes: the item's log, the WebSocket and,
+// for protocol markers, the item's progress and events
+#[derive(Clone)]
+struct OutputSink {
+    db: web::Data<SledStorage>,
+    release_id: Uuid,
+    item_name: String,
+    run: u32,
+    current_progress: Arc<Mutex<f32>>, // Overall release progress, for the broadcasts
+    script_env: Arc<ScriptEnvironment>,
+    control: ItemControl, // Control of the script, the target's own during a rollout
+    dry_run: bool, // Dry runs answer confirmations themselves
+    target: Option<String>, // Rollout target the script runs on
+}
+
+impl OutputSink {
+    fn progress(&self) -> f32 {
+        self.current_progress.lock().map(|current| *current).unwrap_or(0.0)
     }
     
-    // Save the updated release
-    db.save_release(&release)?;
-    
-    // Add more detailed logging
-    info!("STARTING DEPLOYMENT: Release {} - {} is being deployed to {}", 
-          release_id, release.title, env_name);
-    broadcast_app_log("info", &format!("Starting deployment: {} to {}", release.title, env_name));
-    
-    // Broadcast status update
-    broadcast_release_update(
-        release.id.to_string(),
-        format!("{:?}", release.status), 
-        0.0, 
-        Some(format!("Starting deployment process for {}", release.title))
-    );
-    
-    // Process each deployment item in parallel
-    let mut handles = vec![];
+    fn target_name(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
     
-    // Then process each item
-    for item in release.deployment_items.iter() {
-        let item_name = item.name.clone();
-        let release_id_str = release.id.to_string();
-        let db_clone = db.clone();
-        let env_name = env_name.to_string();
+    fn stdout_line(&self, line: &str) {
+        // Secrets never leave the sink
+        let line = self.script_env.redact(line);
+        let item_name = self.item_name.as_str();
+        let shown = with_target(self.target_name(), &line);
         
-        // Run in a separate task
-        let handle = tokio::spawn(async move {
-            let result = process_deployment_item(&item_name, &env_name, release_id_str.clone()).await;
+        // Log the output line
+        info!("{}", shown);
+        
+        // A dry run only keeps the output, the item's progress and events stay untouched
+        if self.dry_run {
+            self.store(LogStream::Stdout, &line);
+            broadcast_release_update(
+                self.release_id.to_string(),
+                "InProgress".to_string(),
+                self.progress(),
+                Some(format!("[{}] [dry run] {}", item_name, line))
+            );
             
-            // Get the release again to avoid conflicts
-            let db_ref = db_clone.as_ref();
-            if let Ok(Some(mut updated_release)) = db_ref.get_release(&release_id) {
-                // Find the right deployment item
-                if let Some(deployment_item) = updated_release.deployment_items.iter_mut().find(|it| it.name == item_name) {
-                    if let Err(e) = &result {
-                        deployment_item.status = ReleaseStatus::Error;
-                        deployment_item.error = Some(e.to_string());
-                    } else {
-                        // Set next status based on current status
-                        deployment_item.status = match updated_release.status {
-                            ReleaseStatus::DeployingToStaging => ReleaseStatus::ReadyToTestInStaging,
-                            ReleaseStatus::DeployingToProduction => ReleaseStatus::ReadyToTestInProduction,
-                            _ => deployment_item.status.clone(),
-                        };
-                    }
-                    
-                    // Calculate overall progress based on all items
-                    let total_items = updated_release.deployment_items.len() as f32;
-                    let completed_progress: f32 = updated_release.deployment_items.iter()
-                        .map(|item| {
-                            match item.status {
-                                ReleaseStatus::ReadyToTestInStaging | 
-                                ReleaseStatus::ReadyToTestInProduction => 100.0,
-                                ReleaseStatus::Error => 0.0,
-                                _ => 0.0 // For items still in progress
-                            }
-                        })
-                        .sum::<f32>() / total_items;
-                    
-                    // Update the overall release progress but NOT the status yet
-                    // We'll only update the overall status when all items are done
-                    updated_release.progress = completed_progress;
-                    
-                    // Save updated release with the new progress
-                    if let Err(e) = db_ref.save_release(&updated_release) {
-                        error!("Failed to save release after deployment item completion: {}", e);
-                    }
-                    
-                    // Broadcast progress update (but with InProgress status)
-                    broadcast_release_update(
-                        updated_release.id.to_string(),
-                        "InProgress".to_string(), 
-                        updated_release.progress, 
-                        Some(format!("Item [{}] completed. Updated overall progress to {:.1}%", item_name, updated_release.progress))
-                    );
+            if let Some(ScriptMarker::Event(ItemEventKind::ConfirmationRequested { prompt })) = protocol::parse_marker(&line) {
+                let control = self.control.clone();
+                let db = self.db.clone();
+                let release_id = self.release_id;
+                let item_name = self.item_name.clone();
+                let run = self.run;
+                tokio::spawn(async move {
+                    let note = match control.answer(true).await {
+                        Ok(()) => format!("Dry run answered \"{}\" with yes", prompt),
+                        Err(e) => format!("Dry run failed to answer \"{}\": {}", prompt, e),
+                    };
+                    store_log_line(&db, &release_id, log_line(&item_name, LogStream::System, &note, run, true));
+                });
+            }
+            return;
+        }
+        
+        // Check if this is a progress line or another protocol marker
+        let marker = protocol::parse_marker(&line);
+        if let Some(ScriptMarker::Progress(progress)) = marker {
+            // Store the item's progress and broadcast the release's new total
+            let release_progress = record_item_progress(&self.db, &self.release_id, item_name, self.target_name(), progress);
+            if let Some(release_progress) = release_progress {
+                if let Ok(mut current) = self.current_progress.lock() {
+                    *current = release_progress;
                 }
+                
+                broadcast_release_update(
+                    self.release_id.to_string(),
+                    "InProgress".to_string(), 
+                    release_progress, 
+                    Some(format!("Item [{}] progress: {:.1}%", with_target(self.target_name(), item_name), progress))
+                );
             }
+        } else {
+            // It's a regular log line, store and broadcast it
+            self.store(LogStream::Stdout, &line);
+            broadcast_release_update(
+                self.release_id.to_string(),
+                "InProgress".to_string(), 
+                self.progress(), 
+                Some(format!("[{}] {}", item_name, shown))
+            );
             
-            result
-        });
+            // Markers for steps, warnings, outputs, ... are kept in the log as well
+            if let Some(ScriptMarker::Event(kind)) = marker {
+                self.record_event(kind);
+            }
+        }
+    }
+    
+    // Stderr lines are treated as errors
+    fn stderr_line(&self, line: &str) {
+        let line = self.script_env.redact(line);
+        
+        let shown = with_target(self.target_name(), &line);
+        
+        // Log the error
+        warn!("STDERR: {}", shown);
         
-        handles.push(handle);
+        // Mark line as coming from stderr, store and broadcast. Dry runs never put
+        // the release in error.
+        self.store(LogStream::Stderr, &line);
+        let (status, err_line) = if self.dry_run {
+            ("InProgress", format!("[{}] [dry run] [stderr] {}", self.item_name, shown))
+        } else {
+            ("Error", format!("[{}] [stderr] {}", self.item_name, shown))
+        };
+        broadcast_release_update(
+            self.release_id.to_string(),
+            status.to_string(), 
+            self.progress(), 
+            Some(err_line)
+        );
     }
     
-    // Wait for all deployment items to complete
-    for handle in handles {
-        if let Err(e) = handle.await {
-            error!("Error joining task: {}", e);
-        }
+    fn store(&self, stream: LogStream, line: &str) {
+        let line = with_target(self.target_name(), line);
+        store_log_line(&self.db, &self.release_id, log_line(&self.item_name, stream, &line, self.run, self.dry_run));
     }
     
-    // After all tasks complete, get the most up-to-date release state
-    let mut release = match db.get_release(&release_id)? {
-        Some(release) => release,
-        None => {
-            error!("Release {} not found after deployment", release_id);
-            return Err("Release not found after deployment".into());
-        }
-    };
-
-    // Check if all deployment items have completed
-    let all_completed = release.deployment_items.iter().all(|item| {
-        match item.status {
-            ReleaseStatus::ReadyToTestInStaging | 
-            ReleaseStatus::ReadyToTestInProduction | 
-            ReleaseStatus::Error => true,
-            _ => false
+    // Store an event the script reported. Events of a rollout target name the target,
+    // and its confirmation request waits for the targets that asked before it.
+    fn record_event(&self, kind: ItemEventKind) {
+        if matches!(kind, ItemEventKind::ConfirmationRequested { .. }) {
+            self.control.await_answer();
         }
-    });
-
-    if !all_completed {
-        info!("Not all deployment items for release {} have completed yet, waiting...", release_id);
-        return Ok(());
+        
+        let target = match &self.target {
+            Some(target) => target,
+            None => return record_item_event(&self.db, &self.release_id, &self.item_name, kind, self.run),
+        };
+        
+        let kind = match kind {
+            ItemEventKind::Step { name } => ItemEventKind::Step { name: format!("[{}] {}", target, name) },
+            ItemEventKind::Warning { message } => ItemEventKind::Warning { message: format!("[{}] {}", target, message) },
+            ItemEventKind::ConfirmationRequested { prompt } => {
+                let prompt = format!("[{}] {}", target, prompt);
+                if !self.control.ask(&prompt) {
+                    return;
+                }
+                ItemEventKind::ConfirmationRequested { prompt }
+            }
+            kind => kind,
+        };
+        record_item_event(&self.db, &self.release_id, &self.item_name, kind, self.run);
     }
+}
 
-    // Check all deployment items for errors
-    let has_errors = release.deployment_items.iter().any(|item| matches!(item.status, ReleaseStatus::Error));
+// Name of a release's client, its id if the client can't be found
+fn client_name(db: &SledStorage, client_id: &str) -> String {
+    Uuid::parse_str(client_id).ok()
+        .and_then(|id| db.get_client(&id).ok().flatten())
+        .map(|client| client.name)
+        .unwrap_or_else(|| client_id.to_string())
+}
 
-    // Update overall status ONLY when all deployment items have completed
-    if has_errors {
-        info!("Release {} has deployment errors, setting status to Error", release_id);
-        release.status = ReleaseStatus::Error;
-        release.progress = 0.0;
+// Process a single deployment item using the appropriate script
+async fn process_deployment_item(item: &ItemRun) -> Result<(), ItemError> {
+    let item_name = item.item_name.as_str();
+    let env_name = item.env_name.as_str();
+    let release_uuid = item.release_id;
+    let release_id = release_uuid.to_string();
+    let run = item.run;
+    let db = &item.db;
+    
+    // Look up the command for this item type in the registry
+    let item_config = config::deployment().item(item_name)
+        .ok_or_else(|| format!("Unknown deployment item type: {}", item_name))?;
+    
+    // A rollback runs the item's rollback command with the same working directory and env
+    let action = if item.rollback {
+        "rollback"
+    } else if item.dry_run {
+        "dry run"
+    } else {
+        "deployment"
+    };
+    let (command_name, args) = if item.rollback {
+        let rollback = item_config.rollback.as_ref()
+            .ok_or_else(|| format!("No rollback command configured for {}", item_name))?;
+        (&rollback.command, rollback.args_for(env_name))
+    } else {
+        (&item_config.command, item_config.args_for(env_name))
+    };
+    let target = item.target.as_deref();
+    let args = match target {
+        Some(target) => config::expand_target(args, target),
+        None => args,
+    };
+    // What the messages below call the run, e.g. "app on app-1"
+    let label = match target {
+        Some(target) => format!("{} on {}", item_name, target),
+        None => item_name.to_string(),
+    };
+    
+    // Scripts get the release's context and the client's secrets as variables
+    let release = db.get_release(&release_uuid)
+        .map_err(|e| format!("Failed to get release {}: {}", release_id, e))?
+        .ok_or_else(|| format!("Release {} not found", release_id))?;
+    let client_name = client_name(db, &release.client_id);
+    let script_env = Arc::new(ScriptEnvironment::new(&RunContext {
+        release: &release,
+        client_name: &client_name,
+        item_name,
+        env_name,
+        run,
+        attempt: item.attempt,
+        rollback: item.rollback,
+        dry_run: item.dry_run,
+        target,
+    }, &item_config.env));
+    
+    // The item may have been cancelled before its script got started
+    if item.control.is_cancelled() {
+        item.log(LogStream::System, "Cancelled before the script was started");
+        return Err(ItemError::Cancelled);
+    }
+    
+    // Manual steps wait for a person instead of running a script
+    if let Some(manual) = &item_config.manual {
+        return run_manual_step(item, &manual.instructions, &label, release.progress).await;
+    }
+    
+    info!("Running script for {} {} in {}: {}", label, action, env_name, command_name);
+    if item.rollback {
+        item.log(LogStream::System, &format!("Rollback run {} started in {} (timeout {}s)",
+            run, env_name, item.timeout.as_secs()));
+    } else if item.dry_run {
+        item.log(LogStream::System, &format!("Dry run {} started in {} with BLEND_DRY_RUN=1 (timeout {}s)",
+            run, env_name, item.timeout.as_secs()));
     } else {
-        // Set next status based on current status
-        release.status = match release.status {
-            ReleaseStatus::DeployingToStaging => ReleaseStatus::ReadyToTestInStaging,
-            ReleaseStatus::DeployingToProduction => ReleaseStatus::ReadyToTestInProduction,
-            _ => release.status.clone(),
+        item.log(LogStream::System, &format!("Run {} started in {} (attempt {}/{}, timeout {}s)",
+            run, env_name, item.attempt, item.max_attempts, item.timeout.as_secs()));
+    }
+    
+    let sink = OutputSink {
+        db: db.clone(),
+        release_id: release_uuid,
+        item_name: item_name.to_string(),
+        run,
+        current_progress: Arc::new(Mutex::new(release.progress)),
+        script_env: script_env.clone(),
+        control: item.control.clone(),
+        dry_run: item.dry_run,
+        target: item.target.clone(),
+    };
+    
+    // Remote items run on a runner inside the client's network, everything else here
+    let exit_code = if item_config.remote {
+        let job = RunnerJob {
+            id: Uuid::new_v4(),
+            release_id: release_uuid,
+            item_name: item_name.to_string(),
+            env_name: env_name.to_string(),
+            command: command_name.clone(),
+            args,
+            working_dir: item_config.working_dir.clone(),
+            env: script_env.variables.clone(),
+            run,
         };
-        release.progress = 100.0;
-        info!("All deployment items for release {} completed successfully, status set to {:?}", 
-               release_id, release.status);
-               
-        // Update all deployment items to match the release status unless they're in error state
-        for item in release.deployment_items.iter_mut() {
-            if item.status != ReleaseStatus::Error {
-                item.status = release.status.clone();
-            }
+        run_on_runner(item, &sink, job, &release.client_id, &client_name).await
+    } else {
+        run_script(item, &sink, command_name, args, item_config.working_dir.as_deref(), &item_config.sandbox).await
+    };
+    
+    let exit_code = match exit_code {
+        Ok(exit_code) => exit_code,
+        Err(reason) => {
+            let message = match &reason {
+                ItemError::TimedOut(_) => format!("{} {} {}", label, action, reason),
+                ItemError::Cancelled => format!("{} {} was cancelled", label, action),
+                _ => reason.to_string(),
+            };
+            
+            warn!("{}", message);
+            item.log(LogStream::System, &message);
+            broadcast_release_update(
+                release_id.clone(),
+                "InProgress".to_string(),
+                sink.progress(),
+                Some(format!("[{}] {}", item_name, message))
+            );
+            
+            return Err(reason);
         }
-    }
-
-    // Save final status
-    db.save_release(&release)?;
-
-    // Broadcast final status
-    let status_str = match release.status {
-        ReleaseStatus::ReadyToTestInStaging => "ReadyToTestInStaging".to_string(),
-        ReleaseStatus::ReadyToTestInProduction => "ReadyToTestInProduction".to_string(),
-        ReleaseStatus::Error => "Error".to_string(),
-        _ => format!("{:?}", release.status)
     };
+    
+    let release_progress = sink.progress();
+    let error_status = if item.dry_run { "InProgress" } else { "Error" };
+        
+    if exit_code != 0 {
+        let error_message = format!("{} {} failed with exit code: {}", 
+            label, action, exit_code);
+            
+        // Log the error
+        error!("{}", error_message);
+        item.log(LogStream::System, &error_message);
+        
+        // Send detailed error information
+        broadcast_release_update(
+            release_id.clone(),
+            error_status.to_string(), 
+            release_progress, 
+            Some(format!("[{}] [ERROR] {}", item_name, error_message))
+        );
+        
+        // Also send a more specific error message to help with debugging
+        let detailed_error = format!("Item [{}] failed. Exit code: {}. Check logs for details.", 
+            label, exit_code);
+        
+        broadcast_release_update(
+            release_id.clone(),
+            error_status.to_string(), 
+            release_progress, 
+            Some(detailed_error)
+        );
+        
+        return Err(ItemError::ExitCode { code: exit_code, message: error_message });
+    }
 
-    let completion_message = if has_errors {
-        format!("Deployment failed for {}. Check details for more information.", release.title)
+    if item.rollback {
+        item.log(LogStream::System, &format!("Rollback run {} completed successfully", run));
+    } else if item.dry_run {
+        item.log(LogStream::System, &format!("Dry run {} completed successfully", run));
     } else {
-        format!("Deployment process complete for {}", release.title)
-    };
+        item.log(LogStream::System, &format!("Run {} completed successfully", run));
+    }
 
     broadcast_release_update(
-        release.id.to_string(),
-        status_str, 
-        release.progress, 
-        Some(completion_message)
+        release_id.clone(),
+        "ItemComplete".to_string(), // Using a special status to indicate this is just an item completion, not the entire release
+        release_progress,
+        Some(format!("Item [{}] {} in {} completed successfully", label, action, env_name))
     );
     
-    // Add app log for deployment completion
-    if has_errors {
-        broadcast_app_log("error", &format!("Deployment failed: {}", release.title));
-    } else {
-        broadcast_app_log("info", &format!("Deployment completed successfully: {}", release.title));
-    }
-    
-    info!("Release process completed for {}: {}", release.id, release.title);
     Ok(())
 }
 
-// Utilities to calculate overall progress during deployment
-fn calculate_item_progress(item_name: &str, current_progress: f32, total_items: usize) -> f32 {
-    // Each item contributes equally to the overall progress
-    current_progress / (total_items as f32) 
-}
-
-fn update_release_progress(release: &mut Release) -> f32 {
-    let total_items = release.deployment_items.len();
-    if total_items == 0 {
-        return 0.0;
+// Pause on a manual step until someone marks it done or failed. It has no timeout, the
+// release waits as long as it takes or until it's cancelled. Dry runs only show the
+// instructions.
+async fn run_manual_step(item: &ItemRun, instructions: &str, label: &str, progress: f32) -> Result<(), ItemError> {
+    let release_id = item.release_id.to_string();
+    
+    if item.dry_run {
+        item.log(LogStream::System, &format!("Dry run {} skipped manual step: {}", item.run, instructions));
+        return Ok(());
     }
     
-    // Sum the progress from each item
-    let total_progress: f32 = release.deployment_items.iter()
-        .map(|item| {
-            match item.status {
-                ReleaseStatus::ReadyToTestInStaging | 
-                ReleaseStatus::ReadyToTestInProduction | 
-                ReleaseStatus::ClearedInStaging |
-                ReleaseStatus::ClearedInProduction => 100.0,
-                ReleaseStatus::Error => 0.0,
-                _ => 0.0 // For items still in progress
-            }
-        })
-        .sum::<f32>() / (total_items as f32);
+    let answer = item.control.wait_for_manual_step();
+    info!("Waiting for manual step {} in {}", label, item.env_name);
+    item.log(LogStream::System, &format!("Run {} in {} waiting for a manual step: {}", item.run, item.env_name, instructions));
+    record_item_event(&item.db, &item.release_id, &item.item_name, ItemEventKind::ManualStepRequested {
+        instructions: instructions.to_string(),
+    }, item.run);
+    broadcast_release_update(
+        release_id.clone(),
+        "InProgress".to_string(),
+        progress,
+        Some(format!("[{}] Waiting for the manual step to be marked done", label))
+    );
     
-    release.progress = total_progress;
-    total_progress
-}
-
-// Process a single deployment item using the appropriate script
-async fn process_deployment_item(item_name: &str, env_name: &str, release_id: String) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // Determine which script to run based on item type
-    let script_path = match item_name {
-        "data" => Path::new(SCRIPTS_DIR).join(DATA_SCRIPT),
-        "solr" => Path::new(SCRIPTS_DIR).join(SOLR_SCRIPT),
-        "app" => Path::new(SCRIPTS_DIR).join(APP_SCRIPT),
-        _ => return Err(format!("Unknown deployment item type: {}", item_name).into()),
+    let answer = tokio::select! {
+        answer = answer => answer.unwrap_or_else(|_| Err(format!("Manual step {} was abandoned", label))),
+        _ = item.control.cancelled() => {
+            item.log(LogStream::System, "Cancelled while waiting for the manual step");
+            return Err(ItemError::Cancelled);
+        }
     };
     
-    // Check if script exists
-    if !script_path.exists() {
-        return Err(format!("Script not found at path: {:?}", script_path).into());
+    match answer {
+        Ok(()) => {
+            item.log(LogStream::System, &format!("Run {} manual step marked done", item.run));
+            broadcast_release_update(
+                release_id,
+                "ItemComplete".to_string(),
+                progress,
+                Some(format!("Item [{}] manual step in {} marked done", label, item.env_name))
+            );
+            Ok(())
+        }
+        Err(message) => {
+            warn!("{}", message);
+            item.log(LogStream::System, &message);
+            broadcast_release_update(
+                release_id,
+                "Error".to_string(),
+                progress,
+                Some(format!("[{}] [ERROR] {}", item.item_name, message))
+            );
+            Err(ItemError::Failed(message))
+        }
+    }
+}
+
+// Commands given as a path are resolved against our own working directory, not
+// the item's, and have to exist. Bare command names are left to the PATH.
+fn resolve_script(command_name: &str) -> Result<PathBuf, String> {
+    let mut script_path = PathBuf::from(command_name);
+    if script_path.components().count() > 1 {
+        if script_path.is_relative() {
+            script_path = std::env::current_dir()
+                .map(|dir| dir.join(&script_path))
+                .unwrap_or(script_path);
+        }
+        
+        if !script_path.exists() {
+            return Err(format!("Script not found at path: {:?}", script_path));
+        }
     }
     
-    info!("Running script for {} deployment in {}: {:?}", item_name, env_name, script_path);
+    Ok(script_path)
+}
+
+// Run an item's script as a child process of the server, returns its exit code
+async fn run_script(
+    item: &ItemRun,
+    sink: &OutputSink,
+    command_name: &str,
+    args: Vec<String>,
+    working_dir: Option<&str>,
+    sandbox: &config::SandboxConfig,
+) -> Result<i32, ItemError> {
+    let item_name = item.item_name.as_str();
+    let script_path = resolve_script(command_name)?;
     
-    // Create command with the script and environment parameter
-    let mut child = Command::new(&script_path)
-        .arg(env_name)
+    // Create command with the script and environment parameter. The script gets its
+    // own process group so cancelling can signal everything it started.
+    let mut command = Command::new(&script_path);
+    sandbox::apply(&mut command, sandbox)
+        .map_err(|e| format!("Failed to start {} script: {}", item_name, e))?;
+    command
+        .args(args)
+        .envs(&sink.script_env.variables)
+        .stdin(Stdio::piped())
         .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(working_dir) = working_dir {
+        command.current_dir(working_dir);
+    }
+    #[cfg(unix)]
+    command.process_group(0);
+    
+    let mut child = command
         .spawn()
         .map_err(|e| format!("Failed to start {} script: {}", item_name, e))?;
-        
-    // Initialize progress tracking
-    let mut current_progress = 0.0;
+    // Kept for the process group, the child forgets its id once it has exited
+    let pid = child.id();
+    
+    // Confirmation requests are answered on the script's stdin
+    item.control.set_stdin(child.stdin.take()).await;
+    
+    let mut readers = Vec::new();
+    
+    // The first limit the script's output complains about, to explain its failure
+    let limit_seen: Arc<Mutex<Option<Limit>>> = Arc::new(Mutex::new(None));
+    let note_limit = {
+        let sandbox = sandbox.clone();
+        let limit_seen = limit_seen.clone();
+        move |line: &str| {
+            if let Some(limit) = sandbox::limit_in_output(&sandbox, line) {
+                if let Ok(mut seen) = limit_seen.lock() {
+                    seen.get_or_insert(limit);
+                }
+            }
+        }
+    };
     
     // Stream stdout
     if let Some(stdout) = child.stdout.take() {
         let mut reader = BufReader::new(stdout).lines();
-        let item_name = item_name.to_string();
-        let release_id = release_id.clone();
+        let sink = sink.clone();
+        let note_limit = note_limit.clone();
         
-        tokio::spawn(async move {
+        readers.push(tokio::spawn(async move {
             while let Ok(Some(line)) = reader.next_line().await {
-                // Log the output line
-                info!("{}", line);
-                
-                // Check if this is a progress line
-                if let Some(captures) = PROGRESS_PATTERN.captures(&line) {
-                    if let Some(progress_str) = captures.get(2) {
-                        if let Ok(progress) = progress_str.as_str().parse::<f32>() {
-                            current_progress = progress;
-                            
-                            // Broadcast progress update for this specific item
-                            broadcast_release_update(
-                                release_id.clone(),
-                                "InProgress".to_string(), 
-                                current_progress, 
-                                Some(format!("Item [{}] progress: {:.1}%", item_name, current_progress))
-                            );
-                        }
-                    }
-                } else {
-                    // It's a regular log line, broadcast it
-                    broadcast_release_update(
-                        release_id.clone(),
-                        "InProgress".to_string(), 
-                        current_progress, 
-                        Some(format!("[{}] {}", item_name, line))
-                    );
-                }
+                note_limit(&line);
+                sink.stdout_line(&line);
                 
                 // Short delay to prevent flooding
                 tokio::time::sleep(Duration::from_millis(50)).await;
             }
-        });
+        }));
     }
     
     // Stream stderr (these will be treated as errors)
     if let Some(stderr) = child.stderr.take() {
         let mut reader = BufReader::new(stderr).lines();
-        let item_name = item_name.to_string();
-        let release_id = release_id.clone();
+        let sink = sink.clone();
         
-        tokio::spawn(async move {
+        readers.push(tokio::spawn(async move {
             while let Ok(Some(line)) = reader.next_line().await {
-                // Log the error
-                warn!("STDERR: {}", line);
-                
-                // Mark line as coming from stderr and broadcast
-                let err_line = format!("[{}] [stderr] {}", item_name, line);
-                broadcast_release_update(
-                    release_id.clone(),
-                    "Error".to_string(), 
-                    current_progress, 
-                    Some(err_line)
-                );
+                note_limit(&line);
+                sink.stderr_line(&line);
                 
                 // Short delay to prevent flooding
                 tokio::time::sleep(Duration::from_millis(50)).await;
             }
-        });
+        }));
     }
     
-    // After getting the output status:
-    let status = child.wait().await
-        .map_err(|e| format!("Failed to wait for {} process: {}", item_name, e))?;
-        
-    if !status.success() {
-        let exit_code = status.code().unwrap_or(-1);
-        let error_message = format!("{} deployment failed with exit code: {}", 
-            item_name, exit_code);
+    // Wait for the script to exit, for the item to be cancelled or for it to time out
+    let status = tokio::select! {
+        status = child.wait() => Ok(status
+            .map_err(|e| format!("Failed to wait for {} process: {}", item_name, e))?),
+        _ = item.control.cancelled() => Err(ItemError::Cancelled),
+        _ = item.control.timed_out(item.timeout) => Err(ItemError::TimedOut(item.timeout)),
+    };
+    
+    item.control.set_stdin(None).await;
+    
+    match status {
+        Ok(status) => {
+            // Make sure every output line is stored before the result is recorded
+            join_output_readers(item, pid, readers).await;
             
-        // Log the error
-        error!("{}", error_message);
-        
-        // Send detailed error information
-        broadcast_release_update(
-            release_id.clone(),
-            "Error".to_string(), 
-            0.0, 
-            Some(format!("[{}] [ERROR] {}", item_name, error_message))
-        );
-        
-        // Also send a more specific error message to help with debugging
-        let detailed_error = format!("Item [{}] failed. Exit code: {}. Check logs for details.", 
-            item_name, exit_code);
-        
-        broadcast_release_update(
-            release_id.clone(),
-            "Error".to_string(), 
-            0.0, 
-            Some(detailed_error)
-        );
-        
-        return Err(error_message.into());
+            // Name the limit a failing script ran into
+            let seen = limit_seen.lock().ok().and_then(|seen| *seen);
+            if let Some(violation) = sandbox::violation(sandbox, &status, seen) {
+                let message = format!("{} script {}", item_name, violation);
+                return Err(match status.code() {
+                    Some(code) => ItemError::ExitCode { code, message },
+                    None => ItemError::Failed(message),
+                });
+            }
+            Ok(status.code().unwrap_or(-1))
+        }
+        Err(reason) => {
+            log_stop_reason(item, &reason);
+            terminate_process_group(&mut child, item_name).await;
+            join_output_readers(item, pid, readers).await;
+            Err(reason)
+        }
     }
+}
 
-    // Announce completion with the correct status for the frontend to understand
-    let status_str = match env_name {
-        "staging" => "ReadyToTestInStaging",
-        "production" => "ReadyToTestInProduction",
-        _ => "Completed",
+// Hand an item's script to a runner and follow it until it exits, returns its exit code
+async fn run_on_runner(
+    item: &ItemRun,
+    sink: &OutputSink,
+    job: RunnerJob,
+    client_id: &str,
+    client_name: &str,
+) -> Result<i32, ItemError> {
+    let job_id = job.id;
+    let mut events = runners::submit(job, client_id, client_name);
+    item.control.set_remote_job(Some(job_id));
+    item.log(LogStream::System, &format!("Waiting for a runner for client {}, environment {} and item {}",
+        client_name, item.env_name, item.item_name));
+    
+    // The timeout includes the time spent waiting for a runner
+    let deadline = item.control.timed_out(item.timeout);
+    tokio::pin!(deadline);
+    
+    let result = loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Some(RunnerEvent::Started(runner_name)) => {
+                    info!("Runner {} picked up {} of release {}", runner_name, item.item_name, item.release_id);
+                    item.log(LogStream::System, &format!("Picked up by runner {}", runner_name));
+                }
+                Some(RunnerEvent::Output(output)) => match output.stream {
+                    LogStream::Stderr => sink.stderr_line(&output.line),
+                    _ => sink.stdout_line(&output.line),
+                },
+                Some(RunnerEvent::Finished(JobResult { exit_code: Some(exit_code), .. })) => break Ok(exit_code),
+                Some(RunnerEvent::Finished(JobResult { error, .. })) => {
+                    break Err(ItemError::Failed(error.unwrap_or_else(|| "The runner did not report an exit code".to_string())));
+                }
+                Some(RunnerEvent::Lost(reason)) => break Err(ItemError::Failed(reason)),
+                None => break Err(ItemError::Failed("The runner job was dropped".to_string())),
+            },
+            _ = item.control.cancelled() => break Err(ItemError::Cancelled),
+            _ = &mut deadline => break Err(ItemError::TimedOut(item.timeout)),
+        }
     };
+    
+    item.control.set_remote_job(None);
+    if let Err(reason @ (ItemError::Cancelled | ItemError::TimedOut(_))) = &result {
+        log_stop_reason(item, reason);
+        runners::cancel(job_id);
+    }
+    result
+}
 
-    broadcast_release_update(
-        release_id.clone(),
-        "ItemComplete".to_string(), // Using a special status to indicate this is just an item completion, not the entire release
-        100.0, // This item is complete
-        Some(format!("Item [{}] deployment to {} completed successfully", item_name, env_name))
-    );
+// Note in the item's log why its script is being stopped
+fn log_stop_reason(item: &ItemRun, reason: &ItemError) {
+    match reason {
+        ItemError::TimedOut(_) => {
+            item.log(LogStream::System, &format!("Timeout of {}s reached, stopping script", item.timeout.as_secs()));
+        }
+        _ => item.log(LogStream::System, "Cancellation requested, stopping script"),
+    }
+}
+
+// Wait for the stdout/stderr reader tasks of a script to drain. Processes the script
+// left running in the background keep its output open, so once the grace period is
+// over they are killed along with the rest of its process group and the readers dropped.
+async fn join_output_readers(item: &ItemRun, pid: Option<u32>, readers: Vec<tokio::task::JoinHandle<()>>) {
+    let item_name = item.item_name.as_str();
+    let abort_handles: Vec<_> = readers.iter().map(|reader| reader.abort_handle()).collect();
     
-    Ok(())
+    let joined = tokio::time::timeout(*CANCEL_GRACE_PERIOD, async {
+        for reader in readers {
+            if let Err(e) = reader.await {
+                error!("Error joining output reader for {}: {}", item_name, e);
+            }
+        }
+    }).await;
+    
+    if joined.is_err() {
+        warn!("Output of the {} script was still open {:?} after it exited", item_name, *CANCEL_GRACE_PERIOD);
+        item.log(LogStream::System, &format!(
+            "Output still open {}s after the script exited, killing the processes it left running",
+            CANCEL_GRACE_PERIOD.as_secs()));
+        
+        #[cfg(unix)]
+        if let Some(pid) = pid {
+            signal_process_group(pid, libc::SIGKILL);
+        }
+        #[cfg(not(unix))]
+        let _ = pid;
+        
+        abort_handles.iter().for_each(|handle| handle.abort());
+    }
+}
+
+// Stop a script and everything it started: SIGTERM to its process group, then
+// SIGKILL for whatever is left once the grace period is over
+pub(crate) async fn terminate_process_group(child: &mut Child, item_name: &str) {
+    #[cfg(unix)]
+    {
+        if let Some(pid) = child.id() {
+            signal_process_group(pid, libc::SIGTERM);
+            
+            if tokio::time::timeout(*CANCEL_GRACE_PERIOD, child.wait()).await.is_err() {
+                warn!("{} script did not exit within {:?} of SIGTERM, killing it", item_name, *CANCEL_GRACE_PERIOD);
+            }
+            
+            // Also clears out children that outlived the script itself
+            signal_process_group(pid, libc::SIGKILL);
+        }
+    }
+    
+    #[cfg(not(unix))]
+    if let Err(e) = child.start_kill() {
+        warn!("Failed to kill {} script: {}", item_name, e);
+    }
+    
+    if let Err(e) = child.wait().await {
+        error!("Failed to wait for stopped {} script: {}", item_name, e);
+    }
+}
+
+#[cfg(unix)]
+fn signal_process_group(pid: u32, signal: libc::c_int) {
+    // The script is the leader of its own process group, so its pid is the group id
+    let result = unsafe { libc::killpg(pid as libc::pid_t, signal) };
+    if result != 0 {
+        let err = std::io::Error::last_os_error();
+        // ESRCH just means the whole group has already exited
+        if err.raw_os_error() != Some(libc::ESRCH) {
+            warn!("Failed to send signal {} to process group {}: {}", signal, pid, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependencies(edges: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        edges.iter()
+            .map(|(name, deps)| (name.to_string(), deps.iter().map(|dep| dep.to_string()).collect()))
+            .collect()
+    }
+
+    // Take every item that is ready right now, like one pass of process_release
+    fn ready_items(
+        pending: &mut Vec<String>,
+        dependencies: &HashMap<String, Vec<String>>,
+        succeeded: &HashMap<String, bool>,
+    ) -> Vec<(String, Option<String>)> {
+        std::iter::from_fn(|| next_ready_item(pending, dependencies, succeeded)).collect()
+    }
+
+    #[test]
+    fn items_start_once_their_prerequisites_succeeded() {
+        let dependencies = dependencies(&[("app", &["data", "solr"]), ("solr", &["data"]), ("data", &[]), ("docs", &[])]);
+        let mut pending = vec!["app".to_string(), "solr".to_string(), "data".to_string(), "docs".to_string()];
+        let mut succeeded = HashMap::new();
+
+        assert_eq!(ready_items(&mut pending, &dependencies, &succeeded),
+            vec![("data".to_string(), None), ("docs".to_string(), None)]);
+
+        succeeded.insert("docs".to_string(), true);
+        assert!(ready_items(&mut pending, &dependencies, &succeeded).is_empty());
+
+        succeeded.insert("data".to_string(), true);
+        assert_eq!(ready_items(&mut pending, &dependencies, &succeeded), vec![("solr".to_string(), None)]);
+
+        succeeded.insert("solr".to_string(), true);
+        assert_eq!(ready_items(&mut pending, &dependencies, &succeeded), vec![("app".to_string(), None)]);
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn items_after_a_failed_prerequisite_are_skipped() {
+        let dependencies = dependencies(&[("app", &["solr"]), ("solr", &["data"]), ("data", &[])]);
+        let mut pending = vec!["app".to_string(), "solr".to_string()];
+        let mut succeeded = HashMap::from([("data".to_string(), false)]);
+
+        // process_release counts a skipped item as not succeeded, so the skip carries on
+        assert_eq!(ready_items(&mut pending, &dependencies, &succeeded),
+            vec![("solr".to_string(), Some("data".to_string()))]);
+        succeeded.insert("solr".to_string(), false);
+
+        assert_eq!(ready_items(&mut pending, &dependencies, &succeeded),
+            vec![("app".to_string(), Some("solr".to_string()))]);
+    }
 }
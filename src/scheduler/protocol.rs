@@ -0,0 +1,114 @@
+use regex::Regex;
+use crate::models::ItemEventKind;
+
+// Marker lines scripts can print to report on their deployment:
+//   [PROGRESS:<item>:<percent>]   progress of the item
+//   [STEP:<name>]                 the step the script is working on
+//   [WARN:<message>]              a warning that doesn't fail the item
+//   [OUTPUT:<key>=<value>]        a result such as the deployed version
+//   [ARTIFACT:<name>=<location>]  something the deployment produced
+//   [CONFIRM:<prompt>]            wait for a human to answer "yes" or "no" on stdin
+lazy_static::lazy_static! {
+    static ref PROGRESS_PATTERN: Regex = Regex::new(r"\[PROGRESS:([A-Za-z0-9_.-]+):(\d+)\]").unwrap();
+    static ref EVENT_PATTERN: Regex = Regex::new(r"^\[(STEP|WARN|OUTPUT|ARTIFACT|CONFIRM):(.+)\]\s*$").unwrap();
+}
+
+pub enum ScriptMarker {
+    Progress(f32),
+    Event(ItemEventKind),
+}
+
+// Parse a line of script output, None for regular output
+pub fn parse_marker(line: &str) -> Option<ScriptMarker> {
+    if let Some(captures) = PROGRESS_PATTERN.captures(line) {
+        return captures.get(2)
+            .and_then(|progress| progress.as_str().parse::<f32>().ok())
+            .map(ScriptMarker::Progress);
+    }
+
+    let captures = EVENT_PATTERN.captures(line.trim_start())?;
+    let payload = captures[2].trim();
+    let kind = match &captures[1] {
+        "STEP" => ItemEventKind::Step { name: payload.to_string() },
+        "WARN" => ItemEventKind::Warning { message: payload.to_string() },
+        "OUTPUT" => {
+            let (key, value) = split_pair(payload)?;
+            ItemEventKind::Output { key, value }
+        }
+        "ARTIFACT" => {
+            let (name, location) = split_pair(payload)?;
+            ItemEventKind::Artifact { name, location }
+        }
+        "CONFIRM" => ItemEventKind::ConfirmationRequested { prompt: payload.to_string() },
+        _ => return None,
+    };
+
+    Some(ScriptMarker::Event(kind))
+}
+
+// Split "key=value" at the first '=', the key can't be empty
+fn split_pair(payload: &str) -> Option<(String, String)> {
+    let (key, value) = payload.split_once('=')?;
+    let key = key.trim();
+    if key.is_empty() {
+        return None;
+    }
+    Some((key.to_string(), value.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(line: &str) -> Option<f32> {
+        match parse_marker(line)? {
+            ScriptMarker::Progress(progress) => Some(progress),
+            ScriptMarker::Event(_) => None,
+        }
+    }
+
+    fn event(line: &str) -> Option<ItemEventKind> {
+        match parse_marker(line)? {
+            ScriptMarker::Event(kind) => Some(kind),
+            ScriptMarker::Progress(_) => None,
+        }
+    }
+
+    #[test]
+    fn progress_markers_report_the_percentage() {
+        assert_eq!(progress("[PROGRESS:data:40] Data deployment progress update"), Some(40.0));
+        assert_eq!(progress("[PROGRESS:solr:100]"), Some(100.0));
+        assert_eq!(progress("[PROGRESS:search-index:25]"), Some(25.0));
+        assert_eq!(progress("[PROGRESS:solr_v2.1:60]"), Some(60.0));
+        assert_eq!(progress("[PROGRESS:cache2:5]"), Some(5.0));
+        assert_eq!(progress("[PROGRESS:data:done]"), None);
+    }
+
+    #[test]
+    fn event_markers_become_item_events() {
+        assert_eq!(event("[STEP:Backup]"), Some(ItemEventKind::Step { name: "Backup".to_string() }));
+        assert_eq!(event("  [WARN: Disk almost full ]"), Some(ItemEventKind::Warning { message: "Disk almost full".to_string() }));
+        assert_eq!(event("[OUTPUT:version=1.5.0]"),
+            Some(ItemEventKind::Output { key: "version".to_string(), value: "1.5.0".to_string() }));
+        assert_eq!(event("[ARTIFACT:backup=s3://backups/data.sql.gz?v=2]"),
+            Some(ItemEventKind::Artifact { name: "backup".to_string(), location: "s3://backups/data.sql.gz?v=2".to_string() }));
+        assert_eq!(event("[CONFIRM:Apply schema migrations to production?]"),
+            Some(ItemEventKind::ConfirmationRequested { prompt: "Apply schema migrations to production?".to_string() }));
+    }
+
+    #[test]
+    fn regular_output_is_not_a_marker() {
+        assert!(parse_marker("[data] Creating database backup...").is_none());
+        assert!(parse_marker("Applying [STEP:Backup] later").is_none());
+        assert!(parse_marker("[STEP:Backup] and more").is_none());
+        assert!(parse_marker("[NOTE:something]").is_none());
+        assert!(parse_marker("[STEP:]").is_none());
+    }
+
+    #[test]
+    fn pairs_need_a_key() {
+        assert!(parse_marker("[OUTPUT:version]").is_none());
+        assert!(parse_marker("[OUTPUT:=1.5.0]").is_none());
+        assert_eq!(event("[OUTPUT:notes=]"), Some(ItemEventKind::Output { key: "notes".to_string(), value: String::new() }));
+    }
+}
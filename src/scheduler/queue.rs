@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap};
+use uuid::Uuid;
+
+// Releases waiting to be processed, ordered by when they are due
+#[derive(Default)]
+pub struct ScheduleQueue {
+    due: BTreeSet<(DateTime<Utc>, Uuid)>,
+    scheduled_at: HashMap<Uuid, DateTime<Utc>>,
+}
+
+impl ScheduleQueue {
+    // Add a release, or move it if it was already queued for another time
+    pub fn upsert(&mut self, release_id: Uuid, scheduled_at: DateTime<Utc>) {
+        self.remove(release_id);
+        self.due.insert((scheduled_at, release_id));
+        self.scheduled_at.insert(release_id, scheduled_at);
+    }
+
+    pub fn remove(&mut self, release_id: Uuid) {
+        if let Some(scheduled_at) = self.scheduled_at.remove(&release_id) {
+            self.due.remove(&(scheduled_at, release_id));
+        }
+    }
+
+    // When the next release is due, if any
+    pub fn next_due_at(&self) -> Option<DateTime<Utc>> {
+        self.due.iter().next().map(|(scheduled_at, _)| *scheduled_at)
+    }
+
+    // Remove and return every release that is due at the given time
+    pub fn pop_due(&mut self, now: DateTime<Utc>) -> Vec<Uuid> {
+        let mut released = Vec::new();
+        while let Some(&(scheduled_at, release_id)) = self.due.iter().next() {
+            if scheduled_at > now {
+                break;
+            }
+            self.remove(release_id);
+            released.push(release_id);
+        }
+        released
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn releases_come_out_in_due_order() {
+        let now = Utc::now();
+        let (first, second, later) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut queue = ScheduleQueue::default();
+        queue.upsert(later, now + Duration::minutes(10));
+        queue.upsert(second, now - Duration::minutes(1));
+        queue.upsert(first, now - Duration::minutes(5));
+
+        assert_eq!(queue.next_due_at(), Some(now - Duration::minutes(5)));
+        assert_eq!(queue.pop_due(now), vec![first, second]);
+        assert_eq!(queue.next_due_at(), Some(now + Duration::minutes(10)));
+        assert!(queue.pop_due(now).is_empty());
+        assert_eq!(queue.pop_due(now + Duration::minutes(10)), vec![later]);
+        assert_eq!(queue.next_due_at(), None);
+    }
+
+    #[test]
+    fn upsert_moves_a_queued_release() {
+        let now = Utc::now();
+        let release_id = Uuid::new_v4();
+        let mut queue = ScheduleQueue::default();
+        queue.upsert(release_id, now - Duration::minutes(1));
+        queue.upsert(release_id, now + Duration::minutes(1));
+
+        assert!(queue.pop_due(now).is_empty());
+        assert_eq!(queue.pop_due(now + Duration::minutes(1)), vec![release_id]);
+    }
+
+    #[test]
+    fn removed_releases_are_not_popped() {
+        let now = Utc::now();
+        let (kept, removed) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut queue = ScheduleQueue::default();
+        queue.upsert(kept, now);
+        queue.upsert(removed, now - Duration::minutes(1));
+        queue.remove(removed);
+        queue.remove(Uuid::new_v4());
+
+        assert_eq!(queue.next_due_at(), Some(now));
+        assert_eq!(queue.pop_due(now), vec![kept]);
+    }
+}
@@ -0,0 +1,159 @@
+use actix_web::web;
+use chrono::{DateTime, Utc};
+use log::{info, warn, error};
+use tokio::time::{interval, Duration};
+use uuid::Uuid;
+use crate::config;
+use crate::models::{ApprovalRequest, RecurringOccurrence, RecurringRelease, Release, ReleaseStatus};
+use crate::storage::SledStorage;
+use crate::websocket::server::{broadcast_app_log, broadcast_approval_update, broadcast_recurring_update};
+use super::schedule_release;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30); // How often due occurrences are looked for
+const MISSED_AFTER: chrono::Duration = chrono::Duration::hours(1); // Older occurrences aren't caught up on
+
+// Turn the occurrences of recurring releases into releases as they come due
+pub fn start(db: web::Data<SledStorage>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut check = interval(CHECK_INTERVAL);
+        loop {
+            check.tick().await;
+
+            match generate_due_releases(&db) {
+                Ok(count) => {
+                    if count > 0 {
+                        info!("Handled {} recurring release occurrences", count);
+                    }
+                }
+                Err(e) => {
+                    error!("Error generating recurring releases: {}", e);
+                    broadcast_app_log("error", &format!("Recurring release error: {}", e));
+                }
+            }
+        }
+    })
+}
+
+// Handle every enabled recurring release whose next occurrence has come. Occurrences
+// missed while blend wasn't running are recorded as skipped.
+fn generate_due_releases(db: &SledStorage) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let now = Utc::now();
+    let mut count = 0;
+
+    for recurring in db.get_all_recurring()? {
+        let scheduled_for = match recurring.next_run_at {
+            Some(next_run_at) if recurring.enabled && next_run_at <= now => next_run_at,
+            _ => continue,
+        };
+
+        let outcome = if now - scheduled_for > MISSED_AFTER {
+            Err("Missed while blend wasn't running".to_string())
+        } else {
+            create_occurrence_release(db, &recurring, scheduled_for)?
+        };
+
+        let occurrence = RecurringOccurrence {
+            scheduled_for,
+            generated_at: now,
+            release_id: outcome.as_ref().ok().copied(),
+            skipped: outcome.err(),
+        };
+        if let Some(reason) = &occurrence.skipped {
+            warn!("Skipped occurrence {} of recurring release {}: {}", scheduled_for, recurring.title, reason);
+        }
+
+        // Missed occurrences collapse into this one, the schedule goes on from now
+        let next_run_at = recurring.next_occurrence(now);
+        let updated = db.update_recurring(&recurring.id, |recurring| {
+            if recurring.next_run_at != Some(scheduled_for) {
+                return;
+            }
+            recurring.record_occurrence(occurrence.clone());
+            recurring.next_run_at = if recurring.enabled { next_run_at } else { None };
+        })?;
+
+        if let Some(updated) = updated {
+            broadcast_recurring_update(&updated);
+        }
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+// Create and clear the release of an occurrence. Returns why the occurrence was skipped
+// when the client already has a release on its way or the deployment would be frozen.
+fn create_occurrence_release(
+    db: &SledStorage,
+    recurring: &RecurringRelease,
+    scheduled_for: DateTime<Utc>,
+) -> Result<Result<Uuid, String>, Box<dyn std::error::Error + Send + Sync>> {
+    let client_exists = Uuid::parse_str(&recurring.client_id).ok()
+        .map(|client_id| db.get_client(&client_id))
+        .transpose()?
+        .flatten()
+        .is_some();
+    if !client_exists {
+        return Ok(Err(format!("Client {} no longer exists", recurring.client_id)));
+    }
+
+    if let Some(active) = db.get_all_releases()?.iter()
+        .find(|release| release.client_id == recurring.client_id && release.is_active())
+    {
+        return Ok(Err(format!("Client already has an active release: {} ({:?})", active.title, active.status)));
+    }
+
+    let mut release = Release::new(
+        recurring.title.clone(),
+        recurring.client_id.clone(),
+        recurring.current_environment.clone(),
+        recurring.target_environment.clone(),
+        recurring.deployment_items.clone(),
+        scheduled_for,
+        recurring.created_by.clone(),
+        recurring.skip_staging,
+    );
+    release.item_timeouts = recurring.item_timeouts.clone();
+
+    // The recurring release stands in for the user clearing the release
+    let old_status = release.status.clone();
+    if let Some(next_status) = release.next_status_when_cleared() {
+        release.status = next_status;
+    }
+
+    if let Some(environment) = release.pending_deployment_environment() {
+        if let Some(freeze) = db.find_freeze(&environment, &release.client_id, scheduled_for)? {
+            return Ok(Err(format!("Deployments to {:?} are frozen: {}", environment, freeze.reason)));
+        }
+
+        if let Some(rule) = config::deployment().approval_rule(environment.script_name()) {
+            info!("Release {} needs {} approvals before it deploys to {:?}", release.id, rule.required, environment);
+            let target_status = release.status.clone();
+            release.request_approval(ApprovalRequest {
+                environment,
+                target_status,
+                previous_status: old_status,
+                required: rule.required,
+                roles: rule.roles.clone(),
+                allow_author: rule.allow_author,
+                requested_by: None,
+                requested_at: Utc::now(),
+                rerun_item: None,
+            });
+        }
+    }
+
+    if release.status != ReleaseStatus::AwaitingApproval {
+        for item in release.deployment_items.iter_mut() {
+            item.status = release.status.clone();
+        }
+    }
+
+    db.save_release(&release)?;
+    info!("Created release {} for recurring release {} ({:?})", release.id, recurring.title, release.status);
+    schedule_release(&release);
+    if release.status == ReleaseStatus::AwaitingApproval {
+        broadcast_approval_update(&release);
+    }
+    Ok(Ok(release.id))
+}
@@ -0,0 +1,220 @@
+use chrono::Utc;
+use log::{info, warn, error};
+use tokio::time::Duration;
+use crate::config;
+use crate::models::{DeploymentItem, LogStream, TargetRun, TargetStatus};
+use crate::websocket::server::{broadcast_item_update, broadcast_release_update};
+use super::{exit_code, process_deployment_item, ItemError, ItemRun};
+
+// Change the item a run belongs to in place and broadcast it
+fn update_item(item: &ItemRun, mut change: impl FnMut(&mut DeploymentItem)) {
+    let updated = item.db.update_release(&item.release_id, |release| {
+        if let Some(deployment_item) = release.deployment_items.iter_mut().find(|it| it.name == item.item_name) {
+            change(deployment_item);
+        }
+        release.progress = release.calculate_progress();
+    });
+
+    match updated {
+        Ok(Some(release)) => {
+            if let Some(deployment_item) = release.deployment_items.iter().find(|it| it.name == item.item_name) {
+                broadcast_item_update(release.id.to_string(), deployment_item);
+            }
+        }
+        Ok(None) => warn!("Release {} was removed during the rollout of {}", item.release_id, item.item_name),
+        Err(e) => error!("Failed to store the rollout of {} for release {}: {}", item.item_name, item.release_id, e),
+    }
+}
+
+fn update_target(item: &ItemRun, target: &str, mut change: impl FnMut(&mut TargetRun)) {
+    update_item(item, |deployment_item| {
+        if let Some(target_run) = deployment_item.target_mut(target) {
+            change(target_run);
+        }
+        deployment_item.progress = deployment_item.target_progress();
+    });
+}
+
+// Mark targets that won't be started
+fn leave_targets(item: &ItemRun, targets: &[&str], status: TargetStatus, reason: &str) {
+    update_item(item, |deployment_item| {
+        for target in targets {
+            if let Some(target_run) = deployment_item.target_mut(target) {
+                target_run.status = status.clone();
+                target_run.error = Some(reason.to_string());
+            }
+        }
+    });
+    for target in targets {
+        item.log(LogStream::System, &format!("[{}] {}", target, reason));
+    }
+}
+
+// Run an item's script on one target
+async fn run_target(item: &ItemRun, target: &str) -> Result<(), ItemError> {
+    update_target(item, target, |target_run| {
+        target_run.status = TargetStatus::Running;
+        target_run.progress = 0.0;
+        target_run.started_at = Some(Utc::now());
+        target_run.finished_at = None;
+        target_run.exit_code = None;
+        target_run.error = None;
+    });
+
+    let target_run = ItemRun {
+        db: item.db.clone(),
+        release_id: item.release_id,
+        item_name: item.item_name.clone(),
+        env_name: item.env_name.clone(),
+        run: item.run,
+        attempt: item.attempt,
+        max_attempts: item.max_attempts,
+        timeout: item.timeout,
+        control: item.control.for_target(),
+        rollback: item.rollback,
+        dry_run: item.dry_run,
+        target: Some(target.to_string()),
+    };
+    let result = process_deployment_item(&target_run).await;
+    target_run.control.forget();
+
+    update_target(item, target, |target_run| {
+        target_run.finished_at = Some(Utc::now());
+        target_run.exit_code = exit_code(&result);
+        match &result {
+            Ok(()) => {
+                target_run.status = TargetStatus::Succeeded;
+                target_run.progress = 100.0;
+            }
+            Err(e) => {
+                target_run.status = match e {
+                    ItemError::Cancelled => TargetStatus::Cancelled,
+                    _ => TargetStatus::Failed,
+                };
+                target_run.error = Some(e.to_string());
+            }
+        }
+    });
+    result
+}
+
+// Run an item once per target of its client's rollout for the environment, a batch at
+// a time. Items run once when the environment has no targets, when the item type
+// asks for it or is a manual step, and in dry runs.
+pub async fn run(item: &ItemRun) -> Result<(), ItemError> {
+    let release = item.db.get_release(&item.release_id)
+        .map_err(|e| format!("Failed to get release {}: {}", item.release_id, e))?
+        .ok_or_else(|| format!("Release {} not found", item.release_id))?;
+    let run_once = config::deployment().item(&item.item_name).is_none_or(|item_config| item_config.run_once || item_config.manual.is_some());
+    let rollout = match config::deployment().rollout_for(&release.client_id, &item.env_name) {
+        Some(rollout) if !run_once && !item.dry_run => rollout,
+        _ => return process_deployment_item(item).await,
+    };
+
+    // Items that weren't rolled out per target are rolled back in one go, the way they
+    // were deployed
+    let deployed_per_target = release.deployment_items.iter()
+        .find(|deployment_item| deployment_item.name == item.item_name)
+        .is_some_and(|deployment_item| !deployment_item.targets.is_empty());
+    if item.rollback && !deployed_per_target {
+        return process_deployment_item(item).await;
+    }
+
+    // Retries only deploy the targets that didn't succeed yet, and rollbacks only undo
+    // the targets that were deployed
+    let keep_succeeded = item.attempt > 1;
+    let planned: Vec<TargetRun> = rollout.targets.iter().enumerate()
+        .map(|(index, target)| TargetRun::new(target.clone(), rollout.batch_of(index)))
+        .collect();
+    let mut pending: Vec<(String, usize)> = Vec::new();
+    update_item(item, |deployment_item| {
+        if item.rollback {
+            deployment_item.plan_rollback_targets(planned.clone());
+        } else {
+            deployment_item.plan_targets(planned.clone(), keep_succeeded);
+        }
+        pending = deployment_item.targets.iter()
+            .filter(|target| target.status == TargetStatus::Pending)
+            .map(|target| (target.name.clone(), target.batch))
+            .collect();
+    });
+
+    let batch_count = planned.iter().map(|target| target.batch).max().unwrap_or(0);
+    let action = if item.rollback { "Rolling back" } else { "Deploying to" };
+    item.log(LogStream::System, &format!("{} {} of {} targets in batches of {}",
+        action, pending.len(), planned.len(), rollout.batch_size));
+
+    let mut started_batch = false;
+    let mut failure: Option<(usize, ItemError)> = None;
+    for batch in 1..=batch_count {
+        let targets: Vec<&str> = pending.iter()
+            .filter(|(_, target_batch)| *target_batch == batch)
+            .map(|(target, _)| target.as_str())
+            .collect();
+        if targets.is_empty() {
+            continue;
+        }
+
+        // A failed batch halts the ones after it
+        match &failure {
+            Some((_, ItemError::Cancelled)) => {
+                leave_targets(item, &targets, TargetStatus::Cancelled, "Not started because the item was cancelled");
+                continue;
+            }
+            Some((failed_batch, _)) => {
+                leave_targets(item, &targets, TargetStatus::Skipped, &format!("Not started because batch {} failed", failed_batch));
+                continue;
+            }
+            None => {}
+        }
+
+        if started_batch && rollout.pause_secs > 0 {
+            item.log(LogStream::System, &format!("Pausing {}s before batch {} of {}", rollout.pause_secs, batch, batch_count));
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(rollout.pause_secs)) => {}
+                _ = item.control.cancelled() => {}
+            }
+        }
+        if item.control.is_cancelled() {
+            leave_targets(item, &targets, TargetStatus::Cancelled, "Not started because the item was cancelled");
+            failure = Some((batch, ItemError::Cancelled));
+            continue;
+        }
+        started_batch = true;
+
+        let message = format!("Batch {} of {}: {}", batch, batch_count, targets.join(", "));
+        info!("{} of release {} in {}: {}", item.item_name, item.release_id, item.env_name, message);
+        item.log(LogStream::System, &message);
+        broadcast_release_update(
+            item.release_id.to_string(),
+            "InProgress".to_string(),
+            release.progress,
+            Some(format!("[{}] {}", item.item_name, message))
+        );
+
+        let mut errors: Vec<ItemError> = futures::future::join_all(targets.iter().map(|target| run_target(item, target))).await
+            .into_iter()
+            .filter_map(Result::err)
+            .collect();
+        if !errors.is_empty() {
+            // A real failure explains the batch better than the cancellations it caused
+            let index = errors.iter().position(|e| !matches!(e, ItemError::Cancelled)).unwrap_or(0);
+            failure = Some((batch, errors.swap_remove(index)));
+        }
+    }
+
+    match failure {
+        None => Ok(()),
+        Some((_, ItemError::Cancelled)) => Err(ItemError::Cancelled),
+        Some((batch, error)) => {
+            let message = format!("Rollout halted at batch {} of {}: {}", batch, batch_count, error);
+            warn!("{} of release {}: {}", item.item_name, item.release_id, message);
+            item.log(LogStream::System, &message);
+            Err(match error {
+                ItemError::ExitCode { code, .. } => ItemError::ExitCode { code, message },
+                ItemError::TimedOut(timeout) => ItemError::TimedOut(timeout),
+                _ => ItemError::Failed(message),
+            })
+        }
+    }
+}
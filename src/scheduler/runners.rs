@@ -0,0 +1,276 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Instant;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use tokio::sync::{mpsc, Notify};
+use tokio::time::Duration;
+use uuid::Uuid;
+use crate::models::{JobOutputAck, JobOutputLine, JobResult, RunnerInfo, RunnerJob, RunnerRegistration};
+
+// Runners that haven't polled or reported output for this long are considered gone
+const DEFAULT_RUNNER_TIMEOUT_SECS: u64 = 30;
+
+lazy_static::lazy_static! {
+    static ref RUNNER_TIMEOUT: Duration = Duration::from_secs(std::env::var("RUNNER_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_RUNNER_TIMEOUT_SECS));
+    static ref REGISTRY: Mutex<Registry> = Mutex::new(Registry::default());
+    // Wakes up polling runners when a job is submitted
+    static ref JOB_SUBMITTED: Notify = Notify::new();
+}
+
+// What happens to a submitted job, as seen by the item waiting for it
+pub enum RunnerEvent {
+    Started(String), // Name of the runner that picked up the job
+    Output(JobOutputLine),
+    Finished(JobResult),
+    Lost(String), // The runner stopped responding
+}
+
+pub enum RunnerError {
+    UnknownRunner,
+    UnknownJob,
+}
+
+struct RunnerState {
+    registration: RunnerRegistration,
+    registered_at: DateTime<Utc>,
+    last_seen: Instant,
+    last_seen_at: DateTime<Utc>,
+    job: Option<Uuid>,
+}
+
+impl RunnerState {
+    fn touch(&mut self) {
+        self.last_seen = Instant::now();
+        self.last_seen_at = Utc::now();
+    }
+}
+
+struct JobState {
+    job: RunnerJob,
+    client_id: String,
+    client_name: String,
+    runner: Option<Uuid>,
+    cancelled: bool,
+    answers: Vec<String>,
+    events: mpsc::UnboundedSender<RunnerEvent>,
+}
+
+#[derive(Default)]
+struct Registry {
+    runners: HashMap<Uuid, RunnerState>,
+    jobs: HashMap<Uuid, JobState>,
+    pending: VecDeque<Uuid>, // Submitted jobs no runner has picked up yet, oldest first
+}
+
+impl Registry {
+    // Hand the oldest pending job the runner's labels allow to the runner
+    fn assign(&mut self, runner_id: Uuid) -> Option<RunnerJob> {
+        let runner = self.runners.get(&runner_id)?;
+        let index = self.pending.iter().position(|job_id| {
+            self.jobs.get(job_id).is_some_and(|state| runner.registration.labels.matches(
+                &state.client_id, &state.client_name, &state.job.env_name, &state.job.item_name))
+        })?;
+        let job_id = self.pending.remove(index)?;
+
+        let runner_name = runner.registration.name.clone();
+        let state = self.jobs.get_mut(&job_id)?;
+        state.runner = Some(runner_id);
+        let _ = state.events.send(RunnerEvent::Started(runner_name));
+        let job = state.job.clone();
+
+        if let Some(runner) = self.runners.get_mut(&runner_id) {
+            runner.job = Some(job_id);
+        }
+        Some(job)
+    }
+
+    // Forget a runner, failing the job it was working on
+    fn remove_runner(&mut self, runner_id: Uuid, reason: &str) {
+        let Some(runner) = self.runners.remove(&runner_id) else {
+            return;
+        };
+
+        if let Some(state) = runner.job.and_then(|job_id| self.jobs.remove(&job_id)) {
+            let _ = state.events.send(RunnerEvent::Lost(format!("Runner {} {}", runner.registration.name, reason)));
+        }
+    }
+}
+
+// Register a runner, returns its id
+pub fn register(registration: RunnerRegistration) -> Uuid {
+    let runner_id = Uuid::new_v4();
+    info!("Runner {} registered as {} with labels {:?}", registration.name, runner_id, registration.labels);
+
+    if let Ok(mut registry) = REGISTRY.lock() {
+        registry.runners.insert(runner_id, RunnerState {
+            registration,
+            registered_at: Utc::now(),
+            last_seen: Instant::now(),
+            last_seen_at: Utc::now(),
+            job: None,
+        });
+    }
+    JOB_SUBMITTED.notify_waiters();
+    runner_id
+}
+
+// Queue a job for the next matching runner, the receiver gets everything that happens to it
+pub fn submit(job: RunnerJob, client_id: &str, client_name: &str) -> mpsc::UnboundedReceiver<RunnerEvent> {
+    let (events, receiver) = mpsc::unbounded_channel();
+
+    if let Ok(mut registry) = REGISTRY.lock() {
+        let job_id = job.id;
+        registry.jobs.insert(job_id, JobState {
+            job,
+            client_id: client_id.to_string(),
+            client_name: client_name.to_string(),
+            runner: None,
+            cancelled: false,
+            answers: Vec::new(),
+            events,
+        });
+        registry.pending.push_back(job_id);
+    }
+    JOB_SUBMITTED.notify_waiters();
+    receiver
+}
+
+// Wait up to `wait` for a job the runner can pick up
+pub async fn poll(runner_id: Uuid, wait: Duration) -> Result<Option<RunnerJob>, RunnerError> {
+    let deadline = tokio::time::Instant::now() + wait;
+    loop {
+        // Register for wakeups before looking, so a job submitted in between isn't missed
+        let submitted = JOB_SUBMITTED.notified();
+        {
+            let mut registry = REGISTRY.lock().map_err(|_| RunnerError::UnknownRunner)?;
+            registry.runners.get_mut(&runner_id).ok_or(RunnerError::UnknownRunner)?.touch();
+            if let Some(job) = registry.assign(runner_id) {
+                return Ok(Some(job));
+            }
+        }
+
+        tokio::select! {
+            _ = submitted => {}
+            _ = tokio::time::sleep_until(deadline) => return Ok(None),
+        }
+    }
+}
+
+// Pass on output of a runner's job, the reply tells the runner to stop or what to answer
+pub fn report_output(runner_id: Uuid, job_id: Uuid, lines: Vec<JobOutputLine>) -> Result<JobOutputAck, RunnerError> {
+    let mut registry = REGISTRY.lock().map_err(|_| RunnerError::UnknownRunner)?;
+    registry.runners.get_mut(&runner_id).ok_or(RunnerError::UnknownRunner)?.touch();
+
+    let state = registry.jobs.get_mut(&job_id)
+        .filter(|state| state.runner == Some(runner_id))
+        .ok_or(RunnerError::UnknownJob)?;
+    for line in lines {
+        let _ = state.events.send(RunnerEvent::Output(line));
+    }
+
+    Ok(JobOutputAck {
+        cancelled: state.cancelled,
+        answers: std::mem::take(&mut state.answers),
+    })
+}
+
+// Record how a runner's job ended and free the runner
+pub fn finish(runner_id: Uuid, job_id: Uuid, result: JobResult) -> Result<(), RunnerError> {
+    let mut registry = REGISTRY.lock().map_err(|_| RunnerError::UnknownRunner)?;
+    let runner = registry.runners.get_mut(&runner_id).ok_or(RunnerError::UnknownRunner)?;
+    runner.touch();
+    if runner.job == Some(job_id) {
+        runner.job = None;
+    }
+
+    let state = registry.jobs.remove(&job_id)
+        .filter(|state| state.runner == Some(runner_id))
+        .ok_or(RunnerError::UnknownJob)?;
+    let _ = state.events.send(RunnerEvent::Finished(result));
+    Ok(())
+}
+
+// Stop a job, a runner working on it is told so with its next output reply
+pub fn cancel(job_id: Uuid) {
+    let Ok(mut registry) = REGISTRY.lock() else {
+        return;
+    };
+
+    registry.pending.retain(|pending| *pending != job_id);
+    match registry.jobs.get_mut(&job_id) {
+        Some(state) if state.runner.is_some() => state.cancelled = true,
+        Some(_) => {
+            registry.jobs.remove(&job_id);
+        }
+        None => {}
+    }
+}
+
+// Queue a line for the script's stdin, returns false if no runner is working on the job
+pub fn answer(job_id: Uuid, line: &str) -> bool {
+    let Ok(mut registry) = REGISTRY.lock() else {
+        return false;
+    };
+
+    match registry.jobs.get_mut(&job_id) {
+        Some(state) if state.runner.is_some() && !state.cancelled => {
+            state.answers.push(line.to_string());
+            true
+        }
+        _ => false,
+    }
+}
+
+// Whether a registered runner could pick up a job for the client, environment and item type
+pub fn any_matching(client_id: &str, client_name: &str, env_name: &str, item_name: &str) -> bool {
+    REGISTRY.lock()
+        .map(|registry| registry.runners.values()
+            .any(|runner| runner.registration.labels.matches(client_id, client_name, env_name, item_name)))
+        .unwrap_or(false)
+}
+
+// Registered runners, oldest first
+pub fn list() -> Vec<RunnerInfo> {
+    let Ok(registry) = REGISTRY.lock() else {
+        return Vec::new();
+    };
+
+    let mut runners: Vec<RunnerInfo> = registry.runners.iter()
+        .map(|(id, runner)| RunnerInfo {
+            id: *id,
+            name: runner.registration.name.clone(),
+            labels: runner.registration.labels.clone(),
+            registered_at: runner.registered_at,
+            last_seen: runner.last_seen_at,
+            job: runner.job,
+        })
+        .collect();
+    runners.sort_by_key(|runner| runner.registered_at);
+    runners
+}
+
+// Drop runners that stopped polling and fail the jobs they were running
+pub fn start_reaper() -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut check = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            check.tick().await;
+
+            let Ok(mut registry) = REGISTRY.lock() else {
+                continue;
+            };
+            let gone: Vec<Uuid> = registry.runners.iter()
+                .filter(|(_, runner)| runner.last_seen.elapsed() > *RUNNER_TIMEOUT)
+                .map(|(id, _)| *id)
+                .collect();
+            for runner_id in gone {
+                warn!("Runner {} stopped responding, removing it", runner_id);
+                registry.remove_runner(runner_id, &format!("stopped responding for {}s", RUNNER_TIMEOUT.as_secs()));
+            }
+        }
+    })
+}
@@ -0,0 +1,215 @@
+use std::process::ExitStatus;
+use tokio::process::Command;
+use crate::config::SandboxConfig;
+
+// PATH scripts get when the server's environment is scrubbed and the item sets none
+const CLEAN_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+// Extra CPU seconds between SIGXCPU and the kernel's SIGKILL, so scripts can clean up
+const CPU_GRACE_SECS: u64 = 5;
+
+// A limit a script ran into
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Cpu,
+    Memory,
+    OpenFiles,
+    Processes,
+}
+
+// The user a script runs as
+#[cfg(target_os = "linux")]
+struct RunAs {
+    name: String,
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+    groups: Vec<libc::gid_t>,
+    home: String,
+}
+
+// Look up a user and the groups it belongs to
+#[cfg(target_os = "linux")]
+fn lookup_user(name: &str) -> Result<RunAs, String> {
+    use std::ffi::{CStr, CString};
+
+    let c_name = CString::new(name).map_err(|_| format!("Invalid sandbox user '{}'", name))?;
+    let mut buffer = vec![0 as libc::c_char; 16 * 1024];
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut found: *mut libc::passwd = std::ptr::null_mut();
+    let result = unsafe {
+        libc::getpwnam_r(c_name.as_ptr(), &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut found)
+    };
+    if result != 0 || found.is_null() {
+        return Err(format!("Sandbox user '{}' does not exist", name));
+    }
+    let home = unsafe { CStr::from_ptr(passwd.pw_dir) }.to_string_lossy().into_owned();
+
+    let mut groups: Vec<libc::gid_t> = vec![0; 64];
+    loop {
+        let mut count = groups.len() as libc::c_int;
+        let result = unsafe { libc::getgrouplist(c_name.as_ptr(), passwd.pw_gid, groups.as_mut_ptr(), &mut count) };
+        if result >= 0 {
+            groups.truncate(count as usize);
+            break;
+        }
+        // Too small, `count` now holds how many groups there are
+        groups.resize((count as usize).max(groups.len() * 2), 0);
+    }
+
+    Ok(RunAs {
+        name: name.to_string(),
+        uid: passwd.pw_uid,
+        gid: passwd.pw_gid,
+        groups,
+        home,
+    })
+}
+
+// Set up the script's environment and the limits and user it starts with. Must be
+// called before the item's own variables are added to the command.
+#[cfg(target_os = "linux")]
+pub fn apply(command: &mut Command, sandbox: &SandboxConfig) -> Result<(), String> {
+    let run_as = sandbox.user.as_deref().map(lookup_user).transpose()?;
+
+    if sandbox.clean_env {
+        command.env_clear().env("PATH", CLEAN_PATH);
+        if let Some(run_as) = &run_as {
+            command
+                .env("HOME", &run_as.home)
+                .env("USER", &run_as.name)
+                .env("LOGNAME", &run_as.name);
+        }
+    }
+
+    let cpu = sandbox.cpu_secs.map(|secs| (secs, secs + CPU_GRACE_SECS));
+    let memory = sandbox.memory_mb.map(|mb| mb.saturating_mul(1024 * 1024));
+    let open_files = sandbox.open_files;
+    let processes = sandbox.max_processes;
+    let nice = sandbox.nice;
+    let user = run_as.map(|run_as| (run_as.uid, run_as.gid, run_as.groups));
+
+    // Runs in the forked child right before exec, so it must not allocate
+    let setup = move || -> std::io::Result<()> {
+        fn check(result: libc::c_int) -> std::io::Result<()> {
+            if result == -1 {
+                Err(std::io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        }
+        fn limit(soft: u64, hard: u64) -> libc::rlimit {
+            libc::rlimit { rlim_cur: soft as libc::rlim_t, rlim_max: hard as libc::rlim_t }
+        }
+
+        unsafe {
+            if let Some((soft, hard)) = cpu {
+                check(libc::setrlimit(libc::RLIMIT_CPU, &limit(soft, hard)))?;
+            }
+            if let Some(bytes) = memory {
+                check(libc::setrlimit(libc::RLIMIT_AS, &limit(bytes, bytes)))?;
+            }
+            if let Some(files) = open_files {
+                check(libc::setrlimit(libc::RLIMIT_NOFILE, &limit(files, files)))?;
+            }
+            if let Some(count) = processes {
+                check(libc::setrlimit(libc::RLIMIT_NPROC, &limit(count, count)))?;
+            }
+            if let Some(nice) = nice {
+                check(libc::setpriority(libc::PRIO_PROCESS as _, 0, nice))?;
+            }
+            // Groups and group id have to change while we're still root
+            if let Some((uid, gid, groups)) = &user {
+                check(libc::setgroups(groups.len(), groups.as_ptr()))?;
+                check(libc::setgid(*gid))?;
+                check(libc::setuid(*uid))?;
+            }
+        }
+        Ok(())
+    };
+    unsafe {
+        command.pre_exec(setup);
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn apply(_command: &mut Command, sandbox: &SandboxConfig) -> Result<(), String> {
+    if sandbox.is_empty() {
+        Ok(())
+    } else {
+        Err("Script sandboxing is only supported on Linux".to_string())
+    }
+}
+
+// Check that the sandbox can be set up, without starting anything
+#[cfg(target_os = "linux")]
+pub fn check(sandbox: &SandboxConfig) -> Result<(), String> {
+    sandbox.user.as_deref().map(lookup_user).transpose().map(|_| ())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn check(sandbox: &SandboxConfig) -> Result<(), String> {
+    apply(&mut Command::new(""), sandbox)
+}
+
+// PATH a script searches for its command
+pub fn script_path(sandbox: &SandboxConfig) -> Option<String> {
+    if sandbox.clean_env {
+        Some(CLEAN_PATH.to_string())
+    } else {
+        std::env::var("PATH").ok()
+    }
+}
+
+// Guess from a line of output which configured limit the script ran into
+pub fn limit_in_output(sandbox: &SandboxConfig, line: &str) -> Option<Limit> {
+    let line = line.to_lowercase();
+    if sandbox.open_files.is_some() && line.contains("too many open files") {
+        Some(Limit::OpenFiles)
+    } else if sandbox.memory_mb.is_some()
+        && ["cannot allocate memory", "out of memory", "memoryerror", "bad_alloc"].iter().any(|hint| line.contains(hint))
+    {
+        Some(Limit::Memory)
+    } else if sandbox.max_processes.is_some()
+        && line.contains("resource temporarily unavailable")
+        && (line.contains("fork") || line.contains("thread"))
+    {
+        Some(Limit::Processes)
+    } else {
+        None
+    }
+}
+
+fn describe(sandbox: &SandboxConfig, limit: Limit) -> String {
+    match limit {
+        Limit::Cpu => format!("its CPU time limit of {}s", sandbox.cpu_secs.unwrap_or_default()),
+        Limit::Memory => format!("its memory limit of {} MB", sandbox.memory_mb.unwrap_or_default()),
+        Limit::OpenFiles => format!("its limit of {} open files", sandbox.open_files.unwrap_or_default()),
+        Limit::Processes => format!("its limit of {} processes", sandbox.max_processes.unwrap_or_default()),
+    }
+}
+
+// Explain a failed exit caused by one of the sandbox's limits, `seen` being the
+// limit the script's output pointed at, if any
+pub fn violation(sandbox: &SandboxConfig, status: &ExitStatus, seen: Option<Limit>) -> Option<String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        match status.signal() {
+            Some(libc::SIGXCPU) if sandbox.cpu_secs.is_some() => {
+                return Some(format!("exceeded {}", describe(sandbox, Limit::Cpu)));
+            }
+            Some(libc::SIGKILL) if sandbox.cpu_secs.is_some() && seen.is_none() => {
+                return Some(format!("was killed, it may have exceeded {}", describe(sandbox, Limit::Cpu)));
+            }
+            _ => {}
+        }
+    }
+
+    if status.success() {
+        return None;
+    }
+    seen.map(|limit| format!("ran into {}", describe(sandbox, limit)))
+}
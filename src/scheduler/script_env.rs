@@ -0,0 +1,130 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use crate::config;
+use crate::models::Release;
+
+// Secret values are replaced with this in everything a script prints
+const REDACTED: &str = "****";
+
+// What a deployment run tells its script about itself
+pub struct RunContext<'a> {
+    pub release: &'a Release,
+    pub client_name: &'a str,
+    pub item_name: &'a str,
+    pub env_name: &'a str,
+    pub run: u32,
+    pub attempt: u32,
+    pub rollback: bool,
+    pub dry_run: bool,
+    pub target: Option<&'a str>, // Rollout target the run deploys to
+}
+
+// Variables passed to a deployment script, and the secret values among them that
+// must never show up in its stored or broadcast output
+pub struct ScriptEnvironment {
+    pub variables: HashMap<String, String>,
+    secrets: Vec<String>,
+}
+
+impl ScriptEnvironment {
+    // Item variables first, then the client's secrets, then the BLEND_* context so
+    // scripts can rely on it
+    pub fn new(context: &RunContext, item_env: &HashMap<String, String>) -> Self {
+        let release = context.release;
+        let mut variables = item_env.clone();
+
+        let secrets = config::secrets().for_deployment(&release.client_id, context.env_name);
+        let secret_values = secret_values(&secrets);
+        variables.extend(secrets);
+
+        let context_variables = [
+            ("BLEND_RELEASE_ID", release.id.to_string()),
+            ("BLEND_RELEASE_TITLE", release.title.clone()),
+            ("BLEND_CLIENT_ID", release.client_id.clone()),
+            ("BLEND_CLIENT_NAME", context.client_name.to_string()),
+            ("BLEND_ENVIRONMENT", context.env_name.to_string()),
+            ("BLEND_REQUESTED_BY", release.created_by.clone()),
+            ("BLEND_SCHEDULED_AT", release.scheduled_at.to_rfc3339()),
+            ("BLEND_ITEM", context.item_name.to_string()),
+            ("BLEND_RUN", context.run.to_string()),
+            ("BLEND_ATTEMPT", context.attempt.to_string()),
+            ("BLEND_ACTION", if context.rollback { "rollback" } else { "deploy" }.to_string()),
+            ("BLEND_DRY_RUN", if context.dry_run { "1" } else { "0" }.to_string()),
+        ];
+        variables.extend(context_variables.into_iter().map(|(name, value)| (name.to_string(), value)));
+        if let Some(target) = context.target {
+            variables.insert("BLEND_TARGET".to_string(), target.to_string());
+        }
+
+        Self {
+            variables,
+            secrets: secret_values,
+        }
+    }
+
+    // Mask every secret value in a line of script output
+    pub fn redact<'l>(&self, line: &'l str) -> Cow<'l, str> {
+        let mut redacted = Cow::Borrowed(line);
+        for secret in &self.secrets {
+            if redacted.contains(secret.as_str()) {
+                redacted = Cow::Owned(redacted.replace(secret.as_str(), REDACTED));
+            }
+        }
+        redacted
+    }
+}
+
+// The values to mask, longer ones first so a secret containing another one is masked whole
+fn secret_values(secrets: &HashMap<String, String>) -> Vec<String> {
+    let mut values: Vec<String> = secrets.values()
+        .filter(|value| !value.is_empty())
+        .cloned()
+        .collect();
+    values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+    values.dedup();
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn environment(secrets: &[(&str, &str)]) -> ScriptEnvironment {
+        let secrets: HashMap<String, String> = secrets.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        ScriptEnvironment {
+            variables: HashMap::new(),
+            secrets: secret_values(&secrets),
+        }
+    }
+
+    #[test]
+    fn secrets_are_masked() {
+        let environment = environment(&[("DB_PASSWORD", "hunter2"), ("API_KEY", "abc123")]);
+
+        assert_eq!(environment.redact("connecting with hunter2 and abc123"), "connecting with **** and ****");
+        assert_eq!(environment.redact("hunter2hunter2"), "********");
+    }
+
+    #[test]
+    fn lines_without_secrets_are_left_alone() {
+        let environment = environment(&[("DB_PASSWORD", "hunter2")]);
+
+        assert!(matches!(environment.redact("[data] Creating database backup..."), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn a_secret_containing_another_is_masked_whole() {
+        let environment = environment(&[("SHORT", "secret"), ("LONG", "secret-suffix")]);
+
+        assert_eq!(environment.redact("token secret-suffix, then secret"), "token ****, then ****");
+    }
+
+    #[test]
+    fn empty_secrets_mask_nothing() {
+        let environment = environment(&[("UNSET", "")]);
+
+        assert_eq!(environment.redact("nothing to hide"), "nothing to hide");
+    }
+}
@@ -0,0 +1,139 @@
+use chrono::Utc;
+use log::{info, warn, error};
+use uuid::Uuid;
+use crate::models::{Release, ReleaseStatus, ReleaseTrain, TrainFailure, TrainStop};
+use crate::storage::SledStorage;
+use crate::websocket::server::{broadcast_app_log, broadcast_release_update, broadcast_train_update};
+use super::cancel_release;
+
+// The train a release belongs to if it was stopped
+pub fn stopped_train(db: &SledStorage, release: &Release) -> Result<Option<ReleaseTrain>, Box<dyn std::error::Error + Send + Sync>> {
+    let train_id = match release.train_id {
+        Some(train_id) => train_id,
+        None => return Ok(None),
+    };
+    Ok(db.get_train(&train_id)?.filter(|train| train.stopped.is_some()))
+}
+
+// Stop a train. Returns None if it doesn't exist or was already stopped.
+pub fn stop_train(
+    db: &SledStorage,
+    train_id: Uuid,
+    reason: String,
+    stopped_by: Option<String>,
+) -> Result<Option<ReleaseTrain>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut stopped = false;
+    let train = db.update_train(&train_id, |train| {
+        stopped = train.stopped.is_none();
+        if stopped {
+            train.stopped = Some(TrainStop {
+                reason: reason.clone(),
+                stopped_by: stopped_by.clone(),
+                stopped_at: Utc::now(),
+            });
+        }
+    })?;
+
+    match train.filter(|_| stopped) {
+        Some(train) => {
+            info!("Stopped release train {} ({}): {}", train.id, train.title, reason);
+            broadcast_train_update(&train);
+            Ok(Some(train))
+        }
+        None => Ok(None),
+    }
+}
+
+// Cancel the releases of a train. Releases still waiting for their deployment are
+// always cancelled, running ones only with `running`. Returns the cancelled releases.
+pub fn cancel_train_releases(
+    db: &SledStorage,
+    train_id: Uuid,
+    running: bool,
+) -> Result<Vec<Release>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut cancelled = Vec::new();
+    for release in db.get_train_releases(&train_id)? {
+        let waiting = release.pending_deployment_environment().is_some();
+        if !release.can_be_cancelled() || !(waiting || running) {
+            continue;
+        }
+        if let Some((release, _)) = cancel_release(db, release.id)? {
+            cancelled.push(release);
+        }
+    }
+    Ok(cancelled)
+}
+
+// Count a failed deployment of a train's release against its train. A train that
+// reaches its failure limit is stopped and its waiting releases are cancelled, the
+// ones already deploying finish.
+pub fn record_outcome(db: &SledStorage, release_id: Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let release = match db.get_release(&release_id)? {
+        Some(release) if release.status == ReleaseStatus::Error => release,
+        _ => return Ok(()),
+    };
+    let train_id = match release.train_id {
+        Some(train_id) => train_id,
+        None => return Ok(()),
+    };
+
+    let failure = TrainFailure {
+        release_id,
+        client_id: release.client_id.clone(),
+        environment: release.last_deployment_environment(),
+        error: release.deployment_items.iter().find_map(|item| item.error.clone()),
+        failed_at: Utc::now(),
+    };
+    let mut recorded = false;
+    let train = match db.update_train(&train_id, |train| {
+        recorded = train.record_failure(failure.clone());
+    })? {
+        Some(train) if recorded => train,
+        Some(_) => return Ok(()),
+        None => {
+            warn!("Release {} belongs to train {} which no longer exists", release_id, train_id);
+            return Ok(());
+        }
+    };
+
+    warn!("Release {} of train {} failed ({} failures)", release_id, train.title, train.failures.len());
+    broadcast_train_update(&train);
+
+    if train.stopped.is_some() || !train.failure_limit_reached() {
+        return Ok(());
+    }
+
+    let reason = format!("Stopped after {} of its clients failed", train.failures.len());
+    if stop_train(db, train_id, reason.clone(), None)?.is_some() {
+        let cancelled = cancel_train_releases(db, train_id, false)?;
+        error!("Release train {}: {}, cancelled {} waiting releases", train.title, reason, cancelled.len());
+        broadcast_app_log("error", &format!("Release train {} {}", train.title, reason.to_lowercase()));
+    }
+    Ok(())
+}
+
+// Cancel a release of a stopped train that came due instead of deploying it
+pub fn cancel_held_release(db: &SledStorage, release: &Release, train: &ReleaseTrain) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let reason = train.stopped.as_ref().map(|stop| stop.reason.clone()).unwrap_or_default();
+    let message = format!("Not deployed because release train {} was stopped: {}", train.title, reason);
+    let updated = db.update_release(&release.id, |release| {
+        release.status = ReleaseStatus::Cancelled;
+        release.approval_request = None;
+        for item in release.deployment_items.iter_mut() {
+            if item.status != ReleaseStatus::Error {
+                item.status = ReleaseStatus::Cancelled;
+            }
+        }
+    })?;
+
+    if let Some(release) = updated {
+        info!("Release {}: {}", release.id, message);
+        broadcast_release_update(
+            release.id.to_string(),
+            format!("{:?}", release.status),
+            release.progress,
+            Some(message)
+        );
+    }
+    Ok(())
+}
@@ -5,6 +5,11 @@ pub use sled_storage::SledStorage;
 pub fn init() -> Result<SledStorage, Box<dyn std::error::Error + Send + Sync>> {
     let storage = SledStorage::new()?;
     
+    let migrated = storage.migrate_release_logs()?;
+    if migrated > 0 {
+        log::info!("Moved the logs of {} releases to the log store", migrated);
+    }
+    
     // Initialize with default clients
     create_default_clients(&storage)?;
     
@@ -1,32 +1,65 @@
-use sled::{Db, Result as SledResult};
+use sled::{Db, Tree, Result as SledResult};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
-use chrono::Utc;
-use crate::models::{Release, User, Client, ReleaseStatus};
+use crate::models::{Release, User, Client, ReleaseStatus, FreezeWindow, Environment, ReleaseTrain, RecurringRelease, InventoryEntry, LogLine, LogStream};
 use log::{info, error};
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use chrono::Utc;
 use std::collections::HashMap;
 
 // Key prefixes for storing different types
 const RELEASE_PREFIX: &str = "release:";
 const USER_PREFIX: &str = "user:";
 const CLIENT_PREFIX: &str = "client:";
+const FREEZE_PREFIX: &str = "freeze:";
+const TRAIN_PREFIX: &str = "train:";
+const RECURRING_PREFIX: &str = "recurring:";
+const INVENTORY_PREFIX: &str = "inventory:";
 const SESSION_PREFIX: &str = "session:";
 const WEBSOCKET_PREFIX: &str = "ws:";
 
+// Deployment item output lives in trees of its own, so a line doesn't rewrite the release
+const LOG_TREE: &str = "logs";
+const LOG_COUNT_TREE: &str = "log_counts";
+
+// Lines stored for a deployment item and lines dropped by the log size cap
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LogCount {
+    stored: usize,
+    dropped: usize,
+}
+
+// Keys of an item's log lines start with the release id and the item name. The run
+// and a sequence number follow, so a prefix scan returns the lines in order.
+fn log_prefix(release_id: &Uuid, item_name: &str) -> Vec<u8> {
+    let mut prefix = release_id.as_bytes().to_vec();
+    prefix.extend_from_slice(item_name.as_bytes());
+    prefix.push(0);
+    prefix
+}
+
 #[derive(Clone)]
 pub struct SledStorage {
     db: Db,
+    logs: Tree, // Log lines keyed by release, item, run and sequence number
+    log_counts: Tree, // LogCount per release and item
     active_websockets: Arc<Mutex<HashMap<String, String>>>, // UUID -> User ID
 }
 
 impl SledStorage {
     pub fn new() -> SledResult<Self> {
         let db_path = std::env::var("DB_PATH").unwrap_or_else(|_| "data".to_string());
-        let db = sled::open(Path::new(&db_path))?;
+        Self::with_db(sled::open(Path::new(&db_path))?)
+    }
+    
+    fn with_db(db: Db) -> SledResult<Self> {
+        let logs = db.open_tree(LOG_TREE)?;
+        let log_counts = db.open_tree(LOG_COUNT_TREE)?;
         Ok(Self { 
             db,
+            logs,
+            log_counts,
             active_websockets: Arc::new(Mutex::new(HashMap::new())),
         })
     }
@@ -56,9 +89,33 @@ impl SledStorage {
         Ok(None)
     }
     
+    // Apply a change to a stored release atomically. The closure may run more than
+    // once if the release is modified concurrently, e.g. by parallel deployment items.
+    pub fn update_release<F>(&self, id: &Uuid, mut f: F) -> Result<Option<Release>, Box<dyn std::error::Error + Send + Sync>>
+    where
+        F: FnMut(&mut Release),
+    {
+        let key = format!("{}{}", RELEASE_PREFIX, id);
+        loop {
+            let current = match self.db.get(&key)? {
+                Some(bytes) => bytes,
+                None => return Ok(None),
+            };
+            
+            let mut release: Release = Self::deserialize(&current)?;
+            f(&mut release);
+            let value = Self::serialize(&release)?;
+            
+            if self.db.compare_and_swap(&key, Some(current), Some(value))?.is_ok() {
+                return Ok(Some(release));
+            }
+        }
+    }
+    
     pub fn delete_release(&self, id: &Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let key = format!("{}{}", RELEASE_PREFIX, id);
         self.db.remove(key)?;
+        self.delete_logs(id)?;
         Ok(())
     }
     
@@ -133,6 +190,379 @@ impl SledStorage {
         Ok(clients)
     }
     
+    // Freeze window methods
+    pub fn save_freeze(&self, freeze: &FreezeWindow) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let key = format!("{}{}", FREEZE_PREFIX, freeze.id);
+        let value = Self::serialize(freeze)?;
+        self.db.insert(key, value)?;
+        Ok(())
+    }
+    
+    pub fn get_freeze(&self, id: &Uuid) -> Result<Option<FreezeWindow>, Box<dyn std::error::Error + Send + Sync>> {
+        let key = format!("{}{}", FREEZE_PREFIX, id);
+        if let Some(bytes) = self.db.get(key)? {
+            return Ok(Some(Self::deserialize(&bytes)?));
+        }
+        Ok(None)
+    }
+    
+    pub fn get_all_freezes(&self) -> Result<Vec<FreezeWindow>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut freezes = Vec::new();
+        let prefix = FREEZE_PREFIX.as_bytes();
+        
+        for item in self.db.scan_prefix(prefix) {
+            let (_, value) = item?;
+            let freeze: FreezeWindow = Self::deserialize(&value)?;
+            freezes.push(freeze);
+        }
+        
+        freezes.sort_by_key(|freeze| freeze.starts_at);
+        Ok(freezes)
+    }
+    
+    pub fn delete_freeze(&self, id: &Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let key = format!("{}{}", FREEZE_PREFIX, id);
+        self.db.remove(key)?;
+        Ok(())
+    }
+    
+    // The freeze covering a deployment, the one lasting longest if several overlap
+    pub fn find_freeze(
+        &self,
+        environment: &Environment,
+        client_id: &str,
+        at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<FreezeWindow>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.get_all_freezes()?
+            .into_iter()
+            .filter(|freeze| freeze.covers(environment, client_id, at))
+            .max_by_key(|freeze| freeze.ends_at))
+    }
+    
+    // Release train methods
+    pub fn save_train(&self, train: &ReleaseTrain) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let key = format!("{}{}", TRAIN_PREFIX, train.id);
+        let value = Self::serialize(train)?;
+        self.db.insert(key, value)?;
+        Ok(())
+    }
+    
+    pub fn get_train(&self, id: &Uuid) -> Result<Option<ReleaseTrain>, Box<dyn std::error::Error + Send + Sync>> {
+        let key = format!("{}{}", TRAIN_PREFIX, id);
+        if let Some(bytes) = self.db.get(key)? {
+            return Ok(Some(Self::deserialize(&bytes)?));
+        }
+        Ok(None)
+    }
+    
+    // Apply a change to a stored train atomically, like update_release
+    pub fn update_train<F>(&self, id: &Uuid, mut f: F) -> Result<Option<ReleaseTrain>, Box<dyn std::error::Error + Send + Sync>>
+    where
+        F: FnMut(&mut ReleaseTrain),
+    {
+        let key = format!("{}{}", TRAIN_PREFIX, id);
+        loop {
+            let current = match self.db.get(&key)? {
+                Some(bytes) => bytes,
+                None => return Ok(None),
+            };
+            
+            let mut train: ReleaseTrain = Self::deserialize(&current)?;
+            f(&mut train);
+            let value = Self::serialize(&train)?;
+            
+            if self.db.compare_and_swap(&key, Some(current), Some(value))?.is_ok() {
+                return Ok(Some(train));
+            }
+        }
+    }
+    
+    pub fn get_all_trains(&self) -> Result<Vec<ReleaseTrain>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut trains = Vec::new();
+        let prefix = TRAIN_PREFIX.as_bytes();
+        
+        for item in self.db.scan_prefix(prefix) {
+            let (_, value) = item?;
+            let train: ReleaseTrain = Self::deserialize(&value)?;
+            trains.push(train);
+        }
+        
+        trains.sort_by_key(|train| train.scheduled_at);
+        Ok(trains)
+    }
+    
+    // Releases belonging to a train
+    pub fn get_train_releases(&self, train_id: &Uuid) -> Result<Vec<Release>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.get_all_releases()?
+            .into_iter()
+            .filter(|release| release.train_id.as_ref() == Some(train_id))
+            .collect())
+    }
+    
+    // Recurring release methods
+    pub fn save_recurring(&self, recurring: &RecurringRelease) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let key = format!("{}{}", RECURRING_PREFIX, recurring.id);
+        let value = Self::serialize(recurring)?;
+        self.db.insert(key, value)?;
+        Ok(())
+    }
+    
+    pub fn get_recurring(&self, id: &Uuid) -> Result<Option<RecurringRelease>, Box<dyn std::error::Error + Send + Sync>> {
+        let key = format!("{}{}", RECURRING_PREFIX, id);
+        if let Some(bytes) = self.db.get(key)? {
+            return Ok(Some(Self::deserialize(&bytes)?));
+        }
+        Ok(None)
+    }
+    
+    // Apply a change to a stored recurring release atomically, like update_release
+    pub fn update_recurring<F>(&self, id: &Uuid, mut f: F) -> Result<Option<RecurringRelease>, Box<dyn std::error::Error + Send + Sync>>
+    where
+        F: FnMut(&mut RecurringRelease),
+    {
+        let key = format!("{}{}", RECURRING_PREFIX, id);
+        loop {
+            let current = match self.db.get(&key)? {
+                Some(bytes) => bytes,
+                None => return Ok(None),
+            };
+            
+            let mut recurring: RecurringRelease = Self::deserialize(&current)?;
+            f(&mut recurring);
+            let value = Self::serialize(&recurring)?;
+            
+            if self.db.compare_and_swap(&key, Some(current), Some(value))?.is_ok() {
+                return Ok(Some(recurring));
+            }
+        }
+    }
+    
+    pub fn get_all_recurring(&self) -> Result<Vec<RecurringRelease>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut recurring_releases = Vec::new();
+        let prefix = RECURRING_PREFIX.as_bytes();
+        
+        for item in self.db.scan_prefix(prefix) {
+            let (_, value) = item?;
+            let recurring: RecurringRelease = Self::deserialize(&value)?;
+            recurring_releases.push(recurring);
+        }
+        
+        recurring_releases.sort_by(|a, b| a.title.cmp(&b.title));
+        Ok(recurring_releases)
+    }
+    
+    pub fn delete_recurring(&self, id: &Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let key = format!("{}{}", RECURRING_PREFIX, id);
+        self.db.remove(key)?;
+        Ok(())
+    }
+    
+    // Inventory methods, one entry per client, environment and item
+    fn inventory_key(client_id: &str, environment: &Environment, item: &str) -> String {
+        format!("{}{}:{}:{}", INVENTORY_PREFIX, client_id, environment.script_name(), item)
+    }
+    
+    // Record a deployment of an item, replacing what was deployed before
+    pub fn record_inventory(&self, entry: &InventoryEntry) -> Result<InventoryEntry, Box<dyn std::error::Error + Send + Sync>> {
+        let key = Self::inventory_key(&entry.client_id, &entry.environment, &entry.item);
+        loop {
+            let current = self.db.get(&key)?;
+            let recorded = match &current {
+                Some(bytes) => {
+                    let mut existing: InventoryEntry = Self::deserialize(bytes)?;
+                    existing.replace_with(entry.clone());
+                    existing
+                }
+                None => entry.clone(),
+            };
+            let value = Self::serialize(&recorded)?;
+            
+            if self.db.compare_and_swap(&key, current, Some(value))?.is_ok() {
+                return Ok(recorded);
+            }
+        }
+    }
+    
+    // Apply a change to an inventory entry atomically, like update_release
+    pub fn update_inventory<F>(
+        &self,
+        client_id: &str,
+        environment: &Environment,
+        item: &str,
+        mut f: F,
+    ) -> Result<Option<InventoryEntry>, Box<dyn std::error::Error + Send + Sync>>
+    where
+        F: FnMut(&mut InventoryEntry),
+    {
+        let key = Self::inventory_key(client_id, environment, item);
+        loop {
+            let current = match self.db.get(&key)? {
+                Some(bytes) => bytes,
+                None => return Ok(None),
+            };
+            
+            let mut entry: InventoryEntry = Self::deserialize(&current)?;
+            f(&mut entry);
+            let value = Self::serialize(&entry)?;
+            
+            if self.db.compare_and_swap(&key, Some(current), Some(value))?.is_ok() {
+                return Ok(Some(entry));
+            }
+        }
+    }
+    
+    pub fn get_all_inventory(&self) -> Result<Vec<InventoryEntry>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut entries = Vec::new();
+        let prefix = INVENTORY_PREFIX.as_bytes();
+        
+        for item in self.db.scan_prefix(prefix) {
+            let (_, value) = item?;
+            let entry: InventoryEntry = Self::deserialize(&value)?;
+            entries.push(entry);
+        }
+        
+        Ok(entries)
+    }
+    
+    // Log methods
+    // Append a line to a deployment item's log, dropping its oldest lines once it holds
+    // more than max_lines
+    pub fn append_log_line(&self, release_id: &Uuid, line: &LogLine, max_lines: usize) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let prefix = log_prefix(release_id, &line.item_name);
+        let mut key = prefix.clone();
+        key.extend_from_slice(&line.run.to_be_bytes());
+        key.extend_from_slice(&self.db.generate_id()?.to_be_bytes());
+        self.logs.insert(key, Self::serialize(line)?)?;
+        
+        let count = self.update_log_count(&prefix, |count| count.stored += 1)?;
+        let excess = count.stored.saturating_sub(max_lines);
+        if excess > 0 {
+            let mut removed = 0;
+            for key in self.logs.scan_prefix(&prefix).keys().take(excess) {
+                // Another writer may have removed the same line already
+                if self.logs.remove(key?)?.is_some() {
+                    removed += 1;
+                }
+            }
+            self.update_log_count(&prefix, |count| {
+                count.stored = count.stored.saturating_sub(removed);
+                count.dropped += removed;
+            })?;
+        }
+        Ok(())
+    }
+    
+    fn update_log_count<F>(&self, prefix: &[u8], mut f: F) -> Result<LogCount, Box<dyn std::error::Error + Send + Sync>>
+    where
+        F: FnMut(&mut LogCount),
+    {
+        loop {
+            let current = self.log_counts.get(prefix)?;
+            let mut count: LogCount = match &current {
+                Some(bytes) => Self::deserialize(bytes)?,
+                None => LogCount::default(),
+            };
+            f(&mut count);
+            let value = Self::serialize(&count)?;
+            
+            if self.log_counts.compare_and_swap(prefix, current, Some(value))?.is_ok() {
+                return Ok(count);
+            }
+        }
+    }
+    
+    // A page of a deployment item's log, optionally of one run only. Returns the lines,
+    // how many lines match and how many were dropped by the log size cap.
+    pub fn get_log_lines(
+        &self,
+        release_id: &Uuid,
+        item_name: &str,
+        run: Option<u32>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<LogLine>, usize, usize), Box<dyn std::error::Error + Send + Sync>> {
+        let item_prefix = log_prefix(release_id, item_name);
+        let mut prefix = item_prefix.clone();
+        if let Some(run) = run {
+            prefix.extend_from_slice(&run.to_be_bytes());
+        }
+        
+        let total = self.logs.scan_prefix(&prefix).keys().count();
+        let mut lines = Vec::new();
+        for entry in self.logs.scan_prefix(&prefix).values().skip(offset).take(limit) {
+            lines.push(Self::deserialize(&entry?)?);
+        }
+        
+        let dropped = match self.log_counts.get(&item_prefix)? {
+            Some(bytes) => Self::deserialize::<LogCount>(&bytes)?.dropped,
+            None => 0,
+        };
+        Ok((lines, total, dropped))
+    }
+    
+    pub fn delete_logs(&self, release_id: &Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        for tree in [&self.logs, &self.log_counts] {
+            for key in tree.scan_prefix(release_id.as_bytes()).keys() {
+                tree.remove(key?)?;
+            }
+        }
+        Ok(())
+    }
+    
+    // Releases stored before logs got their own tree carry them on their items. Move
+    // them over once so the release records stay small. Returns how many releases
+    // were migrated.
+    pub fn migrate_release_logs(&self) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let mut migrated = 0;
+        
+        for entry in self.db.scan_prefix(RELEASE_PREFIX.as_bytes()) {
+            let (key, value) = entry?;
+            let mut release: serde_json::Value = Self::deserialize(&value)?;
+            let release_id = match release.get("id").and_then(|id| id.as_str()).and_then(|id| Uuid::parse_str(id).ok()) {
+                Some(id) => id,
+                None => continue,
+            };
+            let items = match release.get_mut("deployment_items").and_then(|items| items.as_array_mut()) {
+                Some(items) => items,
+                None => continue,
+            };
+            
+            let mut changed = false;
+            for item in items.iter_mut().filter_map(|item| item.as_object_mut()) {
+                let name = item.get("name").and_then(|name| name.as_str()).unwrap_or_default().to_string();
+                let logs = match item.remove("logs") {
+                    Some(logs) => logs,
+                    None => continue,
+                };
+                changed = true;
+                
+                // Those records kept plain strings, without a stream or a run
+                let lines = match logs {
+                    serde_json::Value::Array(lines) => lines,
+                    _ => Vec::new(),
+                };
+                for text in lines.into_iter().filter_map(|line| line.as_str().map(str::to_string)) {
+                    let line = LogLine {
+                        timestamp: Utc::now(),
+                        stream: LogStream::Stdout,
+                        item_name: name.clone(),
+                        line: text,
+                        run: 0,
+                        dry_run: false,
+                    };
+                    self.append_log_line(&release_id, &line, usize::MAX)?;
+                }
+            }
+            
+            if changed {
+                self.db.insert(key, Self::serialize(&release)?)?;
+                migrated += 1;
+            }
+        }
+        
+        Ok(migrated)
+    }
+    
     // Session methods
     pub fn save_session(&self, session_id: &str, user_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let key = format!("{}{}", SESSION_PREFIX, session_id);
@@ -193,14 +623,12 @@ impl SledStorage {
         Ok(0)
     }
     
-    // Method to get releases that need to be processed
+    // Method to get releases that need to be processed, now or once they are due
     pub fn get_releases_to_process(&self) -> Result<Vec<Release>, Box<dyn std::error::Error + Send + Sync>> {
-        let now = Utc::now();
         let mut to_process = Vec::new();
         
         for release in self.get_all_releases()? {
-            info!("-> DB RELEASE SCHEDULED AT: {}", release.scheduled_at);
-            if release.should_process() && release.scheduled_at <= now {
+            if release.should_process() {
                 to_process.push(release);
             }
         }
@@ -208,3 +636,101 @@ impl SledStorage {
         Ok(to_process)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage() -> SledStorage {
+        SledStorage::with_db(sled::Config::new().temporary(true).open().unwrap()).unwrap()
+    }
+
+    fn line(item_name: &str, run: u32, text: &str) -> LogLine {
+        LogLine {
+            timestamp: Utc::now(),
+            stream: LogStream::Stdout,
+            item_name: item_name.to_string(),
+            line: text.to_string(),
+            run,
+            dry_run: false,
+        }
+    }
+
+    fn texts(lines: &[LogLine]) -> Vec<&str> {
+        lines.iter().map(|line| line.line.as_str()).collect()
+    }
+
+    #[test]
+    fn the_cap_drops_the_oldest_lines() {
+        let db = storage();
+        let release_id = Uuid::new_v4();
+        for text in ["one", "two", "three", "four", "five"] {
+            db.append_log_line(&release_id, &line("data", 1, text), 3).unwrap();
+        }
+
+        let (lines, total, dropped) = db.get_log_lines(&release_id, "data", None, 0, 100).unwrap();
+        assert_eq!(texts(&lines), vec!["three", "four", "five"]);
+        assert_eq!(total, 3);
+        assert_eq!(dropped, 2);
+    }
+
+    #[test]
+    fn lines_are_paged_and_filtered_by_run() {
+        let db = storage();
+        let release_id = Uuid::new_v4();
+        for (run, text) in [(1, "first"), (1, "second"), (2, "retry"), (1, "third"), (2, "done")] {
+            db.append_log_line(&release_id, &line("data", run, text), 100).unwrap();
+        }
+        // Items whose names share a prefix keep their logs apart
+        db.append_log_line(&release_id, &line("data-v2", 1, "other item"), 100).unwrap();
+
+        let (lines, total, dropped) = db.get_log_lines(&release_id, "data", None, 0, 100).unwrap();
+        assert_eq!(texts(&lines), vec!["first", "second", "third", "retry", "done"]);
+        assert_eq!((total, dropped), (5, 0));
+
+        let (lines, total, _) = db.get_log_lines(&release_id, "data", None, 1, 2).unwrap();
+        assert_eq!(texts(&lines), vec!["second", "third"]);
+        assert_eq!(total, 5);
+
+        let (lines, total, _) = db.get_log_lines(&release_id, "data", Some(2), 0, 100).unwrap();
+        assert_eq!(texts(&lines), vec!["retry", "done"]);
+        assert_eq!(total, 2);
+
+        let (lines, total, _) = db.get_log_lines(&release_id, "data", Some(1), 2, 100).unwrap();
+        assert_eq!(texts(&lines), vec!["third"]);
+        assert_eq!(total, 3);
+    }
+
+    #[test]
+    fn release_logs_move_to_the_log_tree() {
+        let db = storage();
+        let release = Release::new(
+            "Release 1.5".to_string(),
+            "client".to_string(),
+            Environment::Development,
+            Environment::Staging,
+            vec!["data".to_string(), "app".to_string()],
+            Utc::now(),
+            "author".to_string(),
+            false,
+        );
+
+        // A release as stored before logs had a tree of their own
+        let mut stored = serde_json::to_value(&release).unwrap();
+        stored["deployment_items"][0]["logs"] = serde_json::json!(["Creating backup", "Backup done"]);
+        stored["deployment_items"][1]["logs"] = serde_json::json!([]);
+        db.db.insert(format!("{}{}", RELEASE_PREFIX, release.id), serde_json::to_vec(&stored).unwrap()).unwrap();
+
+        assert_eq!(db.migrate_release_logs().unwrap(), 1);
+        assert_eq!(db.migrate_release_logs().unwrap(), 0);
+
+        let (lines, total, _) = db.get_log_lines(&release.id, "data", None, 0, 100).unwrap();
+        assert_eq!(texts(&lines), vec!["Creating backup", "Backup done"]);
+        assert_eq!(total, 2);
+        assert!(lines.iter().all(|line| line.item_name == "data" && line.run == 0));
+
+        let raw: serde_json::Value = serde_json::from_slice(&db.db.get(format!("{}{}", RELEASE_PREFIX, release.id)).unwrap().unwrap()).unwrap();
+        assert!(raw["deployment_items"][0].get("logs").is_none());
+        assert_eq!(db.get_release(&release.id).unwrap(), Some(release));
+    }
+}
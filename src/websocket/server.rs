@@ -3,7 +3,8 @@ use serde::{Serialize, Deserialize};
 use std::time::{Duration, Instant};
 use log::{info, warn, error, debug};
 use crate::storage::SledStorage;
-use chrono::Utc;
+use crate::models::{Approval, ApprovalRequest, DeploymentItem, DryRun, HealthCheckResult, InventoryEntry, ItemEvent, RecurringRelease, Release, ReleaseStatus, ReleaseTrain};
+use chrono::{DateTime, Utc};
 use actix::prelude::*;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -46,6 +47,56 @@ pub enum WsMessage {
         message: String,
         timestamp: String,
     },
+    ItemUpdate {
+        release_id: String,
+        item_name: String,
+        status: ReleaseStatus,
+        attempt: u32,
+        max_attempts: u32,
+        error: Option<String>,
+        #[serde(default)]
+        progress: f32,
+        #[serde(default)]
+        started_at: Option<DateTime<Utc>>,
+        #[serde(default)]
+        finished_at: Option<DateTime<Utc>>,
+        #[serde(default)]
+        duration_ms: Option<u64>,
+        #[serde(default)]
+        exit_code: Option<i32>,
+    },
+    QueueUpdate {
+        release_id: String,
+        position: Option<usize>, // None once the release has left the queue
+    },
+    ItemEvent {
+        release_id: String,
+        item_name: String,
+        event: ItemEvent,
+    },
+    DryRunUpdate {
+        release_id: String,
+        dry_run: DryRun,
+    },
+    ApprovalUpdate {
+        release_id: String,
+        status: ReleaseStatus,
+        approval_request: Option<ApprovalRequest>,
+        approvals: Vec<Approval>,
+    },
+    HealthCheckUpdate {
+        release_id: String,
+        health_checks: Vec<HealthCheckResult>,
+    },
+    TrainUpdate {
+        train: ReleaseTrain,
+    },
+    RecurringUpdate {
+        recurring: RecurringRelease,
+    },
+    InventoryUpdate {
+        entry: InventoryEntry,
+    },
 }
 
 // Message struct for actor communication
@@ -262,6 +313,42 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WebSocketSession
                         // Clients shouldn't send app logs
                         warn!("Client {} tried to send an app log", self.id);
                     }
+                    Ok(WsMessage::ItemUpdate { .. }) => {
+                        // Clients shouldn't send item updates
+                        warn!("Client {} tried to send an item update", self.id);
+                    }
+                    Ok(WsMessage::QueueUpdate { .. }) => {
+                        // Clients shouldn't send queue updates
+                        warn!("Client {} tried to send a queue update", self.id);
+                    }
+                    Ok(WsMessage::ItemEvent { .. }) => {
+                        // Clients shouldn't send item events
+                        warn!("Client {} tried to send an item event", self.id);
+                    }
+                    Ok(WsMessage::DryRunUpdate { .. }) => {
+                        // Clients shouldn't send dry run updates
+                        warn!("Client {} tried to send a dry run update", self.id);
+                    }
+                    Ok(WsMessage::ApprovalUpdate { .. }) => {
+                        // Approvals go through the REST API so they're checked and recorded
+                        warn!("Client {} tried to send an approval update", self.id);
+                    }
+                    Ok(WsMessage::HealthCheckUpdate { .. }) => {
+                        // Clients shouldn't send health check results
+                        warn!("Client {} tried to send a health check update", self.id);
+                    }
+                    Ok(WsMessage::TrainUpdate { .. }) => {
+                        // Trains are changed through the REST API
+                        warn!("Client {} tried to send a train update", self.id);
+                    }
+                    Ok(WsMessage::RecurringUpdate { .. }) => {
+                        // Recurring releases are changed through the REST API
+                        warn!("Client {} tried to send a recurring release update", self.id);
+                    }
+                    Ok(WsMessage::InventoryUpdate { .. }) => {
+                        // The inventory is kept from deployments and the REST API
+                        warn!("Client {} tried to send an inventory update", self.id);
+                    }
                     Err(e) => {
                         // Invalid message format
                         error!("Invalid message format from {}: {}", self.id, e);
@@ -348,6 +435,178 @@ pub fn broadcast_release_update(release_id: String, status: String, progress: f3
     }
 }
 
+// Function to broadcast the state of a single deployment item to all connected clients
+pub fn broadcast_item_update(release_id: String, item: &DeploymentItem) {
+    let update = WsMessage::ItemUpdate {
+        release_id,
+        item_name: item.name.clone(),
+        status: item.status.clone(),
+        attempt: item.attempt,
+        max_attempts: item.max_attempts,
+        error: item.error.clone(),
+        progress: item.progress,
+        started_at: item.started_at,
+        finished_at: item.finished_at,
+        duration_ms: item.duration_ms,
+        exit_code: item.exit_code,
+    };
+    
+    if let Ok(json) = serde_json::to_string(&update) {
+        if let Ok(sessions) = ACTIVE_SESSIONS.lock() {
+            for (_, addr) in sessions.iter() {
+                addr.do_send(BroadcastMessage {
+                    content: json.clone(),
+                    sender_id: "system".to_string(),
+                });
+            }
+            
+            debug!("Item update for {} broadcast to {} clients", item.name, sessions.len());
+        }
+    } else {
+        error!("Failed to serialize item update");
+    }
+}
+
+// Function to broadcast an event reported by a deployment item's script
+pub fn broadcast_item_event(release_id: String, item_name: &str, event: &ItemEvent) {
+    let message = WsMessage::ItemEvent {
+        release_id,
+        item_name: item_name.to_string(),
+        event: event.clone(),
+    };
+    
+    if let Ok(json) = serde_json::to_string(&message) {
+        if let Ok(sessions) = ACTIVE_SESSIONS.lock() {
+            for (_, addr) in sessions.iter() {
+                addr.do_send(BroadcastMessage {
+                    content: json.clone(),
+                    sender_id: "system".to_string(),
+                });
+            }
+            
+            debug!("Item event for {} broadcast to {} clients", item_name, sessions.len());
+        }
+    } else {
+        error!("Failed to serialize item event");
+    }
+}
+
+// Function to broadcast the state of a release's dry run to all connected clients
+pub fn broadcast_dry_run_update(release_id: String, dry_run: &DryRun) {
+    let message = WsMessage::DryRunUpdate {
+        release_id,
+        dry_run: dry_run.clone(),
+    };
+    
+    if let Ok(json) = serde_json::to_string(&message) {
+        if let Ok(sessions) = ACTIVE_SESSIONS.lock() {
+            for (_, addr) in sessions.iter() {
+                addr.do_send(BroadcastMessage {
+                    content: json.clone(),
+                    sender_id: "system".to_string(),
+                });
+            }
+            
+            debug!("Dry run {} broadcast to {} clients", dry_run.id, sessions.len());
+        }
+    } else {
+        error!("Failed to serialize dry run update");
+    }
+}
+
+// Function to broadcast a release's pending approval and the approvals it got to all connected clients
+pub fn broadcast_approval_update(release: &Release) {
+    let message = WsMessage::ApprovalUpdate {
+        release_id: release.id.to_string(),
+        status: release.status.clone(),
+        approval_request: release.approval_request.clone(),
+        approvals: release.approvals.clone(),
+    };
+    
+    if let Ok(json) = serde_json::to_string(&message) {
+        if let Ok(sessions) = ACTIVE_SESSIONS.lock() {
+            for (_, addr) in sessions.iter() {
+                addr.do_send(BroadcastMessage {
+                    content: json.clone(),
+                    sender_id: "system".to_string(),
+                });
+            }
+            
+            debug!("Approvals of release {} broadcast to {} clients", release.id, sessions.len());
+        }
+    } else {
+        error!("Failed to serialize approval update");
+    }
+}
+
+// Function to broadcast the health checks of a release's latest deployment to all connected clients
+pub fn broadcast_health_check_update(release_id: String, health_checks: &[HealthCheckResult]) {
+    let message = WsMessage::HealthCheckUpdate {
+        release_id: release_id.clone(),
+        health_checks: health_checks.to_vec(),
+    };
+    
+    if let Ok(json) = serde_json::to_string(&message) {
+        if let Ok(sessions) = ACTIVE_SESSIONS.lock() {
+            for (_, addr) in sessions.iter() {
+                addr.do_send(BroadcastMessage {
+                    content: json.clone(),
+                    sender_id: "system".to_string(),
+                });
+            }
+            
+            debug!("Health checks of release {} broadcast to {} clients", release_id, sessions.len());
+        }
+    } else {
+        error!("Failed to serialize health check update");
+    }
+}
+
+// Function to broadcast a release train's failures and state to all connected clients
+pub fn broadcast_train_update(train: &ReleaseTrain) {
+    let message = WsMessage::TrainUpdate {
+        train: train.clone(),
+    };
+    
+    if let Ok(json) = serde_json::to_string(&message) {
+        if let Ok(sessions) = ACTIVE_SESSIONS.lock() {
+            for (_, addr) in sessions.iter() {
+                addr.do_send(BroadcastMessage {
+                    content: json.clone(),
+                    sender_id: "system".to_string(),
+                });
+            }
+            
+            debug!("Train {} broadcast to {} clients", train.id, sessions.len());
+        }
+    } else {
+        error!("Failed to serialize train update");
+    }
+}
+
+// Function to broadcast a release's position in the deployment queue to all connected clients
+pub fn broadcast_queue_update(release_id: String, position: Option<usize>) {
+    let update = WsMessage::QueueUpdate {
+        release_id: release_id.clone(),
+        position,
+    };
+    
+    if let Ok(json) = serde_json::to_string(&update) {
+        if let Ok(sessions) = ACTIVE_SESSIONS.lock() {
+            for (_, addr) in sessions.iter() {
+                addr.do_send(BroadcastMessage {
+                    content: json.clone(),
+                    sender_id: "system".to_string(),
+                });
+            }
+            
+            debug!("Queue update for {} broadcast to {} clients", release_id, sessions.len());
+        }
+    } else {
+        error!("Failed to serialize queue update");
+    }
+}
+
 // Function to broadcast application logs to all connected clients
 pub fn broadcast_app_log(level: &str, message: &str) {
     let timestamp = Utc::now().to_rfc3339();
@@ -389,3 +648,47 @@ pub fn broadcast_app_log(level: &str, message: &str) {
         error!("Failed to serialize app log");
     }
 }
+
+// Function to broadcast a recurring release and its latest occurrences to all connected clients
+pub fn broadcast_recurring_update(recurring: &RecurringRelease) {
+    let message = WsMessage::RecurringUpdate {
+        recurring: recurring.clone(),
+    };
+    
+    if let Ok(json) = serde_json::to_string(&message) {
+        if let Ok(sessions) = ACTIVE_SESSIONS.lock() {
+            for (_, addr) in sessions.iter() {
+                addr.do_send(BroadcastMessage {
+                    content: json.clone(),
+                    sender_id: "system".to_string(),
+                });
+            }
+            
+            debug!("Recurring release {} broadcast to {} clients", recurring.id, sessions.len());
+        }
+    } else {
+        error!("Failed to serialize recurring release update");
+    }
+}
+
+// Function to broadcast a change of what is deployed where to all connected clients
+pub fn broadcast_inventory_update(entry: &InventoryEntry) {
+    let message = WsMessage::InventoryUpdate {
+        entry: entry.clone(),
+    };
+    
+    if let Ok(json) = serde_json::to_string(&message) {
+        if let Ok(sessions) = ACTIVE_SESSIONS.lock() {
+            for (_, addr) in sessions.iter() {
+                addr.do_send(BroadcastMessage {
+                    content: json.clone(),
+                    sender_id: "system".to_string(),
+                });
+            }
+            
+            debug!("Inventory of {} in {:?} broadcast to {} clients", entry.item, entry.environment, sessions.len());
+        }
+    } else {
+        error!("Failed to serialize inventory update");
+    }
+}